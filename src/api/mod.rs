@@ -0,0 +1,292 @@
+//! Local HTTP API to inspect and steer a running bot.
+//!
+//! Every request needs `Authorization: Bearer <api.token>`. Read endpoints:
+//! - `GET /status`: uptime, exchange connections, controls and stats;
+//! - `GET /spreads`: best bid and ask per pair across exchanges;
+//! - `GET /opportunities`: stored opportunities, newest first, one page at a
+//!   time (`limit`, `cursor`) and filtered by `pair`, `route` (`buy->sell`),
+//!   `type`, `min_profit_percent` and an RFC 3339 `from`/`to` range;
+//! - `GET /opportunities/stats?group_by=pair|route|hour`: counts and profit
+//!   of the opportunities matching the same filters;
+//! - `GET /balances`: balances of the last sync.
+//!
+//! Control endpoints (POST): `/pause`, `/resume`, `/kill`,
+//! `/pairs/{base}/{quote}/{enable|disable}` and `/exchanges/{name}/{enable|disable}`.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::bot::{Bot, BotError, ControlError};
+use crate::secrets::Secret;
+use crate::storage::{GroupBy, OpportunityQuery, StorageError};
+
+/// Opportunities returned when no limit is given.
+const DEFAULT_OPPORTUNITY_LIMIT: u32 = 20;
+
+/// Most opportunities returned by one request.
+const MAX_OPPORTUNITY_LIMIT: u32 = 500;
+
+/// Starts the API if `api` is enabled. The task runs until aborted.
+pub async fn spawn(bot: Arc<Bot>) -> Result<Option<JoinHandle<()>>, BotError> {
+    let Some(cfg) = bot.config().await.api.clone() else {
+        return Ok(None);
+    };
+    if !cfg.enabled {
+        return Ok(None);
+    }
+
+    let listener = TcpListener::bind(cfg.listen)
+        .await
+        .map_err(|source| BotError::Api {
+            address: cfg.listen,
+            source,
+        })?;
+    info!(address = %cfg.listen, "Serving control API");
+
+    let app = router(bot, cfg.token);
+    Ok(Some(tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            warn!(error = %e, "Control API stopped");
+        }
+    })))
+}
+
+/// Builds the routes, all behind the bearer token check.
+fn router(bot: Arc<Bot>, token: Secret) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/spreads", get(spreads))
+        .route("/opportunities", get(opportunities))
+        .route("/opportunities/stats", get(opportunity_stats))
+        .route("/balances", get(balances))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/kill", post(kill))
+        .route("/pairs/{base}/{quote}/{action}", post(toggle_pair))
+        .route("/exchanges/{name}/{action}", post(toggle_exchange))
+        .layer(middleware::from_fn_with_state(Arc::new(token), authorize))
+        .with_state(bot)
+}
+
+/// Rejects requests without the configured bearer token.
+async fn authorize(State(token): State<Arc<Secret>>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match presented {
+        Some(presented) if !token.is_empty() && constant_time_eq(presented, token.expose()) => {
+            next.run(request).await
+        }
+        _ => error(StatusCode::UNAUTHORIZED, "missing or invalid bearer token"),
+    }
+}
+
+async fn status(State(bot): State<Arc<Bot>>) -> Response {
+    Json(bot.status().await).into_response()
+}
+
+async fn spreads(State(bot): State<Arc<Bot>>) -> Response {
+    Json(bot.best_spreads().await).into_response()
+}
+
+#[derive(Deserialize)]
+struct OpportunitiesParams {
+    limit: Option<u32>,
+    cursor: Option<String>,
+    pair: Option<String>,
+    route: Option<String>,
+    #[serde(rename = "type")]
+    opportunity_type: Option<String>,
+    min_profit_percent: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    group_by: Option<String>,
+}
+
+impl OpportunitiesParams {
+    /// Builds the storage query of the filters; the error names the bad parameter.
+    fn to_query(&self) -> Result<OpportunityQuery, String> {
+        let mut query = OpportunityQuery::new()
+            .with_time_range(parse_time(&self.from)?, parse_time(&self.to)?);
+        if let Some(pair) = &self.pair {
+            query = query.with_pair(pair.to_uppercase());
+        }
+        if let Some(route) = &self.route {
+            let (buy, sell) = route
+                .split_once("->")
+                .ok_or_else(|| format!("invalid route {}: expected buy->sell", route))?;
+            query = query.with_route(buy, sell);
+        }
+        if let Some(opportunity_type) = &self.opportunity_type {
+            query = query.with_type(opportunity_type.parse()?);
+        }
+        if let Some(min) = &self.min_profit_percent {
+            let min: Decimal = min
+                .parse()
+                .map_err(|_| format!("invalid min_profit_percent {}", min))?;
+            query = query.with_min_profit_percent(min);
+        }
+        Ok(query)
+    }
+}
+
+fn parse_time(value: &Option<String>) -> Result<Option<DateTime<Utc>>, String> {
+    value
+        .as_deref()
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| format!("invalid time {}: expected RFC 3339", value))
+        })
+        .transpose()
+}
+
+async fn opportunities(
+    State(bot): State<Arc<Bot>>,
+    Query(params): Query<OpportunitiesParams>,
+) -> Response {
+    let mut query = match params.to_query() {
+        Ok(query) => query,
+        Err(message) => return error(StatusCode::BAD_REQUEST, &message),
+    };
+    query = query.with_limit(
+        params
+            .limit
+            .unwrap_or(DEFAULT_OPPORTUNITY_LIMIT)
+            .clamp(1, MAX_OPPORTUNITY_LIMIT),
+    );
+    if let Some(cursor) = params.cursor {
+        query = query.with_cursor(cursor);
+    }
+    match bot.opportunities(&query).await {
+        Ok(page) => Json(page).into_response(),
+        Err(e) => control_error(e),
+    }
+}
+
+async fn opportunity_stats(
+    State(bot): State<Arc<Bot>>,
+    Query(params): Query<OpportunitiesParams>,
+) -> Response {
+    let query = match params.to_query() {
+        Ok(query) => query,
+        Err(message) => return error(StatusCode::BAD_REQUEST, &message),
+    };
+    let group_by: GroupBy = match params.group_by.as_deref().unwrap_or("pair").parse() {
+        Ok(group_by) => group_by,
+        Err(message) => return error(StatusCode::BAD_REQUEST, &message),
+    };
+    match bot.opportunity_stats(&query, group_by).await {
+        Ok(stats) => Json(stats).into_response(),
+        Err(e) => control_error(e),
+    }
+}
+
+async fn balances(State(bot): State<Arc<Bot>>) -> Response {
+    Json(bot.balances().await).into_response()
+}
+
+async fn pause(State(bot): State<Arc<Bot>>) -> Response {
+    bot.pause().await;
+    Json(bot.status().await).into_response()
+}
+
+async fn resume(State(bot): State<Arc<Bot>>) -> Response {
+    match bot.resume().await {
+        Ok(()) => Json(bot.status().await).into_response(),
+        Err(e) => control_error(e),
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct KillRequest {
+    reason: Option<String>,
+}
+
+async fn kill(State(bot): State<Arc<Bot>>, request: Option<Json<KillRequest>>) -> Response {
+    let reason = request
+        .and_then(|Json(request)| request.reason)
+        .unwrap_or_else(|| "triggered through the control API".to_string());
+    let cancelled = bot.trigger_kill_switch(reason).await;
+    Json(json!({ "orders_cancelled": cancelled, "status": bot.status().await })).into_response()
+}
+
+async fn toggle_pair(
+    State(bot): State<Arc<Bot>>,
+    Path((base, quote, action)): Path<(String, String, String)>,
+) -> Response {
+    let Some(enabled) = parse_action(&action) else {
+        return error(StatusCode::NOT_FOUND, "expected enable or disable");
+    };
+    let pair = format!("{}/{}", base, quote).to_uppercase();
+    match bot.set_pair_enabled(&pair, enabled).await {
+        Ok(()) => Json(bot.status().await).into_response(),
+        Err(e) => control_error(e),
+    }
+}
+
+async fn toggle_exchange(
+    State(bot): State<Arc<Bot>>,
+    Path((name, action)): Path<(String, String)>,
+) -> Response {
+    let Some(enabled) = parse_action(&action) else {
+        return error(StatusCode::NOT_FOUND, "expected enable or disable");
+    };
+    match bot.set_exchange_enabled(&name, enabled).await {
+        Ok(()) => Json(bot.status().await).into_response(),
+        Err(e) => control_error(e),
+    }
+}
+
+fn parse_action(action: &str) -> Option<bool> {
+    match action {
+        "enable" => Some(true),
+        "disable" => Some(false),
+        _ => None,
+    }
+}
+
+fn control_error(e: ControlError) -> Response {
+    let status = match e {
+        ControlError::UnknownPair(_) | ControlError::UnknownExchange(_) => StatusCode::NOT_FOUND,
+        ControlError::InvalidThreshold(_)
+        | ControlError::Storage(StorageError::InvalidData(_)) => StatusCode::BAD_REQUEST,
+        ControlError::Killed(_) => StatusCode::CONFLICT,
+        ControlError::StorageDisabled => StatusCode::SERVICE_UNAVAILABLE,
+        ControlError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    error(status, &e.to_string())
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+/// Compares without an early exit, so timing does not reveal the matching prefix.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a
+            .bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests;
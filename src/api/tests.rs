@@ -0,0 +1,217 @@
+//! Tests for the control API, served on an ephemeral local port.
+
+use super::*;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const TOKEN: &str = "test-token";
+
+/// Serves the API of a bot that monitors BTC/USDT and ETH/USDT on poloniex.
+async fn serve_test_bot() -> (String, Arc<Bot>, NamedTempFile) {
+    serve_bot_with("").await
+}
+
+/// Serves the API of the test bot with extra config appended.
+async fn serve_bot_with(extra: &str) -> (String, Arc<Bot>, NamedTempFile) {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(
+        file,
+        r#"
+app:
+  name: apitest
+  env: development
+
+exchanges:
+  poloniex:
+    enabled: true
+    fee_taker: "0.001"
+
+pairs:
+  - BTC/USDT
+  - ETH/USDT
+{}
+"#,
+        extra
+    )
+    .unwrap();
+
+    let bot = Arc::new(
+        Bot::from_config_path(file.path().to_str().unwrap())
+            .await
+            .unwrap(),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let app = router(Arc::clone(&bot), Secret::new(TOKEN));
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (base, bot, file)
+}
+
+#[tokio::test]
+async fn test_requests_need_the_bearer_token() {
+    let (base, _bot, _file) = serve_test_bot().await;
+    let client = reqwest::Client::new();
+
+    let missing = client.get(format!("{}/status", base)).send().await.unwrap();
+    assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+
+    let wrong = client
+        .get(format!("{}/status", base))
+        .bearer_auth("wrong-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+
+    let status: serde_json::Value = client
+        .get(format!("{}/status", base))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status["paused"], false);
+    assert_eq!(status["exchanges"]["poloniex"], false);
+    assert_eq!(status["stats"]["detection_cycles"], 0);
+}
+
+#[tokio::test]
+async fn test_controls_pause_toggle_and_kill() {
+    let (base, bot, _file) = serve_test_bot().await;
+    let client = reqwest::Client::new();
+    let post = |path: &str| client.post(format!("{}{}", base, path)).bearer_auth(TOKEN).send();
+
+    assert_eq!(post("/pause").await.unwrap().status(), StatusCode::OK);
+    assert!(bot.status().await.paused);
+    assert_eq!(post("/resume").await.unwrap().status(), StatusCode::OK);
+    assert!(!bot.status().await.paused);
+
+    assert_eq!(post("/pairs/btc/usdt/disable").await.unwrap().status(), StatusCode::OK);
+    assert_eq!(post("/exchanges/poloniex/disable").await.unwrap().status(), StatusCode::OK);
+    let status = bot.status().await;
+    assert_eq!(status.disabled_pairs, vec!["BTC/USDT"]);
+    assert_eq!(status.disabled_exchanges, vec!["poloniex"]);
+
+    assert_eq!(post("/pairs/XRP/USDT/disable").await.unwrap().status(), StatusCode::NOT_FOUND);
+    assert_eq!(post("/exchanges/kraken/enable").await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    // Without storage there is no opportunity history
+    let history = client
+        .get(format!("{}/opportunities?limit=5", base))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(history.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let killed: serde_json::Value = client
+        .post(format!("{}/kill", base))
+        .bearer_auth(TOKEN)
+        .json(&json!({ "reason": "drill" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(killed["orders_cancelled"], true);
+    assert_eq!(killed["status"]["kill_switch"], "drill");
+
+    // The kill switch cannot be resumed
+    assert_eq!(post("/resume").await.unwrap().status(), StatusCode::CONFLICT);
+    assert!(bot.status().await.paused);
+}
+
+#[tokio::test]
+async fn test_opportunities_filter_page_and_aggregate() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("api.db");
+    let (base, _bot, _file) = serve_bot_with(&format!(
+        "storage:\n  enabled: true\n  path: \"{}\"",
+        path.display()
+    ))
+    .await;
+
+    // Seed through a second connection to the same database
+    let seed = crate::storage::connect(&crate::config::StorageConfig {
+        enabled: true,
+        backend: Default::default(),
+        path: Some(path.display().to_string()),
+        url: None,
+        max_connections: None,
+        retention: None,
+    })
+    .await
+    .unwrap();
+    crate::storage::conformance::save_history(
+        seed.as_ref(),
+        &[
+            ("a", "BTC/USDT", ("poloniex", "gate"), 1),
+            ("b", "BTC/USDT", ("gate", "poloniex"), 3),
+            ("c", "ETH/USDT", ("poloniex", "gate"), 2),
+            ("d", "BTC/USDT", ("poloniex", "gate"), 5),
+        ],
+    )
+    .await;
+
+    let client = reqwest::Client::new();
+    let get = |path: String| client.get(format!("{}{}", base, path)).bearer_auth(TOKEN).send();
+    let ids = |page: &serde_json::Value| -> Vec<String> {
+        page["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|o| o["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let first: serde_json::Value = get("/opportunities?pair=btc/usdt&limit=2".to_string())
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(ids(&first), ["d", "b"]);
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let second: serde_json::Value = get(format!("/opportunities?pair=BTC/USDT&limit=2&cursor={}", cursor))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(ids(&second), ["a"]);
+    assert!(second["next_cursor"].is_null());
+
+    let routed: serde_json::Value =
+        get("/opportunities?route=poloniex->gate&min_profit_percent=0.002&type=cross_exchange".to_string())
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+    assert_eq!(ids(&routed), ["d", "c"]);
+
+    let stats: serde_json::Value = get("/opportunities/stats?group_by=route".to_string())
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(stats[0]["key"], "gate->poloniex");
+    assert_eq!(stats[0]["count"], 1);
+    assert_eq!(stats[1]["key"], "poloniex->gate");
+    assert_eq!(stats[1]["count"], 3);
+
+    for bad in [
+        "/opportunities?route=poloniex",
+        "/opportunities?type=spot",
+        "/opportunities?from=yesterday",
+        "/opportunities?cursor=garbage",
+        "/opportunities/stats?group_by=exchange",
+    ] {
+        assert_eq!(get(bad.to_string()).await.unwrap().status(), StatusCode::BAD_REQUEST, "{}", bad);
+    }
+}
@@ -0,0 +1,147 @@
+//! Telegram commands answered by a running bot.
+//!
+//! Each command maps onto the runtime controls also used by the control API;
+//! the notification module parses the messages and formats the replies.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use tracing::{info, warn};
+
+use super::Bot;
+use super::control::ControlError;
+use crate::config::parse_percent;
+use crate::notification::{
+    BalancesData, Command, CommandHandler, OverviewData, PairData, Reply, StatusData,
+};
+
+impl Bot {
+    /// Starts answering Telegram commands if `notification.telegram.commands`
+    /// is on. The listener stops when the notifier is closed.
+    pub async fn listen_for_commands(self: &Arc<Self>) {
+        let Some(ref notifier) = self.notifier else {
+            return;
+        };
+        let chats = match self
+            .config()
+            .await
+            .notification
+            .as_ref()
+            .and_then(|n| n.telegram.as_ref())
+        {
+            Some(telegram) if telegram.commands => telegram.authorised_chat_ids(),
+            _ => return,
+        };
+        if chats.is_empty() {
+            warn!("Telegram commands enabled but no chat is authorised");
+            return;
+        }
+
+        info!(chats = chats.len(), "Listening for Telegram commands");
+        notifier
+            .listen_commands(Arc::clone(self) as Arc<dyn CommandHandler>, chats)
+            .await;
+    }
+
+    async fn status_reply(&self) -> Reply {
+        let status = self.status().await;
+        Reply::Status(StatusData {
+            version: status.version,
+            running: status.running,
+            paused: status.paused,
+            kill_switch: status.kill_switch,
+            dry_run: status.dry_run,
+            uptime: self.uptime().await,
+            exchanges: status.exchanges.into_iter().collect(),
+            disabled_pairs: status.disabled_pairs,
+            disabled_exchanges: status.disabled_exchanges,
+        })
+    }
+
+    async fn stats_reply(&self) -> Reply {
+        let stats = self.stats().await;
+        Reply::Stats(OverviewData {
+            uptime: self.uptime().await,
+            detection_cycles: stats.detection_cycles,
+            opportunities_detected: stats.opportunities_detected,
+            opportunities_executed: stats.opportunities_executed,
+            successful_trades: stats.successful_trades,
+            failed_trades: stats.failed_trades,
+            total_profit: stats.total_profit,
+            dry_run: self.dry_run,
+        })
+    }
+
+    async fn balances_reply(&self) -> Reply {
+        let exchanges = self
+            .balances()
+            .await
+            .into_iter()
+            .map(|(exchange, balances)| {
+                let mut assets: Vec<(String, f64)> = balances
+                    .into_iter()
+                    .map(|(asset, amount)| (asset, amount.to_f64().unwrap_or_default()))
+                    .collect();
+                assets.sort_by(|a, b| a.0.cmp(&b.0));
+                (exchange, assets)
+            })
+            .collect();
+        Reply::Balances(BalancesData { exchanges })
+    }
+
+    async fn pairs_reply(&self) -> Reply {
+        let spreads = self.best_spreads().await;
+        let controls = self.controls.read().await.clone();
+        let percent = |fraction: Decimal| (fraction * Decimal::ONE_HUNDRED).to_f64();
+
+        Reply::Pairs(
+            spreads
+                .into_iter()
+                .map(|spread| PairData {
+                    enabled: controls.pair_enabled(&spread.pair),
+                    threshold_percent: controls.threshold(&spread.pair).and_then(percent),
+                    spread_percent: spread.spread_percent.and_then(|s| s.to_f64()),
+                    buy_exchange: spread.best_ask.map(|(exchange, _)| exchange),
+                    sell_exchange: spread.best_bid.map(|(exchange, _)| exchange),
+                    pair: spread.pair,
+                })
+                .collect(),
+        )
+    }
+
+    async fn threshold_reply(&self, pair: &str, value: &str) -> Result<Reply, ControlError> {
+        let threshold =
+            parse_percent(value).map_err(|_| ControlError::InvalidThreshold(value.to_string()))?;
+        self.set_pair_threshold(pair, threshold).await?;
+        Ok(Reply::ThresholdSet {
+            pair: pair.to_string(),
+            percent: (threshold * Decimal::ONE_HUNDRED).to_f64().unwrap_or_default(),
+        })
+    }
+}
+
+#[async_trait]
+impl CommandHandler for Bot {
+    async fn handle(&self, command: Command, chat_id: &str) -> Reply {
+        let result = match command {
+            Command::Status => Ok(self.status_reply().await),
+            Command::Stats => Ok(self.stats_reply().await),
+            Command::Balances => Ok(self.balances_reply().await),
+            Command::Pairs => Ok(self.pairs_reply().await),
+            Command::Pause => {
+                self.pause().await;
+                Ok(Reply::Paused)
+            }
+            Command::Resume => self.resume().await.map(|()| Reply::Resumed),
+            Command::Threshold { pair, value } => self.threshold_reply(&pair, &value).await,
+            Command::Kill => {
+                let reason = format!("/kill from Telegram chat {}", chat_id);
+                let orders_cancelled = self.trigger_kill_switch(reason).await;
+                Ok(Reply::Killed { orders_cancelled })
+            }
+        };
+        result.unwrap_or_else(|e| Reply::Refused(e.to_string()))
+    }
+}
@@ -0,0 +1,316 @@
+//! Runtime controls of a running bot and the status it reports.
+//!
+//! Used by the control API and the Telegram commands: pausing stops detection and execution while the
+//! streams, balance sync and housekeeping keep running; the kill switch also
+//! cancels open orders and cannot be resumed without a restart.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use super::{Bot, Stats};
+use crate::domain::Orderbook;
+use crate::notification::{ErrorData, Event};
+use crate::storage::{GroupBy, OpportunityPage, OpportunityQuery, OpportunityStats, StorageError};
+
+/// Control action errors.
+#[derive(Debug, Error)]
+pub enum ControlError {
+    #[error("pair {0} is not monitored")]
+    UnknownPair(String),
+    #[error("exchange {0} is not configured")]
+    UnknownExchange(String),
+    #[error("invalid threshold {0}: expected a non-negative fraction or percentage")]
+    InvalidThreshold(String),
+    #[error("kill switch triggered: {0}")]
+    Killed(String),
+    #[error("storage is not enabled")]
+    StorageDisabled,
+    #[error("storage error: {0}")]
+    Storage(#[from] StorageError),
+}
+
+/// Runtime switches set through the control API and Telegram commands.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Controls {
+    paused: bool,
+    /// Why the kill switch was triggered, if it was.
+    kill_switch: Option<String>,
+    disabled_pairs: BTreeSet<String>,
+    disabled_exchanges: BTreeSet<String>,
+    /// Minimum cross-exchange profit by pair, overriding the configured one.
+    thresholds: BTreeMap<String, Decimal>,
+}
+
+impl Controls {
+    /// Returns true if detection and execution are stopped.
+    pub(crate) fn is_halted(&self) -> bool {
+        self.paused || self.kill_switch.is_some()
+    }
+
+    pub(crate) fn pair_enabled(&self, pair: &str) -> bool {
+        !self.disabled_pairs.contains(pair)
+    }
+
+    pub(crate) fn exchange_enabled(&self, exchange: &str) -> bool {
+        !self.disabled_exchanges.contains(exchange)
+    }
+
+    pub(crate) fn threshold(&self, pair: &str) -> Option<Decimal> {
+        self.thresholds.get(pair).copied()
+    }
+}
+
+/// Snapshot of the bot state.
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub version: String,
+    pub running: bool,
+    pub dry_run: bool,
+    pub paused: bool,
+    /// Why the kill switch was triggered; None if it was not.
+    pub kill_switch: Option<String>,
+    pub uptime_secs: u64,
+    /// Connection state by exchange.
+    pub exchanges: BTreeMap<String, bool>,
+    pub disabled_pairs: Vec<String>,
+    pub disabled_exchanges: Vec<String>,
+    /// Minimum profit (fraction) by pair where it was overridden.
+    pub thresholds: BTreeMap<String, Decimal>,
+    pub stats: Stats,
+}
+
+/// Best prices of a pair across the enabled exchanges.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Spread {
+    pub pair: String,
+    /// Highest bid and the exchange quoting it.
+    pub best_bid: Option<(String, Decimal)>,
+    /// Lowest ask and the exchange quoting it.
+    pub best_ask: Option<(String, Decimal)>,
+    /// (best bid - best ask) / best ask * 100, before fees; positive when
+    /// buying on one exchange and selling on another pays.
+    pub spread_percent: Option<Decimal>,
+}
+
+impl Bot {
+    /// Returns the bot state.
+    pub async fn status(&self) -> Status {
+        let controls = self.controls.read().await;
+        Status {
+            version: self.version.clone(),
+            running: self.is_running().await,
+            dry_run: self.dry_run,
+            paused: controls.paused,
+            kill_switch: controls.kill_switch.clone(),
+            uptime_secs: self.uptime().await.as_secs(),
+            exchanges: self.exchange_manager.status().await.into_iter().collect(),
+            disabled_pairs: controls.disabled_pairs.iter().cloned().collect(),
+            disabled_exchanges: controls.disabled_exchanges.iter().cloned().collect(),
+            thresholds: controls.thresholds.clone(),
+            stats: self.stats().await,
+        }
+    }
+
+    /// Returns the best bid and ask of every monitored pair.
+    pub async fn best_spreads(&self) -> Vec<Spread> {
+        let cfg = self.config().await;
+        let controls = self.controls.read().await;
+        let books: Vec<Orderbook> = self
+            .orderbooks
+            .get_all()
+            .await
+            .into_iter()
+            .filter(|book| controls.exchange_enabled(&book.exchange))
+            .collect();
+        best_spreads(&cfg.pairs, &books)
+    }
+
+    /// Returns one page of stored opportunities matching the query.
+    pub async fn opportunities(&self, query: &OpportunityQuery) -> Result<OpportunityPage, ControlError> {
+        let storage = self.storage.as_ref().ok_or(ControlError::StorageDisabled)?;
+        Ok(storage.query(query).await?)
+    }
+
+    /// Returns aggregates of the stored opportunities matching the query.
+    pub async fn opportunity_stats(
+        &self,
+        query: &OpportunityQuery,
+        group_by: GroupBy,
+    ) -> Result<Vec<OpportunityStats>, ControlError> {
+        let storage = self.storage.as_ref().ok_or(ControlError::StorageDisabled)?;
+        Ok(storage.aggregate(query, group_by).await?)
+    }
+
+    /// Returns the balances of the last sync by exchange and asset.
+    pub async fn balances(&self) -> BTreeMap<String, HashMap<String, Decimal>> {
+        self.balances.read().await.clone()
+    }
+
+    /// Stops detection and execution until resumed.
+    pub async fn pause(&self) {
+        self.controls.write().await.paused = true;
+        info!("Trading paused");
+    }
+
+    /// Resumes detection and execution; refused once the kill switch was triggered.
+    pub async fn resume(&self) -> Result<(), ControlError> {
+        let mut controls = self.controls.write().await;
+        if let Some(ref reason) = controls.kill_switch {
+            return Err(ControlError::Killed(reason.clone()));
+        }
+        controls.paused = false;
+        info!("Trading resumed");
+        Ok(())
+    }
+
+    /// Includes or excludes a monitored pair from detection.
+    pub async fn set_pair_enabled(&self, pair: &str, enabled: bool) -> Result<(), ControlError> {
+        if !self.config().await.pairs.iter().any(|p| p == pair) {
+            return Err(ControlError::UnknownPair(pair.to_string()));
+        }
+
+        let mut controls = self.controls.write().await;
+        if enabled {
+            controls.disabled_pairs.remove(pair);
+        } else {
+            controls.disabled_pairs.insert(pair.to_string());
+        }
+        info!(pair = %pair, enabled = enabled, "Pair toggled");
+        Ok(())
+    }
+
+    /// Overrides the minimum cross-exchange profit of a pair, as a fraction
+    /// of the buy cost, until the bot restarts.
+    pub async fn set_pair_threshold(&self, pair: &str, threshold: Decimal) -> Result<(), ControlError> {
+        if !self.config().await.pairs.iter().any(|p| p == pair) {
+            return Err(ControlError::UnknownPair(pair.to_string()));
+        }
+        if threshold.is_sign_negative() {
+            return Err(ControlError::InvalidThreshold(threshold.to_string()));
+        }
+
+        self.controls
+            .write()
+            .await
+            .thresholds
+            .insert(pair.to_string(), threshold);
+        info!(pair = %pair, threshold = %threshold, "Pair threshold set");
+        Ok(())
+    }
+
+    /// Includes or excludes an exchange's orderbooks from detection.
+    pub async fn set_exchange_enabled(&self, exchange: &str, enabled: bool) -> Result<(), ControlError> {
+        if self.exchange_manager.get(exchange).await.is_none() {
+            return Err(ControlError::UnknownExchange(exchange.to_string()));
+        }
+
+        let mut controls = self.controls.write().await;
+        if enabled {
+            controls.disabled_exchanges.remove(exchange);
+        } else {
+            controls.disabled_exchanges.insert(exchange.to_string());
+        }
+        info!(exchange = %exchange, enabled = enabled, "Exchange toggled");
+        Ok(())
+    }
+
+    /// Halts trading for good: stops detection, cancels open orders and alerts.
+    /// Returns false if some orders could not be cancelled.
+    pub async fn trigger_kill_switch(&self, reason: impl Into<String>) -> bool {
+        let reason = reason.into();
+        {
+            let mut controls = self.controls.write().await;
+            controls.paused = true;
+            controls.kill_switch.get_or_insert_with(|| reason.clone());
+        }
+        warn!(reason = %reason, "Kill switch triggered");
+
+        let cancelled = self.cancel_open_orders().await;
+        self.send_notification(Event::error(ErrorData {
+            component: "kill switch".to_string(),
+            message: if cancelled {
+                format!("trading halted: {}", reason)
+            } else {
+                format!("trading halted: {}; some open orders could not be cancelled", reason)
+            },
+            error: None,
+        }))
+        .await;
+        cancelled
+    }
+}
+
+/// Finds the best bid and ask of each pair across the books.
+fn best_spreads(pairs: &[String], books: &[Orderbook]) -> Vec<Spread> {
+    pairs
+        .iter()
+        .map(|pair| {
+            let pair_books = books.iter().filter(|book| &book.pair == pair);
+            let best_bid = pair_books
+                .clone()
+                .filter_map(|book| Some((book.exchange.clone(), book.best_bid()?.price)))
+                .max_by(|a, b| a.1.cmp(&b.1));
+            let best_ask = pair_books
+                .filter_map(|book| Some((book.exchange.clone(), book.best_ask()?.price)))
+                .min_by(|a, b| a.1.cmp(&b.1));
+
+            let spread_percent = match (&best_bid, &best_ask) {
+                (Some((_, bid)), Some((_, ask))) if !ask.is_zero() => {
+                    Some(((bid - ask) / ask * Decimal::ONE_HUNDRED).round_dp(4))
+                }
+                _ => None,
+            };
+
+            Spread {
+                pair: pair.clone(),
+                best_bid,
+                best_ask,
+                spread_percent,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::PriceLevel;
+    use std::time::SystemTime;
+
+    fn book(exchange: &str, bid: i64, ask: i64) -> Orderbook {
+        let level = |price| PriceLevel {
+            price: Decimal::from(price),
+            quantity: Decimal::ONE,
+        };
+        Orderbook {
+            exchange: exchange.to_string(),
+            pair: "BTC/USDT".to_string(),
+            bids: vec![level(bid)],
+            asks: vec![level(ask)],
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_best_spreads_across_exchanges() {
+        let pairs = vec!["BTC/USDT".to_string(), "ETH/USDT".to_string()];
+        let books = vec![book("poloniex", 40100, 40200), book("gate", 39900, 40000)];
+
+        let spreads = best_spreads(&pairs, &books);
+        assert_eq!(
+            spreads[0],
+            Spread {
+                pair: "BTC/USDT".to_string(),
+                best_bid: Some(("poloniex".to_string(), Decimal::from(40100))),
+                best_ask: Some(("gate".to_string(), Decimal::from(40000))),
+                spread_percent: Some(Decimal::new(25, 2)),
+            }
+        );
+        assert_eq!(spreads[1].best_bid, None);
+        assert_eq!(spreads[1].spread_percent, None);
+    }
+}
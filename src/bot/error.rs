@@ -2,6 +2,7 @@
 
 use crate::config::ConfigError;
 use crate::exchanges::ExchangeError;
+use crate::storage::StorageError;
 
 /// Bot error type.
 #[derive(Debug, thiserror::Error)]
@@ -12,4 +13,16 @@ pub enum BotError {
     Config(#[from] ConfigError),
     #[error("exchange error: {0}")]
     Exchange(#[from] ExchangeError),
+    #[error("storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("metrics endpoint {address}: {source}")]
+    Metrics {
+        address: std::net::SocketAddr,
+        source: std::io::Error,
+    },
+    #[error("control API {address}: {source}")]
+    Api {
+        address: std::net::SocketAddr,
+        source: std::io::Error,
+    },
 }
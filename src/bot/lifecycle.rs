@@ -0,0 +1,224 @@
+//! Tracks opportunities across detection cycles until they close.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::{CloseReason, Opportunity, OpportunityLifecycle};
+
+/// State of an open lifecycle between cycles.
+struct OpenLifecycle {
+    lifecycle: OpportunityLifecycle,
+    /// Expiry of the last detection.
+    expires_at: DateTime<Utc>,
+    /// (exchange, pair) of every leg, used to tell a collapse from missing data.
+    books: Vec<(String, String)>,
+}
+
+/// LifecycleTracker matches each cycle's detections to open lifecycles.
+///
+/// An opportunity is identified by its type, pair and exchanges. When it is no
+/// longer detected although all of its books are fresh, the spread collapsed;
+/// when its books went stale, it stays open until its TTL expires.
+#[derive(Default)]
+pub(crate) struct LifecycleTracker {
+    open: HashMap<String, OpenLifecycle>,
+}
+
+impl LifecycleTracker {
+    /// Creates an empty tracker.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if the opportunity continues an open lifecycle.
+    pub(crate) fn is_open(&self, opportunity: &Opportunity) -> bool {
+        self.open.contains_key(&lifecycle_key(opportunity))
+    }
+
+    /// Records one detection cycle.
+    ///
+    /// `fresh_books` holds the (exchange, pair) of every orderbook that was fresh
+    /// during the cycle. `scope` holds the books the cycle re-evaluated, None for
+    /// all of them; lifecycles outside the scope can only expire. Returns the
+    /// lifecycles that were opened, updated or closed.
+    pub(crate) fn observe(
+        &mut self,
+        detected: &[Opportunity],
+        fresh_books: &HashSet<(String, String)>,
+        scope: Option<&HashSet<(String, String)>>,
+        now: DateTime<Utc>,
+    ) -> Vec<OpportunityLifecycle> {
+        let mut changed = Vec::new();
+        let mut seen = HashSet::new();
+
+        for opportunity in detected {
+            let key = lifecycle_key(opportunity);
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+
+            match self.open.get_mut(&key) {
+                Some(open) => {
+                    open.lifecycle.observe(opportunity);
+                    open.expires_at = opportunity.expires_at;
+                    changed.push(open.lifecycle.clone());
+                }
+                None => {
+                    let lifecycle = OpportunityLifecycle::open(opportunity);
+                    changed.push(lifecycle.clone());
+                    self.open.insert(
+                        key,
+                        OpenLifecycle {
+                            lifecycle,
+                            expires_at: opportunity.expires_at,
+                            books: opportunity
+                                .legs
+                                .iter()
+                                .map(|leg| (leg.exchange.clone(), leg.pair.clone()))
+                                .collect(),
+                        },
+                    );
+                }
+            }
+        }
+
+        let vanished: Vec<String> = self
+            .open
+            .keys()
+            .filter(|key| !seen.contains(*key))
+            .cloned()
+            .collect();
+
+        for key in vanished {
+            let open = &self.open[&key];
+            let evaluated =
+                scope.is_none_or(|scope| open.books.iter().any(|book| scope.contains(book)));
+            let collapsed = evaluated
+                && !open.books.is_empty()
+                && open.books.iter().all(|book| fresh_books.contains(book));
+
+            let reason = if collapsed {
+                CloseReason::SpreadCollapsed
+            } else if now >= open.expires_at {
+                CloseReason::Expired
+            } else {
+                continue;
+            };
+
+            if let Some(mut open) = self.open.remove(&key) {
+                open.lifecycle.close(reason, now);
+                changed.push(open.lifecycle);
+            }
+        }
+
+        changed
+    }
+}
+
+/// Identifies the same opportunity across detection cycles.
+fn lifecycle_key(opportunity: &Opportunity) -> String {
+    format!(
+        "{}|{}|{}|{}",
+        opportunity.opportunity_type, opportunity.pair, opportunity.buy_exchange, opportunity.sell_exchange
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{OpportunityLeg, OpportunityType, OrderSide};
+    use rust_decimal::Decimal;
+
+    fn opportunity(id: &str, profit_percent: Decimal, at: DateTime<Utc>) -> Opportunity {
+        let leg = |exchange: &str, side| OpportunityLeg {
+            exchange: exchange.to_string(),
+            pair: "BTC/USDT".to_string(),
+            side,
+            price: Decimal::from(100),
+            quantity: Decimal::ONE,
+            fee: Decimal::ZERO,
+        };
+        Opportunity {
+            id: id.to_string(),
+            opportunity_type: OpportunityType::CrossExchange,
+            pair: "BTC/USDT".to_string(),
+            buy_exchange: "a".to_string(),
+            sell_exchange: "b".to_string(),
+            buy_price: Decimal::from(100),
+            sell_price: Decimal::from(101),
+            quantity: Decimal::ONE,
+            gross_profit: Decimal::ONE,
+            net_profit: Decimal::ONE,
+            net_profit_after_rebalance: Decimal::ONE,
+            profit_percent,
+            buy_fee: Decimal::ZERO,
+            sell_fee: Decimal::ZERO,
+            detected_at: at,
+            expires_at: at + chrono::Duration::seconds(5),
+            legs: vec![leg("a", OrderSide::Buy), leg("b", OrderSide::Sell)],
+        }
+    }
+
+    fn fresh() -> HashSet<(String, String)> {
+        HashSet::from([
+            ("a".to_string(), "BTC/USDT".to_string()),
+            ("b".to_string(), "BTC/USDT".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_updates_open_lifecycle_and_closes_on_collapse() {
+        let mut tracker = LifecycleTracker::new();
+        let t0 = Utc::now();
+        let t1 = t0 + chrono::Duration::seconds(1);
+
+        tracker.observe(&[opportunity("first", Decimal::new(2, 3), t0)], &fresh(), None, t0);
+        let changed = tracker.observe(&[opportunity("second", Decimal::new(4, 3), t1)], &fresh(), None, t1);
+
+        assert_eq!(changed.len(), 1);
+        let lifecycle = &changed[0];
+        assert_eq!(lifecycle.opportunity_id, "first");
+        assert_eq!(lifecycle.observations, 2);
+        assert_eq!(lifecycle.peak_profit_percent, Decimal::new(4, 3));
+        assert_eq!(lifecycle.avg_profit_percent, Decimal::new(3, 3));
+        assert_eq!(lifecycle.duration(), chrono::Duration::seconds(1));
+
+        let t2 = t1 + chrono::Duration::seconds(1);
+        let closed = tracker.observe(&[], &fresh(), None, t2);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].close_reason, Some(CloseReason::SpreadCollapsed));
+        assert_eq!(closed[0].closed_at, Some(t2));
+    }
+
+    #[test]
+    fn test_stale_books_wait_for_expiry() {
+        let mut tracker = LifecycleTracker::new();
+        let t0 = Utc::now();
+        tracker.observe(&[opportunity("first", Decimal::new(2, 3), t0)], &fresh(), None, t0);
+
+        let stale = HashSet::new();
+        assert!(tracker.observe(&[], &stale, None, t0 + chrono::Duration::seconds(1)).is_empty());
+
+        let closed = tracker.observe(&[], &stale, None, t0 + chrono::Duration::seconds(5));
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].close_reason, Some(CloseReason::Expired));
+    }
+
+    #[test]
+    fn test_partial_cycle_only_closes_evaluated_lifecycles() {
+        let mut tracker = LifecycleTracker::new();
+        let t0 = Utc::now();
+        let t1 = t0 + chrono::Duration::seconds(1);
+        tracker.observe(&[opportunity("first", Decimal::new(2, 3), t0)], &fresh(), None, t0);
+
+        // Only an unrelated book changed, so the missing detection says nothing
+        let other = HashSet::from([("a".to_string(), "ETH/USDT".to_string())]);
+        assert!(tracker.observe(&[], &fresh(), Some(&other), t1).is_empty());
+
+        let scope = HashSet::from([("b".to_string(), "BTC/USDT".to_string())]);
+        let closed = tracker.observe(&[], &fresh(), Some(&scope), t1);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].close_reason, Some(CloseReason::SpreadCollapsed));
+    }
+}
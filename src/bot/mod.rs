@@ -2,37 +2,102 @@
 //!
 //! Coordinates all components: exchanges, detector, executor, risk manager, and notifications.
 
+mod commands;
+mod control;
 mod error;
+mod lifecycle;
+mod reload;
+mod shutdown;
 mod stats;
+#[cfg(test)]
+mod tests;
+mod transfers;
 
+pub use control::ControlError;
 pub use error::BotError;
+pub use shutdown::wait_for_signal;
 pub use stats::Stats;
 
-use std::collections::HashSet;
-use std::path::Path;
+use control::Controls;
+use lifecycle::LifecycleTracker;
+use reload::ReloadWatcher;
+use shutdown::OpenOrders;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use tokio::sync::{Mutex, RwLock};
+use chrono::Utc;
+use futures_util::future::join_all;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, Notify, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
 use tracing::{debug, info, warn};
 
-use crate::config::Config;
-use crate::domain::Opportunity;
-use crate::exchanges::Manager;
+use crate::config::{
+    Config, DEFAULT_DETECTION_TIMEOUT, DEFAULT_FALLBACK_INTERVAL, DEFAULT_MIN_REEVALUATION_INTERVAL,
+    DEFAULT_OVERVIEW_INTERVAL, DEFAULT_REFRESH_INTERVAL, DEFAULT_RETENTION_INTERVAL,
+    RetentionConfig, TransferConfig, overlay_path,
+};
+use crate::detector::{CrossExchangeDetector, MultiHopDetector, TriangularDetector};
+use crate::domain::{
+    Fees, Opportunity, OpportunityType, OrderSide, OrderStatus, Orderbook, TransferCosts,
+};
+use crate::exchanges::{ExchangeError, Manager, OrderbookCache};
+use crate::metrics::{self, Metrics};
 use crate::notification::{
-    Event, Notifier, OverviewData, ShutdownData, StartupData, TelegramConfig, TelegramNotifier,
+    ErrorData, Event, EventType, LegData, Notifier, OpportunityData, OpportunityKind, OverviewData, ShutdownData,
+    StartupData, TelegramConfig, TelegramNotifier,
 };
-use crate::storage::{OpportunityStorage, SqliteStorage, SqliteStorageConfig};
+use crate::storage::{self, RecordFilter, Storage, StorageError};
+
+/// How long shutdown waits for in-flight executions when no execution
+/// timeout is configured.
+const DEFAULT_EXECUTION_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Consecutive timed-out passes after which a slow exchange is reported.
+const SLOW_EXCHANGE_ALERT_TIMEOUTS: u32 = 3;
+
+/// Name the multi-hop search is reported under when it times out; it spans
+/// every exchange, so no single one is to blame.
+const MULTI_HOP_SEARCH: &str = "multi_hop";
+
+/// How often the detection progress is logged.
+const CYCLE_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often shutdown checks whether executions have finished.
+const EXECUTION_DRAIN_POLL: Duration = Duration::from_millis(50);
 
 /// Main arbitrage bot that coordinates all components.
 pub struct Bot {
-    cfg: Config,
+    /// Running configuration; live settings are swapped in on reload.
+    cfg: RwLock<Arc<Config>>,
+    config_path: String,
     exchange_manager: Arc<Manager>,
     notifier: Option<Arc<TelegramNotifier>>,
-    storage: Option<Arc<SqliteStorage>>,
+    storage: Option<Arc<dyn Storage>>,
+    orderbooks: Arc<OrderbookCache>,
+    /// Detectors built from the running configuration.
+    detectors: RwLock<Arc<Detectors>>,
+    /// Open opportunity lifecycles across detection cycles.
+    lifecycles: Mutex<LifecycleTracker>,
+    transfer_costs: RwLock<Arc<TransferCosts>>,
+    /// Consecutive timed-out detection passes per exchange (or search).
+    slow_exchanges: Mutex<HashMap<String, u32>>,
+    /// Pause, kill switch and disabled pairs and exchanges.
+    controls: RwLock<Controls>,
+    /// Balances of the last sync by exchange and asset.
+    balances: RwLock<BTreeMap<String, HashMap<String, Decimal>>>,
+    metrics: Arc<Metrics>,
+    /// Task serving the metrics endpoint, when enabled.
+    metrics_server: Mutex<Option<JoinHandle<()>>>,
 
     // Timeouts
-    detection_timeout: Duration,
+    fee_refresh_interval: Duration,
 
     // Runtime state
     version: String,
@@ -41,47 +106,98 @@ pub struct Bot {
     started_at: Mutex<Option<Instant>>,
     running: Mutex<bool>,
     stats: Mutex<Stats>,
+    /// Wakes the main loop when a shutdown is requested.
+    shutdown: Notify,
+    /// Why the bot is stopping, reported in the shutdown notification.
+    shutdown_reason: Mutex<Option<String>>,
 
     // Execution lock - prevents parallel executions for the same pair
     executing_pairs: RwLock<HashSet<String>>,
 }
 
+/// Arbitrage detectors configured by the `arbitrage` section.
+struct Detectors {
+    cross_exchange: CrossExchangeDetector,
+    /// Triangular detector, present when `arbitrage.triangular` is configured.
+    triangular: Option<TriangularDetector>,
+    /// Multi-hop detector, present when `arbitrage.multi_hop` is configured.
+    multi_hop: Option<MultiHopDetector>,
+}
+
+/// What a detection pass found for one pair, or for the cycle searches.
+#[derive(Default)]
+struct Detection {
+    opportunities: Vec<Opportunity>,
+    /// Whether the deadline cut the detection short.
+    timed_out: bool,
+    /// Exchanges (or searches) that had not finished by the deadline.
+    slow: Vec<String>,
+    /// Exchanges (or searches) that finished in time.
+    finished: Vec<String>,
+}
+
+impl Detectors {
+    fn from_config(cfg: &Config) -> Self {
+        Self {
+            cross_exchange: CrossExchangeDetector::from_config(cfg),
+            triangular: TriangularDetector::from_config(cfg),
+            multi_hop: MultiHopDetector::from_config(cfg),
+        }
+    }
+}
+
 impl Bot {
     /// Creates a new Bot instance from the config file path.
     pub async fn from_config_path(config_path: &str) -> Result<Self, BotError> {
         let cfg = Config::load(config_path)?;
 
-        let dry_run = cfg.app.env == "development";
+        let dry_run = cfg.app.env.is_development();
 
-        // Set detection timeout from config or use default
-        let detection_timeout = cfg
-            .arbitrage
-            .as_ref()
-            .and_then(|a| {
-                if a.detection_timeout.as_secs() > 0 {
-                    Some(a.detection_timeout)
-                } else {
-                    None
-                }
+        // Refresh fees as often as the most demanding exchange asks for
+        let fee_refresh_interval = cfg
+            .exchanges
+            .values()
+            .filter(|ex| ex.enabled)
+            .map(|ex| {
+                ex.fees
+                    .as_ref()
+                    .map(|f| f.refresh_interval)
+                    .unwrap_or(DEFAULT_REFRESH_INTERVAL)
             })
-            .unwrap_or(Duration::from_secs(10));
+            .min()
+            .unwrap_or(DEFAULT_REFRESH_INTERVAL);
 
         // Create the exchange manager from config
         let exchange_manager = Manager::from_config(&cfg).await?;
         info!("Exchange manager initialized with {} exchanges", exchange_manager.list().await.len());
 
         let mut bot = Bot {
-            cfg: cfg.clone(),
+            cfg: RwLock::new(Arc::new(cfg.clone())),
+            config_path: config_path.to_string(),
             exchange_manager: Arc::new(exchange_manager),
             notifier: None,
             storage: None,
-            detection_timeout,
+            orderbooks: Arc::new(OrderbookCache::new()),
+            detectors: RwLock::new(Arc::new(Detectors::from_config(&cfg))),
+            lifecycles: Mutex::new(LifecycleTracker::new()),
+            transfer_costs: RwLock::new(Arc::new(transfers::build_transfer_costs(
+                &cfg,
+                &HashMap::new(),
+            ))),
+            slow_exchanges: Mutex::new(HashMap::new()),
+            controls: RwLock::new(Controls::default()),
+            balances: RwLock::new(BTreeMap::new()),
+            metrics: Arc::new(Metrics::new()),
+            metrics_server: Mutex::new(None),
+            fee_refresh_interval,
             version: env!("CARGO_PKG_VERSION").to_string(),
             build_time: "".to_string(),
             dry_run,
             started_at: Mutex::new(None),
             running: Mutex::new(false),
             stats: Mutex::new(Stats::default()),
+            shutdown: Notify::new(),
+            shutdown_reason: Mutex::new(None),
             executing_pairs: RwLock::new(HashSet::new()),
         };
 
@@ -112,32 +228,12 @@ impl Bot {
         if let Some(ref storage_cfg) = cfg.storage {
             debug!(enabled = storage_cfg.enabled, "Storage configuration found");
             if storage_cfg.enabled {
-                // Ensure data directory exists
-                let data_dir = Path::new("data");
-                if !data_dir.exists() {
-                    if let Err(e) = std::fs::create_dir_all(data_dir) {
-                        warn!(error = %e, "Failed to create data directory");
-                    }
-                }
-
-                let filename = storage_cfg
-                    .path
-                    .clone()
-                    .unwrap_or_else(|| "opportunities.db".to_string());
-                let path = format!("data/{}", filename);
+                debug!(backend = ?storage_cfg.backend, "Initializing storage");
 
-                debug!(path = %path, "Initializing storage");
-
-                let config = SqliteStorageConfig {
-                    path: path.clone(),
-                    max_connections: 5,
-                };
-
-                match SqliteStorage::new(config).await {
-                    Ok(storage) => {
-                        bot.storage = Some(Arc::new(storage));
-                        info!(path = %path, "Storage initialized");
-                    }
+                match storage::connect(storage_cfg).await {
+                    Ok(storage) => bot.storage = Some(storage),
+                    // Writing with an older schema could corrupt a newer database
+                    Err(e @ StorageError::SchemaTooNew { .. }) => return Err(e.into()),
                     Err(e) => {
                         warn!(error = %e, "Failed to create storage");
                     }
@@ -165,22 +261,27 @@ impl Bot {
             *started_at = Some(Instant::now());
         }
 
-        let exchanges = self.get_exchange_names();
+        let cfg = self.config().await;
+        let exchanges = get_exchange_names(&cfg);
 
         info!(
             version = %self.version,
             build_time = %self.build_time,
             dry_run = self.dry_run,
             exchanges = ?exchanges,
-            pairs = ?self.cfg.pairs,
+            pairs = ?cfg.pairs,
             "Starting arbitrage bot"
         );
 
+        self.serve_metrics(&cfg).await?;
+        self.exchange_manager.connect_all().await?;
+        self.subscribe_orderbooks(&cfg.pairs).await;
+
         // Send startup notification
         self.send_notification(Event::startup(StartupData {
             version: self.version.clone(),
             exchanges,
-            pairs: self.cfg.pairs.clone(),
+            pairs: cfg.pairs.clone(),
             dry_run: self.dry_run,
         }))
         .await;
@@ -189,7 +290,21 @@ impl Bot {
         self.run_main_loop().await
     }
 
+    /// Asks the main loop to stop. The first reason given is the one reported.
+    pub async fn request_shutdown(&self, reason: impl Into<String>) {
+        self.shutdown_reason
+            .lock()
+            .await
+            .get_or_insert_with(|| reason.into());
+        self.shutdown.notify_one();
+    }
+
     /// Gracefully stops the bot.
+    ///
+    /// Detection stops first; in-flight executions get until the execution
+    /// timeout to finish, then the orders still open are cancelled and the
+    /// exchanges disconnected. The shutdown is reported as graceful only if
+    /// every step succeeded.
     pub async fn stop(&self) -> Result<(), BotError> {
         {
             let mut running = self.running.lock().await;
@@ -199,21 +314,35 @@ impl Bot {
             *running = false;
         }
 
-        info!("Stopping bot...");
+        let reason = self
+            .shutdown_reason
+            .lock()
+            .await
+            .clone()
+            .unwrap_or_else(|| "stopped".to_string());
+        info!(reason = %reason, "Stopping bot...");
 
         let uptime = self.uptime().await;
 
+        let drained = self.wait_for_executions().await;
+        let cancelled = self.cancel_open_orders().await;
+        if let Err(e) = self.exchange_manager.disconnect_all().await {
+            warn!(error = %e, "Failed to disconnect exchanges");
+        }
+
         // Send shutdown notification
         self.send_notification(Event::shutdown(ShutdownData {
-            reason: "graceful shutdown".to_string(),
+            reason,
             uptime,
-            graceful: true,
+            graceful: drained && cancelled,
         }))
         .await;
 
-        // Close notifier
-        if let Some(ref notifier) = self.notifier {
-            let _ = notifier.close().await;
+        // Close notifier, sending what is still queued
+        if let Some(ref notifier) = self.notifier
+            && let Err(e) = notifier.close().await
+        {
+            warn!(error = %e, "Failed to flush notifications");
         }
 
         // Close storage
@@ -225,6 +354,10 @@ impl Bot {
             }
         }
 
+        if let Some(server) = self.metrics_server.lock().await.take() {
+            server.abort();
+        }
+
         info!(uptime = ?uptime, "Bot stopped");
 
         Ok(())
@@ -249,47 +382,189 @@ impl Bot {
             .unwrap_or(Duration::ZERO)
     }
 
-    /// Returns exchange names from config.
-    fn get_exchange_names(&self) -> Vec<String> {
-        self.cfg
-            .exchanges
-            .iter()
-            .filter(|(_, ex)| ex.enabled)
-            .map(|(name, _)| name.clone())
-            .collect()
+    /// Returns the running configuration.
+    pub async fn config(&self) -> Arc<Config> {
+        Arc::clone(&*self.cfg.read().await)
+    }
+
+    /// Subscribes to orderbook streams of all exchanges and feeds the cache.
+    async fn subscribe_orderbooks(&self, pairs: &[String]) {
+        for name in self.exchange_manager.list().await {
+            let Some(exchange) = self.exchange_manager.get(&name).await else {
+                continue;
+            };
+
+            match exchange.subscribe_orderbook(pairs.to_vec()).await {
+                Ok(mut rx) => {
+                    let orderbooks = Arc::clone(&self.orderbooks);
+                    let metrics = Arc::clone(&self.metrics);
+                    tokio::spawn(async move {
+                        while let Some(orderbook) = rx.recv().await {
+                            metrics.orderbook_update(&orderbook.exchange, &orderbook.pair);
+                            orderbooks.update(orderbook).await;
+                        }
+                        debug!(exchange = %name, "Orderbook stream ended");
+                    });
+                }
+                Err(e) => {
+                    warn!(exchange = %name, error = %e, "Failed to subscribe to orderbooks");
+                }
+            }
+        }
     }
 
     /// Main arbitrage detection and execution loop.
+    ///
+    /// Detection runs when orderbooks change, re-evaluating only the routes of
+    /// the updated books. Updates of a pair arriving within the minimum
+    /// re-evaluation interval of its last detection are coalesced. A full pass
+    /// and the periodic housekeeping run on the fallback timer.
     async fn run_main_loop(&self) -> Result<(), BotError> {
-        let mut interval = tokio::time::interval(Duration::from_millis(500));
+        let cfg = self.config().await;
+        let (mut min_reevaluation_interval, mut fallback_interval) = detection_intervals(&cfg);
+        let mut fallback = tokio::time::interval(fallback_interval);
+        fallback.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-        // Overview interval from config or default 1 hour
-        let overview_interval = self
-            .cfg
+        // Books changed since their pair was last evaluated, and when each pair was last evaluated
+        let mut pending: HashSet<(String, String)> = HashSet::new();
+        let mut last_evaluated: HashMap<String, Instant> = HashMap::new();
+
+        let overview_interval = cfg
             .notification
             .as_ref()
             .and_then(|n| n.telegram.as_ref())
             .map(|t| t.overview_interval)
-            .filter(|d| d.as_secs() > 0)
-            .unwrap_or(Duration::from_secs(3600));
+            .unwrap_or(DEFAULT_OVERVIEW_INTERVAL);
 
         let mut last_overview = Instant::now();
 
+        self.refresh_fees().await;
+        let mut last_fee_refresh = Instant::now();
+
+        let retention = self.retention_config().await;
+        let retention_interval = retention
+            .as_ref()
+            .map(|r| r.interval)
+            .unwrap_or(DEFAULT_RETENTION_INTERVAL);
+
+        self.apply_retention().await;
+        let mut last_retention = Instant::now();
+        let mut last_vacuum = Instant::now();
+
+        self.sync_balances().await;
+        let mut last_balance_sync = Instant::now();
+
         info!(
-            detection_interval = ?Duration::from_millis(500),
+            min_reevaluation_interval = ?min_reevaluation_interval,
+            fallback_interval = ?fallback_interval,
             overview_interval = ?overview_interval,
-            detection_timeout = ?self.detection_timeout,
+            fee_refresh_interval = ?self.fee_refresh_interval,
+            retention_interval = ?retention.as_ref().map(|_| retention_interval),
+            detection_timeout = ?detection_timeout(&cfg),
             "Starting main detection loop"
         );
 
+        let mut reload = ReloadWatcher::spawn(vec![
+            PathBuf::from(&self.config_path),
+            overlay_path(&self.config_path, cfg.app.env),
+        ]);
+
+        let mut last_cycle_log = Instant::now();
+
         loop {
-            interval.tick().await;
+            let next_due = pending
+                .iter()
+                .map(|(_, pair)| due_at(&last_evaluated, pair, min_reevaluation_interval))
+                .min();
+
+            let full_pass = tokio::select! {
+                _ = self.shutdown.notified() => break,
+                _ = self.orderbooks.changed() => {
+                    pending.extend(self.orderbooks.take_changed().await);
+                    false
+                }
+                _ = tokio::time::sleep_until(next_due.unwrap_or_else(Instant::now).into()),
+                    if next_due.is_some() => false,
+                _ = fallback.tick() => true,
+            };
 
             if !self.is_running().await {
                 break;
             }
 
-            self.detect_and_execute().await;
+            if !full_pass {
+                let now = Instant::now();
+                let due: HashSet<(String, String)> = pending
+                    .iter()
+                    .filter(|(_, pair)| due_at(&last_evaluated, pair, min_reevaluation_interval) <= now)
+                    .cloned()
+                    .collect();
+                if !due.is_empty() {
+                    pending.retain(|book| !due.contains(book));
+                    for (_, pair) in &due {
+                        last_evaluated.insert(pair.clone(), now);
+                    }
+                    self.detect_and_execute(Some(&due)).await;
+                }
+                continue;
+            }
+
+            if let Some(reason) = reload.pending() {
+                info!(reason = reason, "Reloading configuration");
+                self.reload_config().await;
+
+                let (min_interval, fallback_period) = detection_intervals(&*self.config().await);
+                if fallback_period != fallback_interval {
+                    fallback = tokio::time::interval(fallback_period);
+                    fallback.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                    fallback.reset();
+                }
+                min_reevaluation_interval = min_interval;
+                fallback_interval = fallback_period;
+            }
+
+            pending.clear();
+            let now = Instant::now();
+            for pair in &self.config().await.pairs {
+                last_evaluated.insert(pair.clone(), now);
+            }
+            self.detect_and_execute(None).await;
+
+            if last_cycle_log.elapsed() >= CYCLE_LOG_INTERVAL {
+                let stats = self.stats().await;
+                info!(
+                    cycles = stats.detection_cycles,
+                    timeouts = stats.detection_timeouts,
+                    "Detection running"
+                );
+                last_cycle_log = Instant::now();
+            }
+
+            if last_fee_refresh.elapsed() >= self.fee_refresh_interval {
+                self.refresh_fees().await;
+                last_fee_refresh = Instant::now();
+            }
+
+            if retention.is_some() && last_retention.elapsed() >= retention_interval {
+                self.apply_retention().await;
+                last_retention = Instant::now();
+            }
+
+            if let Some(ref retention) = retention
+                && !retention.vacuum_interval.is_zero()
+                && last_vacuum.elapsed() >= retention.vacuum_interval
+            {
+                self.vacuum_storage().await;
+                last_vacuum = Instant::now();
+            }
+
+            if let Some(ref balance) = self.config().await.balance
+                && balance.enabled
+                && last_balance_sync.elapsed() >= balance.sync_interval
+            {
+                self.sync_balances().await;
+                last_balance_sync = Instant::now();
+            }
 
             // Check if it's time for overview
             if last_overview.elapsed() >= overview_interval {
@@ -301,27 +576,654 @@ impl Bot {
         Ok(())
     }
 
-    /// Runs one cycle of arbitrage detection and execution.
-    async fn detect_and_execute(&self) {
-        let cycles = {
-            let mut stats = self.stats.lock().await;
-            stats.detection_cycles += 1;
-            stats.detection_cycles
+    /// Refreshes trading fees and rebalancing transfer costs.
+    async fn refresh_fees(&self) {
+        self.exchange_manager.refresh_fees_all().await;
+
+        let cfg = self.config().await;
+        let config = transfer_config(&cfg);
+        let fetched = if config.is_none_or(|c| c.fetch_from_exchanges) {
+            self.exchange_manager.withdrawal_fees_all().await
+        } else {
+            HashMap::new()
         };
 
-        // Log every 20 cycles (~10 sec) at Info level
-        if cycles % 20 == 1 {
-            info!(
-                cycle = cycles,
-                pairs = self.cfg.pairs.len(),
-                "Detection cycle running"
+        *self.transfer_costs.write().await =
+            Arc::new(transfers::build_transfer_costs(&cfg, &fetched));
+    }
+
+    /// Fetches the balances of every connected exchange.
+    async fn sync_balances(&self) {
+        if !self
+            .config()
+            .await
+            .balance
+            .as_ref()
+            .is_some_and(|b| b.enabled)
+        {
+            return;
+        }
+
+        for name in self.exchange_manager.list().await {
+            let Some(exchange) = self.exchange_manager.get(&name).await else {
+                continue;
+            };
+            if !exchange.is_connected() {
+                continue;
+            }
+
+            match exchange.get_balances().await {
+                Ok(balances) => {
+                    self.metrics.balances(
+                        &name,
+                        balances
+                            .iter()
+                            .map(|(asset, amount)| (asset.clone(), amount.to_f64().unwrap_or_default())),
+                    );
+                    self.balances.write().await.insert(name, balances);
+                }
+                Err(e) => debug!(exchange = %name, error = %e, "Failed to sync balances"),
+            }
+        }
+    }
+
+    /// Starts the metrics endpoint if `metrics` is enabled.
+    async fn serve_metrics(&self, cfg: &Config) -> Result<(), BotError> {
+        let Some(ref metrics_cfg) = cfg.metrics else {
+            return Ok(());
+        };
+        if !metrics_cfg.enabled {
+            return Ok(());
+        }
+
+        let listener = TcpListener::bind(metrics_cfg.listen)
+            .await
+            .map_err(|source| BotError::Metrics {
+                address: metrics_cfg.listen,
+                source,
+            })?;
+        let server = tokio::spawn(metrics::serve(
+            listener,
+            Arc::clone(&self.metrics),
+            Arc::clone(&self.orderbooks),
+            Arc::clone(&self.exchange_manager),
+        ));
+        *self.metrics_server.lock().await = Some(server);
+        Ok(())
+    }
+
+    /// Returns the retention settings if storage is active and retention configured.
+    async fn retention_config(&self) -> Option<RetentionConfig> {
+        self.storage.as_ref()?;
+        self.config().await.storage.as_ref()?.retention.clone()
+    }
+
+    /// Prunes stored opportunities outside the retention limits.
+    async fn apply_retention(&self) {
+        let (Some(storage), Some(retention)) = (&self.storage, self.retention_config().await)
+        else {
+            return;
+        };
+
+        match storage::apply_retention(storage.as_ref(), &retention, Utc::now()).await {
+            Ok(report) if report.pruned > 0 => {
+                info!(
+                    pruned = report.pruned,
+                    cutoff = ?report.cutoff,
+                    archive = ?report.archive,
+                    "Pruned old opportunities"
+                );
+            }
+            Ok(_) => debug!("No opportunities to prune"),
+            Err(e) => warn!(error = %e, "Failed to apply storage retention"),
+        }
+    }
+
+    /// Reclaims storage space freed by pruning.
+    async fn vacuum_storage(&self) {
+        if let Some(ref storage) = self.storage {
+            match storage.vacuum().await {
+                Ok(()) => info!("Storage vacuumed"),
+                Err(e) => warn!(error = %e, "Failed to vacuum storage"),
+            }
+        }
+    }
+
+    /// Re-reads the config file and applies the settings that can change live.
+    ///
+    /// An invalid file is rejected as a whole and the running configuration is
+    /// kept. Changed settings that need a restart are reported and ignored.
+    async fn reload_config(&self) {
+        let reloaded = match Config::load(&self.config_path) {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                warn!(
+                    path = %self.config_path,
+                    error = %e,
+                    "Config reload rejected, keeping the running configuration"
+                );
+                return;
+            }
+        };
+
+        let running = self.config().await;
+        let changes = reloaded.changes_from(&running);
+        if changes.is_empty() {
+            debug!("Config reload: nothing changed");
+            return;
+        }
+
+        if !changes.restart_required.is_empty() {
+            warn!(
+                settings = ?changes.restart_required,
+                "Config reload: these settings only take effect after a restart"
+            );
+        }
+        if changes.live.is_empty() {
+            return;
+        }
+
+        if !changes.pairs_added.is_empty() || !changes.pairs_removed.is_empty() {
+            for name in self.exchange_manager.list().await {
+                let Some(exchange) = self.exchange_manager.get(&name).await else {
+                    continue;
+                };
+                if let Err(e) = exchange
+                    .update_orderbook_subscription(
+                        changes.pairs_added.clone(),
+                        changes.pairs_removed.clone(),
+                    )
+                    .await
+                {
+                    warn!(exchange = %name, error = %e, "Failed to update orderbook subscription");
+                }
+            }
+            for pair in &changes.pairs_removed {
+                self.orderbooks.remove_pair(pair).await;
+            }
+        }
+
+        let cfg = running.with_live_changes(&reloaded);
+        *self.detectors.write().await = Arc::new(Detectors::from_config(&cfg));
+        *self.cfg.write().await = Arc::new(cfg);
+
+        info!(
+            settings = ?changes.live,
+            pairs_added = ?changes.pairs_added,
+            pairs_removed = ?changes.pairs_removed,
+            "Config reloaded"
+        );
+    }
+
+    /// Runs one cycle of arbitrage detection and execution.
+    ///
+    /// `scope` holds the (exchange, pair) of the books that changed; only routes
+    /// through them are re-evaluated. None evaluates every route.
+    ///
+    /// The cycle has `arbitrage.detection_timeout` to finish. REST orderbook
+    /// requests and searches still running at the deadline are cancelled; each
+    /// pair or cycle search they cut short counts as a timeout, keeping what
+    /// was found before it.
+    async fn detect_and_execute(&self, scope: Option<&HashSet<(String, String)>>) {
+        let controls = self.controls.read().await.clone();
+        if controls.is_halted() {
+            return;
+        }
+
+        let cfg = self.config().await;
+        let detectors = Arc::clone(&*self.detectors.read().await);
+        let transfer_costs = Arc::clone(&*self.transfer_costs.read().await);
+        let started = Instant::now();
+        let deadline = started + detection_timeout(&cfg);
+        let max_age = cfg.orderbook.as_ref().map(|o| o.max_age).unwrap_or_default();
+
+        self.stats.lock().await.detection_cycles += 1;
+
+        let pairs: Vec<&String> = cfg
+            .pairs
+            .iter()
+            .filter(|pair| scope.is_none_or(|scope| scope.iter().any(|(_, p)| p == *pair)))
+            .filter(|pair| controls.pair_enabled(pair))
+            .collect();
+        let outcomes = join_all(pairs.iter().map(|pair| {
+            self.detect_pair(pair, &detectors, &transfer_costs, &controls, max_age, deadline)
+        }))
+        .await;
+
+        let mut opportunities = Vec::new();
+        let mut slow = BTreeSet::new();
+        let mut finished = BTreeSet::new();
+        for (pair, outcome) in pairs.iter().zip(outcomes) {
+            if outcome.timed_out {
+                warn!(pair = %pair, slow_exchanges = ?outcome.slow, "Pair detection timed out");
+                self.count_timeout().await;
+            }
+            opportunities.extend(outcome.opportunities);
+            slow.extend(outcome.slow);
+            finished.extend(outcome.finished);
+        }
+
+        if detectors.triangular.is_some() || detectors.multi_hop.is_some() {
+            let books: Vec<Orderbook> = self
+                .orderbooks
+                .get_all()
+                .await
+                .into_iter()
+                .filter(|book| controls.pair_enabled(&book.pair) && controls.exchange_enabled(&book.exchange))
+                .collect();
+
+            // Fees of every cached book, by exchange and pair
+            let mut fees: HashMap<String, HashMap<String, Fees>> = HashMap::new();
+            for name in self.exchange_manager.list().await {
+                if let Some(exchange) = self.exchange_manager.get(&name).await {
+                    let by_pair = books
+                        .iter()
+                        .filter(|book| book.exchange == name)
+                        .map(|book| (book.pair.clone(), exchange.get_fees(&book.pair)))
+                        .collect();
+                    fees.insert(name, by_pair);
+                }
+            }
+
+            // The searches are CPU-bound; run them off the runtime. They stop
+            // themselves at the deadline.
+            let detectors = Arc::clone(&detectors);
+            let scope = scope.cloned();
+            let search = tokio::task::spawn_blocking(move || {
+                detect_cycles(&detectors, &books, &fees, &transfer_costs, scope.as_ref(), deadline)
+            });
+            match search.await {
+                Ok(outcome) => {
+                    if outcome.timed_out {
+                        warn!(unfinished = ?outcome.slow, "Cycle detection timed out");
+                        self.count_timeout().await;
+                    }
+                    opportunities.extend(outcome.opportunities);
+                    slow.extend(outcome.slow);
+                    finished.extend(outcome.finished);
+                }
+                Err(e) => warn!(error = %e, "Cycle detection failed"),
+            }
+        }
+
+        self.record_slow_exchanges(&slow, &finished, &cfg).await;
+
+        self.metrics.detection(scope.is_none(), started.elapsed());
+
+        // TODO: Execute opportunities once the executor is ready, and close
+        // their lifecycles with CloseReason::Executed
+        let mut saved = HashSet::new();
+        for opportunity in &opportunities {
+            self.metrics.opportunity(opportunity);
+            debug!(
+                opportunity_type = %opportunity.opportunity_type,
+                pair = %opportunity.pair,
+                buy_exchange = %opportunity.buy_exchange,
+                sell_exchange = %opportunity.sell_exchange,
+                profit_percent = %opportunity.profit_percent,
+                "Opportunity detected"
             );
+
+            // Storage deduplicates repeated detections, so only new ones are announced
+            if self.save_opportunity(opportunity).await {
+                saved.insert(opportunity.id.clone());
+                self.send_notification(opportunity_event(opportunity)).await;
+            }
+        }
+
+        self.track_lifecycles(&opportunities, &saved, scope).await;
+    }
+
+    /// Detects cross-exchange opportunities for one pair, first fetching over
+    /// REST the books whose stream went stale. The requests get half the time
+    /// left before the deadline; exchanges that do not answer by then are left
+    /// out, and the books that did arrive are searched until the deadline.
+    async fn detect_pair(
+        &self,
+        pair: &str,
+        detectors: &Detectors,
+        transfer_costs: &TransferCosts,
+        controls: &Controls,
+        max_age: Duration,
+        deadline: Instant,
+    ) -> Detection {
+        let now = Instant::now();
+        let fetch_deadline = now + deadline.saturating_duration_since(now) / 2;
+        let (slow, finished) = self
+            .refresh_stale_books(pair, controls, max_age, fetch_deadline)
+            .await;
+        let mut detection = Detection {
+            timed_out: !slow.is_empty(),
+            slow,
+            finished,
+            ..Detection::default()
+        };
+
+        let mut books = self.orderbooks.get_pair(pair).await;
+        books.retain(|book| {
+            controls.exchange_enabled(&book.exchange) && !detection.slow.contains(&book.exchange)
+        });
+        if books.len() < 2 {
+            return detection;
+        }
+
+        let mut fees = HashMap::new();
+        for book in &books {
+            if let Some(exchange) = self.exchange_manager.get(&book.exchange).await {
+                fees.insert(book.exchange.clone(), exchange.get_fees(pair));
+            }
+        }
+
+        let found = match controls.threshold(pair) {
+            Some(threshold) => detectors
+                .cross_exchange
+                .with_min_profit_threshold(threshold)
+                .detect_until(pair, &books, &fees, transfer_costs, deadline),
+            None => detectors
+                .cross_exchange
+                .detect_until(pair, &books, &fees, transfer_costs, deadline),
+        };
+        match found {
+            Some(found) => detection.opportunities = found,
+            None => detection.timed_out = true,
+        }
+        detection
+    }
+
+    /// Fetches over REST the books of `pair` that are missing or older than
+    /// `max_age` (zero disables the fallback), waiting until the deadline.
+    /// Returns the exchanges that did not answer in time and those that did.
+    async fn refresh_stale_books(
+        &self,
+        pair: &str,
+        controls: &Controls,
+        max_age: Duration,
+        deadline: Instant,
+    ) -> (Vec<String>, Vec<String>) {
+        if max_age.is_zero() {
+            return (Vec::new(), Vec::new());
+        }
+
+        let now = SystemTime::now();
+        let fresh: HashSet<String> = self
+            .orderbooks
+            .get_pair(pair)
+            .await
+            .into_iter()
+            .filter(|book| {
+                now.duration_since(book.timestamp)
+                    .map(|age| age <= max_age)
+                    .unwrap_or(true)
+            })
+            .map(|book| book.exchange)
+            .collect();
+
+        let mut requests = Vec::new();
+        for name in self.exchange_manager.list().await {
+            if fresh.contains(&name) || !controls.exchange_enabled(&name) {
+                continue;
+            }
+            let Some(exchange) = self.exchange_manager.get(&name).await else {
+                continue;
+            };
+            if !exchange.is_connected() || !exchange.supported_pairs().iter().any(|p| p == pair) {
+                continue;
+            }
+            requests.push(async move {
+                let result = tokio::time::timeout_at(deadline.into(), exchange.get_orderbook(pair)).await;
+                (name, result)
+            });
         }
 
-        // TODO: Implement actual detection when components are ready
-        for pair in &self.cfg.pairs {
-            debug!(pair = %pair, "Processing pair");
+        let mut slow = Vec::new();
+        let mut answered = Vec::new();
+        for (name, result) in join_all(requests).await {
+            match result {
+                Err(_) => {
+                    slow.push(name);
+                    continue;
+                }
+                Ok(Ok(book)) => self.orderbooks.update(book).await,
+                Ok(Err(e)) => {
+                    debug!(exchange = %name, pair = %pair, error = %e, "REST orderbook fallback failed");
+                }
+            }
+            answered.push(name);
         }
+        (slow, answered)
+    }
+
+    /// Counts a pair or cycle search cut short by the detection timeout.
+    async fn count_timeout(&self) {
+        self.stats.lock().await.detection_timeouts += 1;
+        self.metrics.detection_timeout();
+    }
+
+    /// Records which exchanges (or searches) timed out during a detection
+    /// pass, each once however many pairs it held up; those that only
+    /// finished in time start over. An error notification names one once it
+    /// has timed out in `SLOW_EXCHANGE_ALERT_TIMEOUTS` passes in a row.
+    async fn record_slow_exchanges(
+        &self,
+        slow: &BTreeSet<String>,
+        finished: &BTreeSet<String>,
+        cfg: &Config,
+    ) {
+        let mut alerts = Vec::new();
+        {
+            let mut slow_exchanges = self.slow_exchanges.lock().await;
+            for name in finished.difference(slow) {
+                slow_exchanges.remove(name);
+            }
+            for name in slow {
+                let count = slow_exchanges.entry(name.clone()).or_default();
+                *count += 1;
+                if *count == SLOW_EXCHANGE_ALERT_TIMEOUTS {
+                    alerts.push(name);
+                }
+            }
+        }
+
+        for name in alerts {
+            warn!(exchange = %name, timeouts = SLOW_EXCHANGE_ALERT_TIMEOUTS, "Exchange keeps timing out");
+            self.send_notification(Event::error(ErrorData {
+                component: name.clone(),
+                message: format!(
+                    "detection timed out in {} passes in a row (detection_timeout {:?})",
+                    SLOW_EXCHANGE_ALERT_TIMEOUTS,
+                    detection_timeout(cfg)
+                ),
+                error: None,
+            }))
+            .await;
+        }
+    }
+
+    /// Updates opportunity lifecycles with this cycle's detections and persists the changes.
+    ///
+    /// `saved` holds the IDs of detections stored during this cycle; `scope`
+    /// the books it re-evaluated, None for all.
+    async fn track_lifecycles(
+        &self,
+        opportunities: &[Opportunity],
+        saved: &HashSet<String>,
+        scope: Option<&HashSet<(String, String)>>,
+    ) {
+        let max_age = self
+            .config()
+            .await
+            .orderbook
+            .as_ref()
+            .map(|o| o.max_age)
+            .unwrap_or_default();
+        let now = SystemTime::now();
+        let controls = self.controls.read().await.clone();
+
+        // Books of disabled exchanges count as stale: their lifecycles can only expire
+        let fresh_books: HashSet<(String, String)> = self
+            .orderbooks
+            .get_all()
+            .await
+            .into_iter()
+            .filter(|book| controls.exchange_enabled(&book.exchange))
+            .filter(|book| {
+                max_age.is_zero()
+                    || now
+                        .duration_since(book.timestamp)
+                        .map(|age| age <= max_age)
+                        .unwrap_or(true)
+            })
+            .map(|book| (book.exchange, book.pair))
+            .collect();
+
+        let changed = {
+            let mut tracker = self.lifecycles.lock().await;
+
+            // Stored lifecycles reference the detection that opened them, so a
+            // deduplicated (unsaved) detection may only continue an open lifecycle
+            let tracked: Vec<Opportunity> = opportunities
+                .iter()
+                .filter(|o| {
+                    self.storage.is_none() || saved.contains(&o.id) || tracker.is_open(o)
+                })
+                .cloned()
+                .collect();
+
+            tracker.observe(&tracked, &fresh_books, scope, Utc::now())
+        };
+
+        for lifecycle in changed {
+            if let Some(reason) = lifecycle.close_reason {
+                debug!(
+                    id = %lifecycle.opportunity_id,
+                    reason = %reason,
+                    duration_ms = lifecycle.duration().num_milliseconds(),
+                    peak_profit_percent = %lifecycle.peak_profit_percent,
+                    "Opportunity closed"
+                );
+            }
+
+            if let Some(ref storage) = self.storage
+                && let Err(e) = storage.save_lifecycle(&lifecycle).await
+            {
+                warn!(
+                    error = %e,
+                    id = %lifecycle.opportunity_id,
+                    "Failed to save opportunity lifecycle"
+                );
+            }
+        }
+    }
+
+    /// Waits until no pair is executing, for at most the execution timeout.
+    /// Returns false if executions were still running at the deadline.
+    async fn wait_for_executions(&self) -> bool {
+        let timeout = self
+            .config()
+            .await
+            .execution
+            .as_ref()
+            .map(|e| e.timeout)
+            .filter(|t| !t.is_zero())
+            .unwrap_or(DEFAULT_EXECUTION_DRAIN_TIMEOUT);
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let executing = self.executing_pairs.read().await.clone();
+            if executing.is_empty() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                warn!(pairs = ?executing, "Executions still running at shutdown");
+                return false;
+            }
+            tokio::time::sleep(EXECUTION_DRAIN_POLL).await;
+        }
+    }
+
+    /// Cancels the orders the bot placed that are still open on a connected
+    /// exchange. Returns false if any could not be looked up or cancelled.
+    ///
+    /// The bot's orders are those recorded in storage, so orders from an
+    /// earlier run are cleaned up too while orders placed by hand or by other
+    /// tools on the same accounts are left alone. Each recorded open order is
+    /// looked up first, as a fill or cancel may not have been recorded. Dry
+    /// runs place no orders and cancel nothing.
+    async fn cancel_open_orders(&self) -> bool {
+        if self.dry_run {
+            return true;
+        }
+        let Some(ref storage) = self.storage else {
+            debug!("No storage, no recorded orders to cancel");
+            return true;
+        };
+
+        let mut recorded = OpenOrders::default();
+        match storage.get_orders(&RecordFilter::default()).await {
+            Ok(orders) => orders.iter().for_each(|order| recorded.update(order)),
+            Err(e) => {
+                warn!(error = %e, "Failed to load recorded orders");
+                return false;
+            }
+        }
+
+        let mut all_cancelled = true;
+        let mut open = OpenOrders::default();
+        for order in recorded.take() {
+            let Some(exchange) = self.exchange_manager.get(&order.exchange).await else {
+                continue;
+            };
+            if !exchange.is_connected() {
+                continue;
+            }
+            match exchange.get_order(&order.id).await {
+                Ok(current) => open.update(&current),
+                // Gone from the exchange since it was recorded
+                Err(ExchangeError::OrderNotFound(_)) => {}
+                Err(e) => {
+                    warn!(exchange = %order.exchange, id = %order.id, error = %e, "Failed to look up order");
+                    all_cancelled = false;
+                }
+            }
+        }
+
+        let orders = open.take();
+        if orders.is_empty() {
+            return all_cancelled;
+        }
+
+        info!(count = orders.len(), "Cancelling open orders");
+        for mut order in orders {
+            let Some(exchange) = self.exchange_manager.get(&order.exchange).await else {
+                warn!(exchange = %order.exchange, id = %order.id, "Open order on unknown exchange");
+                all_cancelled = false;
+                continue;
+            };
+
+            match exchange.cancel_order(&order.id).await {
+                Ok(()) => {
+                    order.status = OrderStatus::Cancelled;
+                    order.updated_at = SystemTime::now();
+                    if let Some(ref storage) = self.storage
+                        && let Err(e) = storage.save_order(&order).await
+                    {
+                        warn!(error = %e, id = %order.id, "Failed to save cancelled order");
+                    }
+                }
+                // Filled or cancelled in the meantime
+                Err(ExchangeError::OrderNotFound(_)) => {}
+                Err(e) => {
+                    warn!(
+                        exchange = %order.exchange,
+                        id = %order.id,
+                        error = %e,
+                        "Failed to cancel order"
+                    );
+                    all_cancelled = false;
+                }
+            }
+        }
+        all_cancelled
     }
 
     /// Attempts to acquire a lock for executing trades on the given pair.
@@ -373,16 +1275,15 @@ impl Bot {
         }
     }
 
-    /// Sends a notification event if the notifier is configured.
+    /// Queues a notification event if the notifier is configured and the
+    /// event's `notify_*` toggle is on. Detection never waits for delivery.
     async fn send_notification(&self, event: Event) {
+        if !notifies(&*self.config().await, event.event_type) {
+            return;
+        }
+
         if let Some(ref notifier) = self.notifier {
-            if let Err(e) = notifier.send(&event).await {
-                debug!(
-                    event_type = %event.event_type,
-                    error = %e,
-                    "Failed to send notification"
-                );
-            }
+            notifier.send_async(event);
         }
     }
 
@@ -410,3 +1311,144 @@ impl Bot {
         .await;
     }
 }
+
+/// Returns the names of the enabled exchanges.
+fn get_exchange_names(cfg: &Config) -> Vec<String> {
+    cfg.exchanges
+        .iter()
+        .filter(|(_, ex)| ex.enabled)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Runs the triangular and multi-hop detectors over every cached book until
+/// the deadline, keeping the opportunities that trade on a book in `scope`
+/// (None keeps all). Triangular searches are reported under their exchange,
+/// the multi-hop search under `MULTI_HOP_SEARCH`.
+fn detect_cycles(
+    detectors: &Detectors,
+    books: &[Orderbook],
+    fees: &HashMap<String, HashMap<String, Fees>>,
+    transfer_costs: &TransferCosts,
+    scope: Option<&HashSet<(String, String)>>,
+    deadline: Instant,
+) -> Detection {
+    let touches_scope = |opportunity: &Opportunity| {
+        scope.is_none_or(|scope| {
+            opportunity
+                .legs
+                .iter()
+                .any(|leg| scope.contains(&(leg.exchange.clone(), leg.pair.clone())))
+        })
+    };
+
+    let mut detection = Detection::default();
+    let mut record = |name: &str, found: Option<Vec<Opportunity>>| match found {
+        Some(found) => {
+            detection
+                .opportunities
+                .extend(found.into_iter().filter(|o| touches_scope(o)));
+            detection.finished.push(name.to_string());
+        }
+        None => {
+            detection.timed_out = true;
+            detection.slow.push(name.to_string());
+        }
+    };
+
+    if let Some(ref triangular) = detectors.triangular {
+        for (name, by_pair) in fees {
+            if scope.is_some_and(|scope| !scope.iter().any(|(exchange, _)| exchange == name)) {
+                continue;
+            }
+            record(name, triangular.detect_until(name, books, by_pair, deadline));
+        }
+    }
+
+    if let Some(ref multi_hop) = detectors.multi_hop {
+        let found = multi_hop.detect_until(books, fees, transfer_costs, deadline);
+        record(MULTI_HOP_SEARCH, found);
+    }
+
+    detection
+}
+
+/// Returns the minimum re-evaluation interval and the fallback interval.
+fn detection_intervals(cfg: &Config) -> (Duration, Duration) {
+    cfg.arbitrage
+        .as_ref()
+        .map(|a| (a.min_reevaluation_interval, a.fallback_interval))
+        .unwrap_or((DEFAULT_MIN_REEVALUATION_INTERVAL, DEFAULT_FALLBACK_INTERVAL))
+}
+
+/// Returns when a pair may be evaluated again.
+fn due_at(last_evaluated: &HashMap<String, Instant>, pair: &str, min_interval: Duration) -> Instant {
+    last_evaluated
+        .get(pair)
+        .map(|last| *last + min_interval)
+        .unwrap_or_else(Instant::now)
+}
+
+/// Returns the configured detection timeout.
+fn detection_timeout(cfg: &Config) -> Duration {
+    cfg.arbitrage
+        .as_ref()
+        .map(|a| a.detection_timeout)
+        .unwrap_or(DEFAULT_DETECTION_TIMEOUT)
+}
+
+/// Returns true if the Telegram `notify_*` toggle for the event type is on.
+/// Startup and shutdown are always sent.
+fn notifies(cfg: &Config, event_type: EventType) -> bool {
+    let Some(telegram) = cfg.notification.as_ref().and_then(|n| n.telegram.as_ref()) else {
+        return false;
+    };
+
+    match event_type {
+        EventType::Startup | EventType::Shutdown => true,
+        EventType::Opportunity => telegram.notify_opportunities,
+        EventType::Execution => telegram.notify_executions,
+        EventType::Error => telegram.notify_errors,
+        EventType::Overview => telegram.notify_overview,
+    }
+}
+
+/// Returns the `arbitrage.transfer` config section, if present.
+fn transfer_config(cfg: &Config) -> Option<&TransferConfig> {
+    cfg.arbitrage.as_ref().and_then(|a| a.transfer.as_ref())
+}
+
+/// Builds an opportunity notification event.
+fn opportunity_event(opportunity: &Opportunity) -> Event {
+    let kind = match opportunity.opportunity_type {
+        OpportunityType::CrossExchange => OpportunityKind::CrossExchange,
+        OpportunityType::Triangular => OpportunityKind::Triangular,
+        OpportunityType::MultiHop => OpportunityKind::MultiHop,
+    };
+    let legs = opportunity
+        .legs
+        .iter()
+        .map(|leg| LegData {
+            exchange: leg.exchange.clone(),
+            pair: leg.pair.clone(),
+            buy: leg.side == OrderSide::Buy,
+            price: leg.price.to_f64().unwrap_or_default(),
+            quantity: leg.quantity.to_f64().unwrap_or_default(),
+        })
+        .collect();
+
+    Event::opportunity(OpportunityData {
+        kind,
+        pair: opportunity.pair.clone(),
+        buy_exchange: opportunity.buy_exchange.clone(),
+        sell_exchange: opportunity.sell_exchange.clone(),
+        buy_price: opportunity.buy_price.to_f64().unwrap_or_default(),
+        sell_price: opportunity.sell_price.to_f64().unwrap_or_default(),
+        spread_percent: (opportunity.profit_percent * Decimal::ONE_HUNDRED)
+            .to_f64()
+            .unwrap_or_default(),
+        potential_profit: opportunity.net_profit.to_f64().unwrap_or_default(),
+        quantity: opportunity.quantity.to_f64().unwrap_or_default(),
+        legs,
+    })
+}
@@ -0,0 +1,83 @@
+//! Watches for configuration reload requests.
+//!
+//! A reload is requested when the modification time of a config file (the base
+//! file or its environment overlay) changes or, on Unix, when the process
+//! receives SIGHUP.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// How often the config file's modification time is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Background tasks that signal configuration reload requests.
+pub(crate) struct ReloadWatcher {
+    rx: mpsc::Receiver<&'static str>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ReloadWatcher {
+    /// Starts watching the config files at `paths` and listening for SIGHUP.
+    pub(crate) fn spawn(paths: Vec<PathBuf>) -> Self {
+        let (tx, rx) = mpsc::channel(1);
+        let mut tasks: Vec<JoinHandle<()>> = paths
+            .into_iter()
+            .map(|path| tokio::spawn(watch_file(path, tx.clone())))
+            .collect();
+
+        #[cfg(unix)]
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(mut hangup) => tasks.push(tokio::spawn(async move {
+                while hangup.recv().await.is_some() {
+                    // A reload is already pending when the channel is full
+                    let _ = tx.try_send("SIGHUP");
+                }
+            })),
+            Err(e) => warn!(error = %e, "Failed to listen for SIGHUP"),
+        }
+
+        Self { rx, tasks }
+    }
+
+    /// Returns the reason of a pending reload request, if any.
+    pub(crate) fn pending(&mut self) -> Option<&'static str> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Drop for ReloadWatcher {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Requests a reload whenever the file's modification time changes, including
+/// when a missing file appears.
+async fn watch_file(path: PathBuf, tx: mpsc::Sender<&'static str>) {
+    let modified = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    let mut last: Option<SystemTime> = modified(&path);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        // A missing file is usually an editor replacing it; wait for the new one
+        let Some(current) = modified(&path) else {
+            continue;
+        };
+        if last != Some(current) {
+            debug!(path = %path.display(), "Config file changed");
+            last = Some(current);
+            let _ = tx.try_send("config file changed");
+        }
+    }
+}
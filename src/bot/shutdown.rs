@@ -0,0 +1,110 @@
+//! Shutdown signals and the orders left to clean up on shutdown.
+//!
+//! The bot stops on SIGINT (Ctrl+C) or, on Unix, SIGTERM. Orders the bot
+//! placed that are still open are cancelled before the exchanges disconnect.
+
+use std::collections::HashMap;
+
+use tracing::warn;
+
+use crate::domain::{Order, OrderStatus};
+
+/// Waits for a shutdown signal and returns its name.
+pub async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => return "SIGINT",
+                    _ = terminate.recv() => return "SIGTERM",
+                }
+            }
+            Err(e) => warn!(error = %e, "Failed to listen for SIGTERM"),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!(error = %e, "Failed to listen for SIGINT");
+        std::future::pending::<()>().await;
+    }
+    "SIGINT"
+}
+
+/// Open orders, one per (exchange, order ID).
+#[derive(Default)]
+pub(crate) struct OpenOrders {
+    /// Orders by (exchange, order ID).
+    orders: HashMap<(String, String), Order>,
+}
+
+impl OpenOrders {
+    /// Records an order; orders that are no longer open are forgotten.
+    pub(crate) fn update(&mut self, order: &Order) {
+        let key = (order.exchange.clone(), order.id.clone());
+        match order.status {
+            OrderStatus::Pending | OrderStatus::Open => {
+                self.orders.insert(key, order.clone());
+            }
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Failed => {
+                self.orders.remove(&key);
+            }
+        }
+    }
+
+    /// Removes and returns every open order, oldest first.
+    pub(crate) fn take(&mut self) -> Vec<Order> {
+        let mut orders: Vec<Order> = self.orders.drain().map(|(_, order)| order).collect();
+        orders.sort_by_key(|order| order.created_at);
+        orders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{OrderSide, OrderType};
+    use rust_decimal::Decimal;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn order(exchange: &str, id: &str, status: OrderStatus, created_secs: u64) -> Order {
+        let created_at = UNIX_EPOCH + Duration::from_secs(created_secs);
+        Order {
+            id: id.to_string(),
+            exchange: exchange.to_string(),
+            pair: "BTC/USDT".to_string(),
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            price: Decimal::from(40000),
+            quantity: Decimal::ONE,
+            status,
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    #[test]
+    fn test_open_orders_forget_settled_orders() {
+        let mut open = OpenOrders::default();
+        open.update(&order("poloniex", "2", OrderStatus::Open, 20));
+        open.update(&order("poloniex", "1", OrderStatus::Pending, 10));
+        open.update(&order("poloniex-sub1", "1", OrderStatus::Open, 30));
+        open.update(&order("poloniex", "2", OrderStatus::Filled, 20));
+
+        let taken: Vec<(String, String)> = open
+            .take()
+            .into_iter()
+            .map(|o| (o.exchange, o.id))
+            .collect();
+        assert_eq!(
+            taken,
+            vec![
+                ("poloniex".to_string(), "1".to_string()),
+                ("poloniex-sub1".to_string(), "1".to_string()),
+            ]
+        );
+        assert!(open.take().is_empty());
+    }
+}
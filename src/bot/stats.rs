@@ -1,9 +1,13 @@
 //! Runtime statistics for the bot.
 
+use serde::Serialize;
+
 /// Runtime statistics for the bot.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Stats {
     pub detection_cycles: u64,
+    /// Pairs and cycle searches cancelled at the detection timeout.
+    pub detection_timeouts: u64,
     pub opportunities_detected: u64,
     pub opportunities_executed: u64,
     pub successful_trades: u64,
@@ -0,0 +1,528 @@
+//! Tests for the bot lifecycle, with a mock exchange and a mock Telegram API.
+
+use super::*;
+use crate::domain::{Order, OrderType, PriceLevel, Trade};
+use crate::exchanges::{Exchange, ExchangeError};
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{Value, json};
+use std::io::Write;
+use std::sync::Mutex as StdMutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tempfile::NamedTempFile;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+const TOKEN: &str = "test-token";
+
+/// Pairs the test bot monitors.
+const PAIRS: [&str; 3] = ["BTC/USDT", "ETH/USDT", "LTC/USDT"];
+
+/// Mock exchange serving REST orderbooks and holding open orders.
+struct MockExchange {
+    name: String,
+    connected: AtomicBool,
+    /// Best bid and ask served for every pair, if any.
+    quotes: Option<(Decimal, Decimal)>,
+    /// How long an orderbook request takes to answer.
+    orderbook_delay: Duration,
+    /// Orderbook requests answered.
+    orderbooks_served: AtomicUsize,
+    open_orders: Vec<Order>,
+    /// Orders whose cancellation fails.
+    failing: Vec<String>,
+    /// IDs of every cancel request, in order.
+    cancel_requests: StdMutex<Vec<String>>,
+}
+
+impl MockExchange {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            connected: AtomicBool::new(true),
+            quotes: None,
+            orderbook_delay: Duration::ZERO,
+            orderbooks_served: AtomicUsize::new(0),
+            open_orders: Vec::new(),
+            failing: Vec::new(),
+            cancel_requests: StdMutex::new(Vec::new()),
+        }
+    }
+
+    fn with_quotes(mut self, bid: i64, ask: i64) -> Self {
+        self.quotes = Some((Decimal::from(bid), Decimal::from(ask)));
+        self
+    }
+
+    fn with_orderbook_delay(mut self, delay: Duration) -> Self {
+        self.orderbook_delay = delay;
+        self
+    }
+
+    fn orderbook(&self, pair: &str) -> Option<Orderbook> {
+        let (bid, ask) = self.quotes?;
+        Some(Orderbook {
+            pair: pair.to_string(),
+            exchange: self.name.clone(),
+            bids: vec![PriceLevel { price: bid, quantity: Decimal::ONE }],
+            asks: vec![PriceLevel { price: ask, quantity: Decimal::ONE }],
+            timestamp: SystemTime::now(),
+        })
+    }
+
+    fn with_open_order(mut self, id: &str, pair: &str) -> Self {
+        let now = SystemTime::now();
+        self.open_orders.push(Order {
+            id: id.to_string(),
+            exchange: self.name.clone(),
+            pair: pair.to_string(),
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            price: Decimal::ONE,
+            quantity: Decimal::ONE,
+            status: OrderStatus::Open,
+            created_at: now,
+            updated_at: now,
+        });
+        self
+    }
+
+    fn with_failing_cancel(mut self, id: &str) -> Self {
+        self.failing.push(id.to_string());
+        self
+    }
+
+    fn cancel_requests(&self) -> Vec<String> {
+        self.cancel_requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Exchange for MockExchange {
+    async fn connect(&self) -> crate::exchanges::Result<()> {
+        self.connected.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> crate::exchanges::Result<()> {
+        self.connected.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    async fn get_orderbook(&self, pair: &str) -> crate::exchanges::Result<Orderbook> {
+        tokio::time::sleep(self.orderbook_delay).await;
+        self.orderbooks_served.fetch_add(1, Ordering::SeqCst);
+        self.orderbook(pair)
+            .ok_or_else(|| ExchangeError::PairNotSupported(pair.to_string()))
+    }
+
+    async fn subscribe_orderbook(
+        &self,
+        _pairs: Vec<String>,
+    ) -> crate::exchanges::Result<mpsc::UnboundedReceiver<Orderbook>> {
+        Ok(mpsc::unbounded_channel().1)
+    }
+
+    async fn place_order(&self, _order: Order) -> crate::exchanges::Result<Trade> {
+        unimplemented!("not needed for bot tests")
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> crate::exchanges::Result<()> {
+        self.cancel_requests.lock().unwrap().push(order_id.to_string());
+        if self.failing.iter().any(|id| id == order_id) {
+            return Err(ExchangeError::Api("cancel rejected".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn get_order(&self, order_id: &str) -> crate::exchanges::Result<Order> {
+        self.open_orders
+            .iter()
+            .find(|order| order.id == order_id)
+            .cloned()
+            .ok_or_else(|| ExchangeError::OrderNotFound(order_id.to_string()))
+    }
+
+    async fn get_balances(&self) -> crate::exchanges::Result<HashMap<String, Decimal>> {
+        Ok(HashMap::new())
+    }
+
+    fn get_fees(&self, _pair: &str) -> Fees {
+        Fees::new(Decimal::ZERO, Decimal::ZERO)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supported_pairs(&self) -> Vec<String> {
+        PAIRS.iter().map(|pair| pair.to_string()).collect()
+    }
+}
+
+async fn record_message(
+    State(sent): State<Arc<StdMutex<Vec<String>>>>,
+    Json(body): Json<Value>,
+) -> Json<Value> {
+    sent.lock().unwrap().push(body["text"].as_str().unwrap().to_string());
+    Json(json!({ "ok": true, "result": {} }))
+}
+
+/// Starts a mock Bot API and returns a notifier sending to it, with the
+/// texts it received.
+async fn mock_notifier() -> (Arc<TelegramNotifier>, Arc<StdMutex<Vec<String>>>) {
+    let sent = Arc::new(StdMutex::new(Vec::new()));
+    let app = Router::new()
+        .route(&format!("/bot{}/sendMessage", TOKEN), post(record_message))
+        .with_state(Arc::clone(&sent));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/bot", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let notifier = TelegramNotifier::new(TelegramConfig::new(TOKEN, "42").with_api_url(base)).unwrap();
+    (Arc::new(notifier), sent)
+}
+
+/// Builds a bot monitoring `PAIRS` with Telegram notifications going to a
+/// mock Bot API, marked as running. `extra` is appended to the config.
+async fn running_bot(
+    exchanges: Vec<Arc<dyn Exchange>>,
+    extra: &str,
+) -> (Bot, Arc<StdMutex<Vec<String>>>, NamedTempFile, NamedTempFile) {
+    let mut token = NamedTempFile::new().unwrap();
+    write!(token, "{}", TOKEN).unwrap();
+
+    let mut file = NamedTempFile::new().unwrap();
+    write!(
+        file,
+        r#"
+app:
+  name: bottest
+  env: development
+
+exchanges:
+  poloniex:
+    enabled: true
+    fee_taker: "0.001"
+
+notification:
+  telegram:
+    enabled: true
+    bot_token: "file:{}"
+    notify_errors: true
+
+pairs:
+  - BTC/USDT
+  - ETH/USDT
+  - LTC/USDT
+{}
+"#,
+        token.path().display(),
+        extra
+    )
+    .unwrap();
+
+    let mut bot = Bot::from_config_path(file.path().to_str().unwrap())
+        .await
+        .unwrap();
+    for exchange in exchanges {
+        bot.exchange_manager.register(exchange).await;
+    }
+    let (notifier, sent) = mock_notifier().await;
+    bot.notifier = Some(notifier);
+    *bot.running.lock().await = true;
+
+    (bot, sent, file, token)
+}
+
+/// Returns the shutdown notification among the sent texts.
+fn shutdown_message(sent: &StdMutex<Vec<String>>) -> String {
+    sent.lock()
+        .unwrap()
+        .iter()
+        .find(|text| text.contains("Бот остановлен"))
+        .cloned()
+        .expect("shutdown notification sent")
+}
+
+/// Starts a trading (not dry-run) test bot that stores its orders under
+/// `dir` and has recorded the given open orders on the mock exchange.
+async fn trading_bot(
+    exchange: Arc<MockExchange>,
+    dir: &tempfile::TempDir,
+    recorded: &[&str],
+) -> (Bot, Arc<StdMutex<Vec<String>>>, NamedTempFile, NamedTempFile) {
+    let storage = format!(
+        "storage:\n  enabled: true\n  path: \"{}\"",
+        dir.path().join("orders.db").display()
+    );
+    let (mut bot, sent, file, token) = running_bot(vec![exchange], &storage).await;
+    bot.dry_run = false;
+    let storage = bot.storage.as_ref().unwrap();
+    for id in recorded {
+        let order = crate::storage::conformance::sample_order("mock", id, "BTC/USDT", 0);
+        storage.save_order(&order).await.unwrap();
+    }
+    (bot, sent, file, token)
+}
+
+#[tokio::test]
+async fn test_stop_cancels_the_open_orders_the_bot_placed() {
+    let exchange = Arc::new(
+        MockExchange::new("mock")
+            .with_open_order("1", "BTC/USDT")
+            .with_open_order("2", "BTC/USDT")
+            .with_open_order("3", "ETH/USDT"),
+    );
+    let dir = tempfile::tempdir().unwrap();
+    // Order 4 was filled without the fill being recorded
+    let (bot, sent, _file, _token) = trading_bot(exchange.clone(), &dir, &["1", "3", "4"]).await;
+
+    bot.request_shutdown("received SIGTERM").await;
+    bot.request_shutdown("error: later").await;
+    bot.stop().await.unwrap();
+
+    // Order 2 was not placed by the bot and is left alone
+    let mut requests = exchange.cancel_requests();
+    requests.sort();
+    assert_eq!(requests, vec!["1", "3"]);
+    assert!(!exchange.is_connected());
+
+    let message = shutdown_message(&sent);
+    assert!(message.contains("Причина: received SIGTERM"), "{}", message);
+    assert!(message.contains("Graceful"), "{}", message);
+}
+
+#[tokio::test]
+async fn test_stop_is_not_graceful_when_a_cancel_fails() {
+    let exchange = Arc::new(
+        MockExchange::new("mock")
+            .with_open_order("1", "BTC/USDT")
+            .with_failing_cancel("1"),
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let (bot, sent, _file, _token) = trading_bot(exchange.clone(), &dir, &["1"]).await;
+
+    bot.stop().await.unwrap();
+
+    assert_eq!(exchange.cancel_requests(), vec!["1"]);
+    let message = shutdown_message(&sent);
+    assert!(message.contains("Причина: stopped"), "{}", message);
+    assert!(message.contains("Forced"), "{}", message);
+}
+
+#[tokio::test]
+async fn test_stop_cancels_nothing_in_a_dry_run() {
+    let exchange = Arc::new(MockExchange::new("mock").with_open_order("1", "BTC/USDT"));
+    let dir = tempfile::tempdir().unwrap();
+    let (mut bot, sent, _file, _token) = trading_bot(exchange.clone(), &dir, &["1"]).await;
+    bot.dry_run = true;
+
+    bot.stop().await.unwrap();
+
+    assert!(exchange.cancel_requests().is_empty());
+    assert!(shutdown_message(&sent).contains("Graceful"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_wait_for_signal_returns_on_sigterm() {
+    let waiting = tokio::spawn(wait_for_signal());
+    // Let the task install its handlers before the signal is raised
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let status = std::process::Command::new("kill")
+        .args(["-TERM", &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let signal = tokio::time::timeout(Duration::from_secs(5), waiting)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(signal, "SIGTERM");
+}
+
+/// Config with a REST fallback for books older than 1s and a 100ms detection timeout.
+const FALLBACK: &str = r#"
+orderbook:
+  max_age: 1s
+
+arbitrage:
+  detection_timeout: 100ms
+"#;
+
+/// Returns the error notifications among the sent texts.
+fn error_messages(sent: &StdMutex<Vec<String>>) -> Vec<String> {
+    sent.lock()
+        .unwrap()
+        .iter()
+        .filter(|text| text.contains("Ошибка"))
+        .cloned()
+        .collect()
+}
+
+#[tokio::test]
+async fn test_detect_pair_cuts_off_slow_exchange_and_keeps_arrived_books() {
+    let slow = Arc::new(
+        MockExchange::new("slow")
+            .with_quotes(200, 201)
+            .with_orderbook_delay(Duration::from_millis(500)),
+    );
+    let (bot, _sent, _file, _token) = running_bot(
+        vec![
+            Arc::new(MockExchange::new("a").with_quotes(99, 100)),
+            Arc::new(MockExchange::new("b").with_quotes(110, 111)),
+            slow.clone(),
+        ],
+        FALLBACK,
+    )
+    .await;
+    let detectors = Arc::clone(&*bot.detectors.read().await);
+
+    let started = Instant::now();
+    let detection = bot
+        .detect_pair(
+            "BTC/USDT",
+            &detectors,
+            &TransferCosts::default(),
+            &Controls::default(),
+            Duration::from_secs(1),
+            started + Duration::from_millis(100),
+        )
+        .await;
+    assert!(started.elapsed() < Duration::from_millis(400));
+
+    assert!(detection.timed_out);
+    assert_eq!(detection.slow, vec!["slow"]);
+    let mut finished = detection.finished.clone();
+    finished.sort();
+    assert_eq!(finished, vec!["a", "b"]);
+
+    // The books that did arrive are still searched
+    assert_eq!(detection.opportunities.len(), 1);
+    assert_eq!(detection.opportunities[0].buy_exchange, "a");
+    assert_eq!(detection.opportunities[0].sell_exchange, "b");
+
+    // The slow request was dropped rather than left running
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(slow.orderbooks_served.load(Ordering::SeqCst), 0);
+    assert!(bot.orderbooks.get_pair("BTC/USDT").await.iter().all(|b| b.exchange != "slow"));
+}
+
+#[tokio::test]
+async fn test_detect_pair_search_stops_at_the_deadline() {
+    let (bot, _sent, _file, _token) = running_bot(
+        vec![
+            Arc::new(MockExchange::new("a").with_quotes(99, 100)),
+            Arc::new(MockExchange::new("b").with_quotes(110, 111)),
+        ],
+        "",
+    )
+    .await;
+    for name in ["a", "b"] {
+        let exchange = bot.exchange_manager.get(name).await.unwrap();
+        bot.orderbooks.update(exchange.get_orderbook("BTC/USDT").await.unwrap()).await;
+    }
+    let detectors = Arc::clone(&*bot.detectors.read().await);
+
+    let detection = bot
+        .detect_pair(
+            "BTC/USDT",
+            &detectors,
+            &TransferCosts::default(),
+            &Controls::default(),
+            Duration::ZERO,
+            Instant::now(),
+        )
+        .await;
+    assert!(detection.timed_out);
+    assert!(detection.slow.is_empty());
+    assert!(detection.opportunities.is_empty());
+}
+
+#[tokio::test]
+async fn test_slow_exchange_alerts_once_after_consecutive_passes() {
+    let (bot, sent, _file, _token) = running_bot(
+        vec![
+            Arc::new(MockExchange::new("a").with_quotes(99, 100)),
+            Arc::new(
+                MockExchange::new("slow")
+                    .with_quotes(110, 111)
+                    .with_orderbook_delay(Duration::from_secs(10)),
+            ),
+        ],
+        FALLBACK,
+    )
+    .await;
+
+    for pass in 1..=SLOW_EXCHANGE_ALERT_TIMEOUTS {
+        bot.detect_and_execute(None).await;
+        // Every pair timed out, but the exchange counts once per pass
+        assert_eq!(bot.stats().await.detection_timeouts, 3 * pass as u64);
+        assert_eq!(bot.slow_exchanges.lock().await.get("slow"), Some(&pass));
+        assert!(!bot.slow_exchanges.lock().await.contains_key("a"));
+    }
+    bot.detect_and_execute(None).await;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while error_messages(&sent).is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let errors = error_messages(&sent);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("Компонент: slow"), "{}", errors[0]);
+}
+
+#[tokio::test]
+async fn test_cycle_searches_report_what_did_not_finish() {
+    let (bot, _sent, _file, _token) = running_bot(
+        Vec::new(),
+        r#"
+arbitrage:
+  triangular: {}
+  multi_hop: {}
+"#,
+    )
+    .await;
+    let detectors = Arc::clone(&*bot.detectors.read().await);
+    let mut books = Vec::new();
+    let mut fees: HashMap<String, HashMap<String, Fees>> = HashMap::new();
+    for (name, bid, ask) in [("a", 99, 100), ("b", 110, 111)] {
+        books.push(MockExchange::new(name).with_quotes(bid, ask).orderbook("BTC/USDT").unwrap());
+        fees.entry(name.to_string())
+            .or_default()
+            .insert("BTC/USDT".to_string(), Fees::new(Decimal::ZERO, Decimal::ZERO));
+    }
+
+    let detection = detect_cycles(
+        &detectors,
+        &books,
+        &fees,
+        &TransferCosts::default(),
+        None,
+        Instant::now(),
+    );
+    assert!(detection.timed_out);
+    let mut slow = detection.slow;
+    slow.sort();
+    assert_eq!(slow, vec!["a", "b", MULTI_HOP_SEARCH]);
+
+    let detection = detect_cycles(
+        &detectors,
+        &books,
+        &fees,
+        &TransferCosts::default(),
+        None,
+        Instant::now() + Duration::from_secs(60),
+    );
+    assert!(!detection.timed_out);
+    assert_eq!(detection.finished.len(), 3);
+}
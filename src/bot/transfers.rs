@@ -0,0 +1,50 @@
+//! Builds the rebalancing transfer cost model from config and exchange data.
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+use crate::config::Config;
+use crate::domain::TransferCosts;
+
+/// Builds transfer costs from fetched withdrawal fees (exchange -> asset -> fee),
+/// overlaid with configured withdrawal fees and route costs. Accounts sharing a
+/// venue are registered so that transfers between them are free.
+pub(crate) fn build_transfer_costs(
+    cfg: &Config,
+    fetched: &HashMap<String, HashMap<String, Decimal>>,
+) -> TransferCosts {
+    let config = super::transfer_config(cfg);
+    let amortization_trades = config.and_then(|c| c.amortization_trades).unwrap_or(1);
+    let mut costs = TransferCosts::new(amortization_trades);
+
+    for (id, exchange) in &cfg.exchanges {
+        costs.set_venue(id, &exchange.venue(id).to_lowercase());
+    }
+
+    for (exchange, fees) in fetched {
+        for (asset, fee) in fees {
+            costs.set_withdrawal_fee(exchange, asset, *fee);
+        }
+    }
+
+    let Some(config) = config else {
+        return costs;
+    };
+
+    for (asset, by_exchange) in &config.withdrawal_fees {
+        for (exchange, fee) in by_exchange {
+            costs.set_withdrawal_fee(exchange, asset, *fee);
+        }
+    }
+
+    for route in &config.routes {
+        costs.set_route_fee(&route.asset, &route.from, &route.to, route.fee);
+    }
+
+    for (asset, size) in &config.rebalance_sizes {
+        costs.set_rebalance_size(asset, *size);
+    }
+
+    costs
+}
@@ -0,0 +1,26 @@
+//! CLI error types.
+
+use crate::bot::BotError;
+use crate::config::ConfigError;
+use crate::exchanges::ExchangeError;
+use crate::secrets::SecretError;
+use crate::storage::StorageError;
+
+/// CLI error type.
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("config error: {0}")]
+    Config(#[from] ConfigError),
+    #[error("exchange error: {0}")]
+    Exchange(#[from] ExchangeError),
+    #[error("storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("bot error: {0}")]
+    Bot(#[from] BotError),
+    #[error("secret error: {0}")]
+    Secret(#[from] SecretError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Usage(String),
+}
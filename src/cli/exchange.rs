@@ -0,0 +1,97 @@
+//! Diagnostic commands that talk to a single exchange through the Manager.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::cli::CliError;
+use crate::config::Config;
+use crate::exchanges::{Exchange, Manager};
+
+/// Prints orderbook updates for the pairs until `count` have been received.
+pub async fn stream_books(
+    config: &Config,
+    name: &str,
+    pairs: Vec<String>,
+    count: usize,
+) -> Result<(), CliError> {
+    let exchange = connect(config, name).await?;
+    let mut rx = exchange.subscribe_orderbook(pairs).await?;
+
+    let mut received = 0;
+    while received < count {
+        let Some(orderbook) = rx.recv().await else {
+            break;
+        };
+
+        println!(
+            "{} {} bid={} ask={} spread={}",
+            orderbook.exchange,
+            orderbook.pair,
+            display(orderbook.best_bid().map(|l| l.price)),
+            display(orderbook.best_ask().map(|l| l.price)),
+            display(orderbook.spread()),
+        );
+        received += 1;
+    }
+
+    exchange.disconnect().await?;
+
+    if received < count {
+        return Err(CliError::Usage(format!(
+            "orderbook stream ended after {} of {} updates",
+            received, count
+        )));
+    }
+    Ok(())
+}
+
+/// Prints the non-zero balances of the exchange account, by asset.
+pub async fn balances(config: &Config, name: &str) -> Result<(), CliError> {
+    let exchange = connect(config, name).await?;
+    let balances = exchange.get_balances().await;
+    exchange.disconnect().await?;
+
+    for (asset, amount) in balances?.into_iter().collect::<BTreeMap<_, _>>() {
+        println!("{}\t{}", asset, amount);
+    }
+    Ok(())
+}
+
+/// Connects to the exchange and prints how long it took.
+pub async fn ping(config: &Config, name: &str) -> Result<(), CliError> {
+    let started = Instant::now();
+    let exchange = connect(config, name).await?;
+    let elapsed = started.elapsed();
+    exchange.disconnect().await?;
+
+    println!("{}: connected in {:?}", exchange.name(), elapsed);
+    Ok(())
+}
+
+/// Creates the named account, if enabled in the config, and connects it.
+async fn connect(config: &Config, name: &str) -> Result<Arc<dyn Exchange>, CliError> {
+    if !config.exchanges.get(name).is_some_and(|exchange| exchange.enabled) {
+        let mut available: Vec<&str> = config
+            .exchanges
+            .iter()
+            .filter(|(_, exchange)| exchange.enabled)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        available.sort();
+        return Err(CliError::Usage(format!(
+            "exchange {} is not enabled (available: {})",
+            name,
+            available.join(", ")
+        )));
+    }
+
+    let exchange = Manager::create_account(config, name)?;
+    exchange.connect().await?;
+    Ok(exchange)
+}
+
+/// Formats an optional value, with "-" for None.
+fn display(value: Option<impl ToString>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string())
+}
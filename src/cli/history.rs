@@ -0,0 +1,39 @@
+//! Export of stored opportunity history.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::cli::{CliError, HistoryArgs};
+use crate::config::Config;
+use crate::storage::{self, OpportunityQuery, OpportunityWriter, SortKey, SortOrder};
+
+/// Streams the opportunities matching the filters to the output.
+/// Returns the number of opportunities exported.
+///
+/// The database is opened read-only: exporting never creates or migrates it.
+pub async fn export_history(config: &Config, args: &HistoryArgs) -> Result<u64, CliError> {
+    let storage_cfg = config
+        .storage
+        .as_ref()
+        .ok_or_else(|| CliError::Usage("storage is not configured".to_string()))?;
+
+    let mut query = OpportunityQuery::new()
+        .with_time_range(args.from, args.to)
+        .with_sort(SortKey::DetectedAt, SortOrder::Ascending);
+    query.pair = args.pair.clone();
+    query.buy_exchange = args.buy_exchange.clone();
+    query.sell_exchange = args.sell_exchange.clone();
+
+    let storage = storage::open_read_only(storage_cfg).await?;
+
+    let output: Box<dyn Write> = match args.output {
+        Some(ref path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let mut writer = OpportunityWriter::new(output, args.format)?;
+    let exported = storage::export_opportunities(storage.as_ref(), &query, &mut writer).await;
+    storage.close().await?;
+
+    Ok(exported?)
+}
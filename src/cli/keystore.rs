@@ -0,0 +1,49 @@
+//! Management of the encrypted keystore.
+
+use std::env;
+use std::io::{self, Read};
+
+use zeroize::Zeroizing;
+
+use crate::cli::{CliError, KeystoreAction, KeystoreArgs};
+use crate::secrets::{KEYSTORE_PASSPHRASE_VAR, Keystore, Secret};
+
+/// Runs a keystore action with the passphrase from the environment.
+pub fn keystore(args: &KeystoreArgs) -> Result<(), CliError> {
+    // Load .env file if it exists (ignore error if not found)
+    dotenvy::dotenv().ok();
+    let passphrase = Secret::new(env::var(KEYSTORE_PASSPHRASE_VAR).unwrap_or_default());
+
+    match args.action {
+        KeystoreAction::Set { ref name } => {
+            let mut keystore = Keystore::open_or_create(&args.path, &passphrase)?;
+
+            let mut value = Zeroizing::new(String::new());
+            io::stdin().read_to_string(&mut value)?;
+            let value = Secret::new(value.trim_end_matches(['\r', '\n']));
+            if value.is_empty() {
+                return Err(CliError::Usage("no secret given on stdin".to_string()));
+            }
+
+            keystore.set(name, &value)?;
+            keystore.save()?;
+            eprintln!("Stored {} in {}", name, args.path);
+        }
+        KeystoreAction::Remove { ref name } => {
+            let mut keystore = Keystore::open(&args.path, &passphrase)?;
+            if !keystore.remove(name) {
+                return Err(CliError::Usage(format!("{} is not in {}", name, args.path)));
+            }
+            keystore.save()?;
+            eprintln!("Removed {} from {}", name, args.path);
+        }
+        KeystoreAction::List => {
+            let keystore = Keystore::open(&args.path, &passphrase)?;
+            for name in keystore.names() {
+                println!("{}", name);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! Command-line interface: argument definitions and diagnostic commands.
+
+mod error;
+mod exchange;
+mod history;
+mod keystore;
+
+pub use error::CliError;
+pub use exchange::{balances, ping, stream_books};
+pub use history::export_history;
+pub use keystore::keystore;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand};
+
+use crate::config::ArchiveFormat;
+
+/// Default path of the configuration file.
+pub const DEFAULT_CONFIG_PATH: &str = "configs/config.yaml";
+
+/// Cryptocurrency arbitrage bot.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Path to the configuration file.
+    #[arg(long, global = true, default_value = DEFAULT_CONFIG_PATH)]
+    pub config: String,
+
+    /// Command to run (default: run).
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Top-level commands.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Runs the arbitrage bot.
+    Run,
+    /// Validates the configuration and prints it with every problem found.
+    CheckConfig,
+    /// Prints orderbook updates streamed from an exchange.
+    StreamBooks {
+        /// Exchange name as configured (e.g., "poloniex").
+        #[arg(long)]
+        exchange: String,
+        /// Trading pair in "BASE/QUOTE" format; repeat for several pairs.
+        #[arg(long, required = true)]
+        pair: Vec<String>,
+        /// Number of updates to print before exiting.
+        #[arg(long, default_value_t = 100)]
+        count: usize,
+    },
+    /// Prints the non-zero balances of an exchange account.
+    Balances {
+        /// Exchange name as configured.
+        #[arg(long)]
+        exchange: String,
+    },
+    /// Checks connectivity to an exchange and reports the round trip.
+    Ping {
+        /// Exchange name as configured.
+        #[arg(long)]
+        exchange: String,
+    },
+    /// Exports stored opportunity history, oldest first.
+    History(HistoryArgs),
+    /// Manages the encrypted keystore; the passphrase is read from
+    /// KEYSTORE_PASSPHRASE.
+    Keystore(KeystoreArgs),
+}
+
+/// Keystore file and action.
+#[derive(Debug, Args)]
+pub struct KeystoreArgs {
+    /// Path of the keystore file.
+    #[arg(long)]
+    pub path: String,
+    /// Action to perform.
+    #[command(subcommand)]
+    pub action: KeystoreAction,
+}
+
+/// Keystore actions.
+#[derive(Debug, Subcommand)]
+pub enum KeystoreAction {
+    /// Stores a secret read from stdin, creating the keystore if needed.
+    Set {
+        /// Entry name, referenced as `keystore:NAME`.
+        name: String,
+    },
+    /// Removes a secret.
+    Remove {
+        /// Entry name.
+        name: String,
+    },
+    /// Lists the entry names.
+    List,
+}
+
+/// Filters and output of the history export.
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// Output format: jsonl or csv.
+    #[arg(long, default_value = "jsonl", value_parser = parse_format)]
+    pub format: ArchiveFormat,
+    /// File to write; stdout if omitted.
+    #[arg(long)]
+    pub output: Option<String>,
+    /// Trading pair (or asset cycle).
+    #[arg(long)]
+    pub pair: Option<String>,
+    /// Exchange bought on.
+    #[arg(long)]
+    pub buy_exchange: Option<String>,
+    /// Exchange sold on.
+    #[arg(long)]
+    pub sell_exchange: Option<String>,
+    /// Earliest detection time: RFC 3339 or YYYY-MM-DD (midnight UTC).
+    #[arg(long, value_parser = parse_date)]
+    pub from: Option<DateTime<Utc>>,
+    /// Detection time to stop before, in the same formats as `--from`.
+    #[arg(long, value_parser = parse_date)]
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Parses an export format name.
+fn parse_format(s: &str) -> Result<ArchiveFormat, String> {
+    match s {
+        "jsonl" => Ok(ArchiveFormat::Jsonl),
+        "csv" => Ok(ArchiveFormat::Csv),
+        _ => Err(format!("unknown format: {} (expected jsonl or csv)", s)),
+    }
+}
+
+/// Parses an RFC 3339 timestamp or a `YYYY-MM-DD` date at midnight UTC.
+fn parse_date(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("invalid date: {}", s))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,95 @@
+//! Tests for CLI argument parsing.
+
+use super::*;
+use clap::CommandFactory;
+
+#[test]
+fn test_cli_definition_is_valid() {
+    Cli::command().debug_assert();
+}
+
+#[test]
+fn test_no_command_runs_bot_with_default_config() {
+    let cli = Cli::try_parse_from(["bot"]).unwrap();
+    assert!(cli.command.is_none());
+    assert_eq!(cli.config, DEFAULT_CONFIG_PATH);
+}
+
+#[test]
+fn test_config_is_global() {
+    let cli = Cli::try_parse_from(["bot", "ping", "--exchange", "gate", "--config=prod.yaml"])
+        .unwrap();
+    assert_eq!(cli.config, "prod.yaml");
+    assert!(matches!(cli.command, Some(Command::Ping { ref exchange }) if exchange == "gate"));
+}
+
+#[test]
+fn test_stream_books_requires_pair() {
+    assert!(Cli::try_parse_from(["bot", "stream-books", "--exchange", "poloniex"]).is_err());
+
+    let cli = Cli::try_parse_from([
+        "bot",
+        "stream-books",
+        "--exchange",
+        "poloniex",
+        "--pair",
+        "BTC/USDT",
+        "--pair",
+        "ETH/USDT",
+    ])
+    .unwrap();
+    match cli.command {
+        Some(Command::StreamBooks { pair, count, .. }) => {
+            assert_eq!(pair, ["BTC/USDT", "ETH/USDT"]);
+            assert_eq!(count, 100);
+        }
+        other => panic!("unexpected command: {:?}", other),
+    }
+}
+
+#[test]
+fn test_history_parses_filters() {
+    let cli = Cli::try_parse_from([
+        "bot",
+        "history",
+        "--format",
+        "csv",
+        "--pair",
+        "BTC/USDT",
+        "--from",
+        "2024-01-01",
+        "--to",
+        "2024-01-02T12:00:00+02:00",
+    ])
+    .unwrap();
+    let Some(Command::History(args)) = cli.command else {
+        panic!("expected history command");
+    };
+
+    assert_eq!(args.format, ArchiveFormat::Csv);
+    assert_eq!(args.pair.as_deref(), Some("BTC/USDT"));
+    assert_eq!(args.from.unwrap().to_rfc3339(), "2024-01-01T00:00:00+00:00");
+    assert_eq!(args.to.unwrap().to_rfc3339(), "2024-01-02T10:00:00+00:00");
+    assert!(args.output.is_none());
+}
+
+#[test]
+fn test_history_rejects_unknown_format() {
+    assert!(Cli::try_parse_from(["bot", "history", "--format", "xml"]).is_err());
+    assert!(Cli::try_parse_from(["bot", "history", "--from", "yesterday"]).is_err());
+}
+
+#[test]
+fn test_keystore_requires_path_and_action() {
+    assert!(Cli::try_parse_from(["bot", "keystore", "list"]).is_err());
+    assert!(Cli::try_parse_from(["bot", "keystore", "--path", "ks.json"]).is_err());
+
+    let cli =
+        Cli::try_parse_from(["bot", "keystore", "--path", "ks.json", "set", "poloniex_api_key"])
+            .unwrap();
+    let Some(Command::Keystore(args)) = cli.command else {
+        panic!("expected keystore command");
+    };
+    assert_eq!(args.path, "ks.json");
+    assert!(matches!(args.action, KeystoreAction::Set { ref name } if name == "poloniex_api_key"));
+}
@@ -0,0 +1,28 @@
+//! Control API configuration.
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+use super::redact;
+use crate::secrets::Secret;
+
+/// Default address of the control API.
+pub const DEFAULT_API_LISTEN: &str = "127.0.0.1:9185";
+
+/// HTTP control and status API settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiConfig {
+    /// Whether the API is served.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Address the API listens on (default: 127.0.0.1:9185).
+    #[serde(default = "default_listen")]
+    pub listen: SocketAddr,
+    /// Bearer token reference required on every request (default: `env:API_TOKEN`).
+    #[serde(default, serialize_with = "redact::secret")]
+    pub token: Secret,
+}
+
+fn default_listen() -> SocketAddr {
+    DEFAULT_API_LISTEN.parse().expect("valid default address")
+}
@@ -1,14 +1,46 @@
 //! Application-level configuration.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+/// Deployment environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppEnv {
+    /// Local development: dry run, credentials optional.
+    #[serde(alias = "dev")]
+    Development,
+    /// Pre-production: trades for real, credentials required.
+    #[serde(alias = "stage")]
+    Staging,
+    /// Production: trades for real, credentials required.
+    #[serde(alias = "prod")]
+    Production,
+}
+
+impl AppEnv {
+    /// Returns true if the bot only simulates trading.
+    pub fn is_development(self) -> bool {
+        self == AppEnv::Development
+    }
+}
+
+impl std::fmt::Display for AppEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppEnv::Development => write!(f, "development"),
+            AppEnv::Staging => write!(f, "staging"),
+            AppEnv::Production => write!(f, "production"),
+        }
+    }
+}
 
 /// Application-level settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AppConfig {
     /// Application name used in logs and metrics.
     pub name: String,
     /// Environment: "development", "staging", or "production".
-    pub env: String,
+    pub env: AppEnv,
     /// Logging verbosity: "debug", "info", "warn", "error".
     pub log_level: Option<String>,
 }
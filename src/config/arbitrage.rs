@@ -1,28 +1,116 @@
 //! Arbitrage detection configuration.
 
-use serde::Deserialize;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-use super::duration;
+use super::transfer::TransferConfig;
+use super::{decimal, duration, percent};
+
+/// Default timeout for each detection cycle.
+pub const DEFAULT_DETECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default minimum time between two detections of the same pair.
+pub const DEFAULT_MIN_REEVALUATION_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Default interval of the full detection pass run regardless of book updates.
+pub const DEFAULT_FALLBACK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default time an opportunity is considered valid.
+pub const DEFAULT_OPPORTUNITY_TTL: Duration = Duration::from_secs(5);
+
+/// Default maximum number of conversions in a multi-hop cycle.
+pub const DEFAULT_MAX_HOPS: usize = 4;
 
 /// Arbitrage detection settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ArbitrageConfig {
     /// Cross-exchange arbitrage detection (optional).
     pub cross_exchange: Option<CrossExchangeConfig>,
+    /// Triangular arbitrage detection within one exchange (optional).
+    pub triangular: Option<TriangularConfig>,
+    /// Multi-hop path search across exchanges (optional).
+    pub multi_hop: Option<MultiHopConfig>,
     /// Timeout for each detection cycle (default: 10s).
-    #[serde(default, with = "duration")]
+    #[serde(default = "default_detection_timeout", with = "duration")]
     pub detection_timeout: Duration,
+    /// Minimum time between two detections of the same pair (default: 20ms).
+    /// Book updates arriving in between are coalesced into one detection.
+    #[serde(default = "default_min_reevaluation_interval", with = "duration")]
+    pub min_reevaluation_interval: Duration,
+    /// Interval of the full detection pass that runs even when no book
+    /// changes, e.g. to notice books going stale (default: 1s).
+    #[serde(default = "default_fallback_interval", with = "duration")]
+    pub fallback_interval: Duration,
+    /// Rebalancing transfer costs (optional).
+    pub transfer: Option<TransferConfig>,
 }
 
 /// Cross-exchange arbitrage settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CrossExchangeConfig {
-    /// Minimum profit percentage to trigger (e.g., "0.003" for 0.3%).
-    pub min_profit_threshold: Option<String>,
-    /// Minimum quantity to trade (e.g., "0.0001").
-    pub min_quantity: Option<String>,
+    /// Minimum profit to trigger, as a fraction or percentage (e.g., "0.003" or "0.3%"; default: 0).
+    #[serde(default, with = "percent")]
+    pub min_profit_threshold: Decimal,
+    /// Minimum quantity to trade (e.g., "0.0001"; default: 0).
+    #[serde(default, with = "decimal")]
+    pub min_quantity: Decimal,
+    /// How long an opportunity is considered valid (default: 5s).
+    #[serde(default = "default_opportunity_ttl", with = "duration")]
+    pub opportunity_ttl: Duration,
+}
+
+/// Triangular arbitrage settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TriangularConfig {
+    /// Minimum profit to trigger, as a fraction or percentage (e.g., "0.002" or "0.2%"; default: 0).
+    #[serde(default, with = "percent")]
+    pub min_profit_threshold: Decimal,
+    /// Assets a cycle may start and end in (default: the alphabetically first
+    /// asset of each cycle).
+    #[serde(default)]
+    pub start_assets: Vec<String>,
     /// How long an opportunity is considered valid (default: 5s).
-    #[serde(default, with = "duration")]
+    #[serde(default = "default_opportunity_ttl", with = "duration")]
     pub opportunity_ttl: Duration,
 }
+
+/// Multi-hop arbitrage settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MultiHopConfig {
+    /// Maximum number of conversions in a cycle (default: 4).
+    #[serde(default = "default_max_hops")]
+    pub max_hops: usize,
+    /// Minimum profit to trigger, as a fraction or percentage (e.g., "0.003" or "0.3%"; default: 0).
+    #[serde(default, with = "percent")]
+    pub min_profit_threshold: Decimal,
+    /// Assets a cycle may start and end in (default: any asset).
+    #[serde(default)]
+    pub start_assets: Vec<String>,
+    /// Allow moving an asset between exchanges as part of a cycle (default: false).
+    #[serde(default)]
+    pub include_transfers: bool,
+    /// How long an opportunity is considered valid (default: 5s).
+    #[serde(default = "default_opportunity_ttl", with = "duration")]
+    pub opportunity_ttl: Duration,
+}
+
+fn default_detection_timeout() -> Duration {
+    DEFAULT_DETECTION_TIMEOUT
+}
+
+fn default_min_reevaluation_interval() -> Duration {
+    DEFAULT_MIN_REEVALUATION_INTERVAL
+}
+
+fn default_fallback_interval() -> Duration {
+    DEFAULT_FALLBACK_INTERVAL
+}
+
+fn default_opportunity_ttl() -> Duration {
+    DEFAULT_OPPORTUNITY_TTL
+}
+
+fn default_max_hops() -> usize {
+    DEFAULT_MAX_HOPS
+}
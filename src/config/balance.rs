@@ -1,21 +1,27 @@
 //! Balance configuration.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 use super::duration;
 
+/// Default interval between balance syncs.
+pub const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default maximum age of balance data.
+pub const DEFAULT_BALANCE_MAX_AGE: Duration = Duration::from_secs(60);
+
 /// Balance caching and sync settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BalanceConfig {
     /// Whether balance caching and sync should be used.
     #[serde(default = "default_true")]
     pub enabled: bool,
     /// Interval for periodic balance sync with exchanges (default: 30s).
-    #[serde(default, with = "duration")]
+    #[serde(default = "default_sync_interval", with = "duration")]
     pub sync_interval: Duration,
     /// Maximum age of balance data before it's considered stale (default: 60s).
-    #[serde(default, with = "duration")]
+    #[serde(default = "default_max_age", with = "duration")]
     pub max_age: Duration,
     /// Enable balance sync after each trade execution (default: true).
     #[serde(default = "default_true")]
@@ -25,3 +31,11 @@ pub struct BalanceConfig {
 fn default_true() -> bool {
     true
 }
+
+fn default_sync_interval() -> Duration {
+    DEFAULT_SYNC_INTERVAL
+}
+
+fn default_max_age() -> Duration {
+    DEFAULT_BALANCE_MAX_AGE
+}
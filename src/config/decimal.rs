@@ -0,0 +1,103 @@
+//! Custom serde module for decimals written as strings or plain numbers.
+//!
+//! Values are read as text so "0.0010" keeps its digits; unquoted YAML numbers
+//! are accepted too. Parse errors are reported at load time with the YAML path.
+
+use rust_decimal::Decimal;
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_with(deserializer, parse_decimal)
+}
+
+pub fn serialize<S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.to_string())
+}
+
+pub(crate) fn parse_decimal(s: &str) -> Result<Decimal, String> {
+    Decimal::from_str(s.trim()).map_err(|_| format!("invalid decimal: {:?}", s))
+}
+
+/// Parses a string or number with `parse`.
+///
+/// Parsing happens inside the visitor so that errors carry the YAML path.
+pub(super) fn deserialize_with<'de, D>(deserializer: D, parse: Parse) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(DecimalVisitor(parse))
+}
+
+/// Same as [`deserialize_with`], with null or a missing value as None.
+pub(super) fn deserialize_option_with<'de, D>(
+    deserializer: D,
+    parse: Parse,
+) -> Result<Option<Decimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(OptionVisitor(parse))
+}
+
+type Parse = fn(&str) -> Result<Decimal, String>;
+
+struct DecimalVisitor(Parse);
+
+impl Visitor<'_> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a number or a numeric string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Decimal, E> {
+        (self.0)(v).map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Decimal, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Decimal, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    // Goes through the shortest round-trip text, so 0.001 stays exactly 0.001
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Decimal, E> {
+        self.visit_str(&v.to_string())
+    }
+}
+
+struct OptionVisitor(Parse);
+
+impl<'de> Visitor<'de> for OptionVisitor {
+    type Value = Option<Decimal>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a number, a numeric string or null")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Option<Decimal>, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Option<Decimal>, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Option<Decimal>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DecimalVisitor(self.0)).map(Some)
+    }
+}
@@ -0,0 +1,329 @@
+//! Exhaustive configuration checks.
+//!
+//! Every problem is collected rather than stopping at the first one, so a
+//! single `check-config` run reports everything that needs fixing. Errors make
+//! the configuration unusable; warnings flag settings that load but are
+//! probably not what was meant.
+
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+use super::{Config, StorageBackend, env_prefix};
+use crate::exchanges::SUPPORTED_VENUES;
+
+/// How serious a configuration problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The configuration cannot be used.
+    Error,
+    /// The configuration loads but a setting is likely wrong.
+    Warning,
+}
+
+/// A single configuration problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// Description, prefixed with the path of the offending setting.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Returns true if the problem makes the configuration unusable.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+/// Collects diagnostics while the configuration is walked.
+#[derive(Default)]
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn error(&mut self, message: impl Into<String>) {
+        self.0.push(Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+        });
+    }
+
+    fn warning(&mut self, message: impl Into<String>) {
+        self.0.push(Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+        });
+    }
+
+    /// Checks that a decimal, if set, is not negative.
+    fn non_negative(&mut self, path: &str, value: impl Into<Option<Decimal>>) {
+        if let Some(value) = value.into()
+            && value.is_sign_negative()
+        {
+            self.error(format!("{}: must not be negative, got {}", path, value));
+        }
+    }
+
+    /// Checks that a duration is not zero.
+    fn positive(&mut self, path: &str, value: Duration) {
+        if value.is_zero() {
+            self.error(format!("{}: must be positive", path));
+        }
+    }
+
+    /// Checks that a pair has the "BASE/QUOTE" format.
+    fn pair(&mut self, path: &str, pair: &str) {
+        if !is_valid_pair(pair) {
+            self.error(format!(
+                "{}: malformed pair \"{}\" (expected BASE/QUOTE)",
+                path, pair
+            ));
+        }
+    }
+}
+
+impl Config {
+    /// Checks the whole configuration and returns every problem found.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut d = Diagnostics::default();
+
+        if self.app.name.is_empty() {
+            d.error("app.name is required");
+        }
+
+        if self.pairs.is_empty() {
+            d.error("at least one trading pair is required");
+        }
+        let mut seen = HashSet::new();
+        for pair in &self.pairs {
+            d.pair("pairs", pair);
+            if !seen.insert(pair) {
+                d.warning(format!("pairs: {} is listed more than once", pair));
+            }
+        }
+
+        let mut names: Vec<&String> = self.exchanges.keys().collect();
+        names.sort();
+
+        let mut enabled_exchanges = 0;
+        let mut key_owners: HashMap<&str, &String> = HashMap::new();
+        for name in names {
+            let exchange = &self.exchanges[name];
+            if !exchange.enabled {
+                continue;
+            }
+            enabled_exchanges += 1;
+
+            // The bot refuses to start with an account it cannot create
+            if !SUPPORTED_VENUES.contains(&exchange.venue(name).to_lowercase().as_str()) {
+                let problem = match exchange.exchange {
+                    Some(ref venue) => {
+                        format!("exchanges.{}.exchange: unknown exchange \"{}\"", name, venue)
+                    }
+                    None => format!("exchanges.{}: unknown exchange", name),
+                };
+                d.error(format!(
+                    "{} (supported: {})",
+                    problem,
+                    SUPPORTED_VENUES.join(", ")
+                ));
+            }
+
+            // Two accounts with one key would count the same inventory twice
+            if !exchange.api_key.is_empty()
+                && let Some(other) = key_owners.insert(exchange.api_key.expose(), name)
+            {
+                d.error(format!(
+                    "exchanges.{}: uses the same API key as exchanges.{}",
+                    name, other
+                ));
+            }
+
+            if exchange.fee_taker.is_none() {
+                d.error(format!("exchange {}: fee_taker is required", name));
+            }
+            d.non_negative(&format!("exchanges.{}.fee_taker", name), exchange.fee_taker);
+            d.non_negative(&format!("exchanges.{}.fee_maker", name), exchange.fee_maker);
+
+            if let Some(ref fees) = exchange.fees {
+                d.positive(
+                    &format!("exchanges.{}.fees.refresh_interval", name),
+                    fees.refresh_interval,
+                );
+
+                let mut pairs: Vec<&String> = fees.overrides.keys().collect();
+                pairs.sort();
+                for pair in pairs {
+                    let path = format!("exchanges.{}.fees.overrides", name);
+                    d.pair(&path, pair);
+                    let fee = &fees.overrides[pair];
+                    d.non_negative(&format!("{}.{}.maker", path, pair), fee.maker);
+                    d.non_negative(&format!("{}.{}.taker", path, pair), fee.taker);
+                }
+                for (i, discount) in fees.discounts.iter().enumerate() {
+                    let path = format!("exchanges.{}.fees.discounts[{}]", name, i);
+                    d.non_negative(&format!("{}.min_balance", path), discount.min_balance);
+                    d.non_negative(&format!("{}.multiplier", path), discount.multiplier);
+                }
+            }
+
+            if let Some(ref websocket) = exchange.websocket {
+                d.positive(
+                    &format!("exchanges.{}.websocket.ping_interval", name),
+                    websocket.ping_interval,
+                );
+            }
+
+            // Only require credentials in production/staging
+            if !self.app.env.is_development()
+                && (exchange.api_key.is_empty() || exchange.api_secret.is_empty())
+            {
+                let prefix = env_prefix(name);
+                d.error(format!(
+                    "exchange {}: API credentials not found (set {}_API_KEY and {}_API_SECRET env vars)",
+                    name, prefix, prefix
+                ));
+            }
+        }
+
+        if enabled_exchanges == 0 {
+            d.error("at least one exchange must be enabled");
+        }
+
+        if let Some(ref arbitrage) = self.arbitrage {
+            d.positive("arbitrage.detection_timeout", arbitrage.detection_timeout);
+            d.positive("arbitrage.fallback_interval", arbitrage.fallback_interval);
+
+            if let Some(ref cross) = arbitrage.cross_exchange {
+                d.non_negative(
+                    "arbitrage.cross_exchange.min_quantity",
+                    cross.min_quantity,
+                );
+                d.positive(
+                    "arbitrage.cross_exchange.opportunity_ttl",
+                    cross.opportunity_ttl,
+                );
+            }
+            if let Some(ref triangular) = arbitrage.triangular {
+                d.positive(
+                    "arbitrage.triangular.opportunity_ttl",
+                    triangular.opportunity_ttl,
+                );
+            }
+            if let Some(ref multi_hop) = arbitrage.multi_hop {
+                if multi_hop.max_hops < 2 {
+                    d.error("arbitrage.multi_hop.max_hops must be at least 2");
+                }
+                d.positive(
+                    "arbitrage.multi_hop.opportunity_ttl",
+                    multi_hop.opportunity_ttl,
+                );
+            }
+            if let Some(ref transfer) = arbitrage.transfer {
+                let mut assets: Vec<&String> = transfer.withdrawal_fees.keys().collect();
+                assets.sort();
+                for asset in assets {
+                    let by_exchange = &transfer.withdrawal_fees[asset];
+                    let mut exchanges: Vec<&String> = by_exchange.keys().collect();
+                    exchanges.sort();
+                    for exchange in exchanges {
+                        d.non_negative(
+                            &format!("arbitrage.transfer.withdrawal_fees.{}.{}", asset, exchange),
+                            by_exchange[exchange],
+                        );
+                    }
+                }
+                for (i, route) in transfer.routes.iter().enumerate() {
+                    d.non_negative(&format!("arbitrage.transfer.routes[{}].fee", i), route.fee);
+                }
+            }
+        }
+
+        if let Some(telegram) = self.notification.as_ref().and_then(|n| n.telegram.as_ref())
+            && telegram.enabled
+        {
+            d.positive(
+                "notification.telegram.overview_interval",
+                telegram.overview_interval,
+            );
+        }
+
+        if let Some(ref storage) = self.storage
+            && storage.enabled
+            && storage.backend == StorageBackend::Postgres
+            && storage.url.as_deref().unwrap_or_default().is_empty()
+        {
+            d.error("storage.url is required for the postgres backend (or set DATABASE_URL)");
+        }
+
+        if let Some(retention) = self.storage.as_ref().and_then(|s| s.retention.as_ref()) {
+            if retention.max_age.is_zero() && retention.max_rows.is_none() {
+                d.error("storage.retention requires max_age or max_rows");
+            }
+            if retention.max_rows == Some(0) {
+                d.error("storage.retention.max_rows must be positive");
+            }
+            d.positive("storage.retention.interval", retention.interval);
+        }
+
+        if let Some(ref metrics) = self.metrics
+            && metrics.enabled
+            && !metrics.listen.ip().is_loopback()
+        {
+            d.warning(format!(
+                "metrics.listen: {} exposes metrics beyond this host",
+                metrics.listen
+            ));
+        }
+
+        if let Some(ref api) = self.api
+            && api.enabled
+        {
+            if api.token.is_empty() {
+                d.error("api.token is required when the API is enabled (or set API_TOKEN)");
+            }
+            if !api.listen.ip().is_loopback() {
+                d.warning(format!(
+                    "api.listen: {} exposes the control API beyond this host",
+                    api.listen
+                ));
+            }
+        }
+
+        if let Some(ref risk) = self.risk {
+            d.non_negative(
+                "risk.max_position_per_exchange",
+                risk.max_position_per_exchange,
+            );
+            d.non_negative("risk.daily_loss_limit", risk.daily_loss_limit);
+            d.non_negative("risk.kill_switch_drawdown", risk.kill_switch_drawdown);
+            if let Some(max_open_orders) = risk.max_open_orders
+                && max_open_orders <= 0
+            {
+                d.error("risk.max_open_orders must be positive");
+            }
+        }
+
+        d.0
+    }
+}
+
+/// Returns true if the pair is two non-empty alphanumeric assets joined by "/".
+fn is_valid_pair(pair: &str) -> bool {
+    let valid_asset = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric());
+    match pair.split_once('/') {
+        Some((base, quote)) => valid_asset(base) && valid_asset(quote) && base != quote,
+        None => false,
+    }
+}
@@ -1,16 +1,72 @@
 //! Custom serde module for parsing duration strings like "30s", "5m", "1h".
 
-use serde::{self, Deserialize, Deserializer};
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+use std::fmt;
 use std::time::Duration;
 
 pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s: Option<String> = Option::deserialize(deserializer)?;
-    match s {
-        Some(s) => parse_duration(&s).map_err(serde::de::Error::custom),
-        None => Ok(Duration::ZERO),
+    deserializer.deserialize_option(DurationVisitor)
+}
+
+/// Parses inside the visitor so that errors carry the YAML path.
+struct DurationVisitor;
+
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a duration like \"30s\", \"5m\" or \"1h\"")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Duration, E> {
+        Ok(Duration::ZERO)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Duration, E> {
+        Ok(Duration::ZERO)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Duration, E> {
+        parse_duration(v).map_err(E::custom)
+    }
+}
+
+/// Serializes a duration in the largest unit that represents it exactly.
+pub fn serialize<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format_duration(*value))
+}
+
+pub(crate) fn format_duration(value: Duration) -> String {
+    if value.is_zero() {
+        return "0s".to_string();
+    }
+    if !value.subsec_nanos().is_multiple_of(1_000_000) {
+        return format!("{:?}", value);
+    }
+
+    let millis = value.as_millis();
+    if millis.is_multiple_of(3_600_000) {
+        format!("{}h", millis / 3_600_000)
+    } else if millis.is_multiple_of(60_000) {
+        format!("{}m", millis / 60_000)
+    } else if millis.is_multiple_of(1000) {
+        format!("{}s", millis / 1000)
+    } else {
+        format!("{}ms", millis)
     }
 }
 
@@ -9,6 +9,10 @@ pub enum ConfigError {
     ReadFile(#[from] std::io::Error),
     #[error("failed to parse config: {0}")]
     Parse(#[from] serde_yaml::Error),
+    #[error("invalid environment override {0}")]
+    EnvOverride(String),
+    #[error("failed to resolve secrets: {0}")]
+    Secrets(String),
     #[error("validation failed: {0}")]
     Validation(String),
 }
@@ -1,43 +1,90 @@
 //! Exchange configuration.
 
-use serde::Deserialize;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-use super::duration;
+use super::fees::FeeConfig;
+use super::{duration, percent, redact};
+use crate::secrets::Secret;
 
-/// Settings for a single exchange.
-#[derive(Debug, Clone, Deserialize)]
+/// Default interval between WebSocket pings. Poloniex requires a message or
+/// ping every 30 seconds; 20 seconds leaves a margin.
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Default delay before reconnecting a dropped WebSocket.
+pub const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Settings for a single exchange account.
+///
+/// The key under `exchanges` is the account id. Several accounts may trade on
+/// the same venue (e.g. Poloniex sub-accounts), each with its own credentials
+/// and inventory.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ExchangeConfig {
     /// Whether this exchange should be used.
     #[serde(default)]
     pub enabled: bool,
+    /// Venue this account trades on (default: the account id).
+    #[serde(default)]
+    pub exchange: Option<String>,
     /// Enable testnet/sandbox mode.
     #[serde(default)]
     pub testnet: bool,
-    /// API key (loaded from environment variable).
-    #[serde(skip)]
-    pub api_key: String,
-    /// API secret (loaded from environment variable).
-    #[serde(skip)]
-    pub api_secret: String,
-    /// Taker fee as a decimal string (e.g., "0.001" for 0.1%).
-    pub fee_taker: Option<String>,
+    /// API key reference (default: `env:{ID}_API_KEY`).
+    #[serde(default, serialize_with = "redact::secret")]
+    pub api_key: Secret,
+    /// API secret reference (default: `env:{ID}_API_SECRET`).
+    #[serde(default, serialize_with = "redact::secret")]
+    pub api_secret: Secret,
+    /// Taker fee as a fraction or percentage (e.g., "0.001" or "0.1%").
+    #[serde(default, with = "percent::option")]
+    pub fee_taker: Option<Decimal>,
+    /// Maker fee as a fraction or percentage (defaults to `fee_taker`).
+    #[serde(default, with = "percent::option")]
+    pub fee_maker: Option<Decimal>,
+    /// Fee refresh, per-pair overrides and conditional discounts (optional).
+    pub fees: Option<FeeConfig>,
     /// Maximum API requests per minute.
     pub rate_limit: Option<i64>,
     /// WebSocket connection settings.
     pub websocket: Option<WebSocketConfig>,
 }
 
+impl ExchangeConfig {
+    /// Returns the venue of the account configured under `id`.
+    pub fn venue<'a>(&'a self, id: &'a str) -> &'a str {
+        self.exchange.as_deref().unwrap_or(id)
+    }
+}
+
+/// Returns the prefix of the default credential variables of an account:
+/// the id uppercased, with anything but letters and digits replaced by `_`
+/// (e.g. "poloniex-sub1" -> "POLONIEX_SUB1").
+pub fn env_prefix(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
 /// WebSocket connection settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WebSocketConfig {
     /// Whether WebSocket should be used for real-time data.
     #[serde(default)]
     pub enabled: bool,
-    /// Interval between ping messages to keep connection alive.
-    #[serde(default, with = "duration")]
+    /// Interval between ping messages to keep connection alive (default: 20s).
+    #[serde(default = "default_ping_interval", with = "duration")]
     pub ping_interval: Duration,
-    /// Delay before attempting to reconnect after disconnection.
-    #[serde(default, with = "duration")]
+    /// Delay before attempting to reconnect after disconnection (default: 5s).
+    #[serde(default = "default_reconnect_delay", with = "duration")]
     pub reconnect_delay: Duration,
 }
+
+fn default_ping_interval() -> Duration {
+    DEFAULT_PING_INTERVAL
+}
+
+fn default_reconnect_delay() -> Duration {
+    DEFAULT_RECONNECT_DELAY
+}
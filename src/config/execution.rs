@@ -1,12 +1,12 @@
 //! Execution configuration.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 use super::duration;
 
 /// Order execution settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ExecutionConfig {
     /// Maximum time to wait for order execution.
     #[serde(default, with = "duration")]
@@ -16,7 +16,7 @@ pub struct ExecutionConfig {
 }
 
 /// Retry settings for failed operations.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RetryConfig {
     /// Maximum number of retry attempts.
     pub max_attempts: Option<i32>,
@@ -0,0 +1,68 @@
+//! Fee schedule configuration.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{decimal, duration, percent, sorted};
+
+/// Default interval between fee rate refreshes.
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Fee schedule settings for a single exchange.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FeeConfig {
+    /// Interval between fee rate refreshes from the exchange (default: 1h).
+    #[serde(default = "default_refresh_interval", with = "duration")]
+    pub refresh_interval: Duration,
+    /// Per-pair fee overrides keyed by pair (e.g., "BTC/USDT").
+    #[serde(default, serialize_with = "sorted::serialize")]
+    pub overrides: HashMap<String, FeeOverrideConfig>,
+    /// Conditional discounts applied on top of the resolved fees.
+    #[serde(default)]
+    pub discounts: Vec<FeeDiscountConfig>,
+}
+
+/// Fixed fees for a single pair, taking precedence over fetched rates.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FeeOverrideConfig {
+    /// Maker fee as a fraction or percentage (defaults to the resolved maker fee).
+    #[serde(default, with = "percent::option")]
+    pub maker: Option<Decimal>,
+    /// Taker fee as a fraction or percentage (defaults to the resolved taker fee).
+    #[serde(default, with = "percent::option")]
+    pub taker: Option<Decimal>,
+}
+
+/// Discount that applies while a balance condition holds
+/// (e.g., holding at least 1 GT on Gate.io).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FeeDiscountConfig {
+    /// Asset whose balance enables the discount (e.g., "GT").
+    pub asset: String,
+    /// Minimum available balance of `asset` (e.g., "1"; default: 0).
+    #[serde(default, with = "decimal")]
+    pub min_balance: Decimal,
+    /// Multiplier applied to maker and taker fees (e.g., "0.9" for 10% off; default: 1).
+    #[serde(default = "default_multiplier", with = "decimal")]
+    pub multiplier: Decimal,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            overrides: HashMap::new(),
+            discounts: Vec::new(),
+        }
+    }
+}
+
+fn default_refresh_interval() -> Duration {
+    DEFAULT_REFRESH_INTERVAL
+}
+
+fn default_multiplier() -> Decimal {
+    Decimal::ONE
+}
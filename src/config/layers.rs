@@ -0,0 +1,226 @@
+//! Layered configuration sources.
+//!
+//! The configuration is merged from, in order of precedence:
+//! 1. `APP__SECTION__KEY` environment variables, e.g. `APP__RISK__DAILY_LOSS_LIMIT=500`
+//!    (values are parsed as YAML, keys are matched case-insensitively);
+//! 2. the `{stem}.{env}.yaml` overlay next to the base file, chosen by `app.env`
+//!    (e.g. `configs/config.production.yaml`), if it exists;
+//! 3. the base file.
+//!
+//! Mappings are merged key by key; any other value replaces the lower layer's.
+//! Secrets keep their dedicated variables (see [`Config::load`](super::Config::load)).
+
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use super::{AppEnv, ConfigError, reload};
+
+/// Prefix of environment variables that override config values.
+pub const ENV_PREFIX: &str = "APP__";
+
+/// Separator between path segments in override variable names.
+const ENV_SEPARATOR: &str = "__";
+
+/// Where a configuration value was set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The base file or an environment overlay.
+    File(PathBuf),
+    /// An `APP__...` environment variable.
+    Env(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(name) => write!(f, "env {}", name),
+        }
+    }
+}
+
+/// The merged configuration tree and the layer that set each value.
+#[derive(Debug)]
+pub struct Layers {
+    value: Value,
+    /// Text of the base file while no other layer changed it.
+    base_content: Option<String>,
+    sources: BTreeMap<String, Source>,
+}
+
+impl Layers {
+    /// Reads the base file at `path`, its environment overlay and the
+    /// `APP__` overrides among `vars`.
+    pub fn read(
+        path: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ConfigError> {
+        let base = PathBuf::from(path);
+        let mut layers = Layers {
+            value: Value::Mapping(Mapping::new()),
+            base_content: None,
+            sources: BTreeMap::new(),
+        };
+
+        let mut overrides: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+            .collect();
+        overrides.sort();
+
+        let content = fs::read_to_string(&base)?;
+        layers.merge_file(&base, &content)?;
+        layers.base_content = Some(content);
+
+        // The overlay is chosen by the environment the base file and overrides select
+        let mut selected = layers.value.clone();
+        for (name, raw) in &overrides {
+            set_override(&mut selected, name, raw)?;
+        }
+        if let Some(env) = app_env(&selected) {
+            let overlay = overlay_path(path, env);
+            match fs::read_to_string(&overlay) {
+                Ok(content) => layers.merge_file(&overlay, &content)?,
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        for (name, raw) in &overrides {
+            layers.base_content = None;
+            let path = set_override(&mut layers.value, name, raw)?;
+            let mut leaves = BTreeMap::new();
+            if let Some(value) = lookup(&layers.value, &path) {
+                reload::flatten_into(value, path.join("."), &mut leaves);
+            }
+            for leaf in leaves.into_keys() {
+                layers.sources.insert(leaf, Source::Env(name.clone()));
+            }
+        }
+
+        // Values replaced by a whole mapping or scalar of a higher layer are gone
+        let leaves = reload::flatten_value(&layers.value);
+        layers.sources.retain(|path, _| leaves.contains_key(path));
+
+        Ok(layers)
+    }
+
+    /// Deserializes the merged tree.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        // Parsing text keeps the offending field's path in errors, and the
+        // base file's own text keeps their line numbers meaningful
+        let content = match self.base_content {
+            Some(ref content) => content.clone(),
+            None => serde_yaml::to_string(&self.value)?,
+        };
+        Ok(serde_yaml::from_str(&content)?)
+    }
+
+    /// Returns the layer that set each value, by dotted path.
+    ///
+    /// Values left at their defaults have no source.
+    pub fn sources(&self) -> &BTreeMap<String, Source> {
+        &self.sources
+    }
+
+    /// Merges a YAML file over the current tree.
+    fn merge_file(&mut self, path: &Path, content: &str) -> Result<(), ConfigError> {
+        let value: Value = serde_yaml::from_str(content)?;
+        // An empty file overrides nothing
+        if value.is_null() {
+            return Ok(());
+        }
+
+        self.base_content = None;
+        for leaf in reload::flatten_value(&value).into_keys() {
+            self.sources.insert(leaf, Source::File(path.to_path_buf()));
+        }
+        merge(&mut self.value, value);
+        Ok(())
+    }
+}
+
+/// Returns the overlay file for an environment: `configs/config.yaml` becomes
+/// `configs/config.production.yaml` in production.
+pub fn overlay_path(path: &str, env: AppEnv) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("config");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.{}.{}", stem, env, ext),
+        None => format!("{}.{}", stem, env),
+    };
+    path.with_file_name(name)
+}
+
+/// Merges `overlay` into `base`: mappings key by key, anything else replaced.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Applies one `APP__SECTION__KEY=value` override and returns the keys it set.
+fn set_override(root: &mut Value, name: &str, raw: &str) -> Result<Vec<String>, ConfigError> {
+    let invalid = |reason: &str| ConfigError::EnvOverride(format!("{}: {}", name, reason));
+
+    let segments: Vec<String> = name[ENV_PREFIX.len()..]
+        .split(ENV_SEPARATOR)
+        .map(str::to_lowercase)
+        .collect();
+    if segments.iter().any(String::is_empty) {
+        return Err(invalid("empty path segment"));
+    }
+
+    // Parse like a value written in the file, falling back to plain text
+    let value = serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()));
+
+    let mut node = root;
+    let mut path = Vec::with_capacity(segments.len());
+    for segment in &segments {
+        if node.is_null() {
+            *node = Value::Mapping(Mapping::new());
+        }
+        let Value::Mapping(map) = node else {
+            return Err(invalid(&format!("{} is not a section", path.join("."))));
+        };
+
+        // Keep the spelling of an existing key, e.g. an upper-case exchange name
+        let key = map
+            .keys()
+            .filter_map(Value::as_str)
+            .find(|key| key.eq_ignore_ascii_case(segment))
+            .unwrap_or(segment)
+            .to_string();
+        path.push(key.clone());
+        node = map.entry(Value::String(key)).or_insert(Value::Null);
+    }
+    *node = value;
+
+    Ok(path)
+}
+
+/// Returns the value at a key path.
+fn lookup<'a>(root: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(root, |node, key| node.get(key.as_str()))
+}
+
+/// Returns the `app.env` of a tree, if it is a valid environment.
+fn app_env(root: &Value) -> Option<AppEnv> {
+    let env = root.get("app")?.get("env")?;
+    serde_yaml::from_value(env.clone()).ok()
+}
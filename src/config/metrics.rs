@@ -0,0 +1,22 @@
+//! Metrics endpoint configuration.
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+/// Default address of the Prometheus endpoint.
+pub const DEFAULT_METRICS_LISTEN: &str = "127.0.0.1:9184";
+
+/// Prometheus metrics endpoint settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MetricsConfig {
+    /// Whether the metrics endpoint is served.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Address the endpoint listens on (default: 127.0.0.1:9184).
+    #[serde(default = "default_listen")]
+    pub listen: SocketAddr,
+}
+
+fn default_listen() -> SocketAddr {
+    DEFAULT_METRICS_LISTEN.parse().expect("valid default address")
+}
@@ -3,41 +3,72 @@
 //! Uses serde_yaml to load YAML configuration files with support for
 //! environment variable overrides for sensitive credentials.
 
+mod api;
 mod app;
 mod arbitrage;
 mod balance;
+mod decimal;
+mod diagnostics;
 mod duration;
 mod error;
 mod exchange;
 mod execution;
+pub(crate) mod fees;
+mod layers;
+mod metrics;
 mod notification;
 mod orderbook;
+mod percent;
+mod redact;
+mod reload;
 mod risk;
+mod secrets;
+mod sorted;
 mod storage;
+mod transfer;
 
-pub use app::AppConfig;
-pub use arbitrage::{ArbitrageConfig, CrossExchangeConfig};
+pub use api::ApiConfig;
+pub use app::{AppConfig, AppEnv};
+pub use arbitrage::{
+    ArbitrageConfig, DEFAULT_DETECTION_TIMEOUT, DEFAULT_FALLBACK_INTERVAL,
+    DEFAULT_MIN_REEVALUATION_INTERVAL, DEFAULT_OPPORTUNITY_TTL,
+};
 pub use balance::BalanceConfig;
+pub use diagnostics::{Diagnostic, Severity};
 pub use error::ConfigError;
-pub use exchange::{ExchangeConfig, WebSocketConfig};
-pub use execution::{ExecutionConfig, RetryConfig};
-pub use notification::{NotificationConfig, TelegramConfig};
+pub use exchange::{DEFAULT_PING_INTERVAL, DEFAULT_RECONNECT_DELAY, ExchangeConfig, env_prefix};
+pub use execution::ExecutionConfig;
+pub use fees::DEFAULT_REFRESH_INTERVAL;
+pub use layers::{Layers, overlay_path};
+pub use metrics::MetricsConfig;
+pub use notification::{DEFAULT_OVERVIEW_INTERVAL, NotificationConfig};
 pub use orderbook::OrderbookConfig;
 pub use risk::RiskConfig;
-pub use storage::StorageConfig;
+pub use secrets::SecretsConfig;
+pub use storage::{
+    ArchiveConfig, ArchiveFormat, DEFAULT_RETENTION_INTERVAL, RetentionConfig, StorageBackend,
+    StorageConfig,
+};
+pub use transfer::TransferConfig;
 
-use serde::Deserialize;
-use std::{collections::HashMap, env, fs};
+pub(crate) use percent::parse_percent;
+
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, env};
+
+use crate::secrets::{KEYSTORE_PASSPHRASE_VAR, Keystore, Providers, Secret, SecretRef};
 
 /// Root configuration structure for the arbitrage bot.
 ///
 /// Required sections: app, exchanges, pairs.
-/// Optional sections: orderbook, arbitrage, execution, risk, notification, storage, balance.
-#[derive(Debug, Clone, Deserialize)]
+/// Optional sections: orderbook, arbitrage, execution, risk, notification, storage, balance,
+/// secrets, metrics, api.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     /// Application-level settings like name and environment.
     pub app: AppConfig,
     /// Maps exchange names to their configurations.
+    #[serde(serialize_with = "sorted::serialize")]
     pub exchanges: HashMap<String, ExchangeConfig>,
     /// Orderbook caching and staleness settings (optional).
     pub orderbook: Option<OrderbookConfig>,
@@ -55,110 +86,173 @@ pub struct Config {
     pub storage: Option<StorageConfig>,
     /// Balance caching and sync (optional).
     pub balance: Option<BalanceConfig>,
+    /// Secret providers beyond environment variables and files (optional).
+    pub secrets: Option<SecretsConfig>,
+    /// Prometheus metrics endpoint (optional).
+    pub metrics: Option<MetricsConfig>,
+    /// HTTP control and status API (optional).
+    pub api: Option<ApiConfig>,
 }
 
 impl Config {
     /// Load configuration from a YAML file at the given path.
     ///
     /// First loads environment variables from `.env` file (if exists),
-    /// then loads YAML config and credentials from environment variables:
+    /// then merges the YAML config with its environment overlay and
+    /// `APP__SECTION__KEY` overrides (see [`Layers`]), and finally resolves
+    /// credentials. Secret fields take references (see [`crate::secrets`]);
+    /// unset ones default to environment variables:
     /// - `{EXCHANGE}_API_KEY`, `{EXCHANGE}_API_SECRET`
-    /// - `TELEGRAM_BOT_TOKEN`, `TELEGRAM_CHAT_ID`, `TELEGRAM_ERROR_CHAT_ID`
+    /// - `TELEGRAM_BOT_TOKEN`
+    /// - `API_TOKEN`
+    ///
+    /// `TELEGRAM_CHAT_ID`, `TELEGRAM_ERROR_CHAT_ID` and `DATABASE_URL`
+    /// (PostgreSQL storage) are always read from the environment.
     pub fn load(path: &str) -> Result<Self, ConfigError> {
-        // Load .env file if it exists (ignore error if not found)
-        dotenvy::dotenv().ok();
-
-        let content = fs::read_to_string(path)?;
-        let mut config: Config = serde_yaml::from_str(&content)?;
+        let mut config: Config = Self::layers(path)?.deserialize()?;
 
         config.load_credentials_from_env();
+        let errors = config.resolve_secrets();
+        if !errors.is_empty() {
+            return Err(ConfigError::Secrets(errors.join("; ")));
+        }
         config.validate()?;
 
         Ok(config)
     }
 
-    /// Load credentials from environment variables.
-    fn load_credentials_from_env(&mut self) {
-        // Load exchange credentials
-        for (name, exchange) in self.exchanges.iter_mut() {
+    /// Load configuration like [`Config::load`], but return every problem
+    /// found instead of failing validation.
+    ///
+    /// Only unreadable or unparseable files are errors; the diagnostics
+    /// include warnings that `load` ignores.
+    pub fn check(path: &str) -> Result<(Self, Vec<Diagnostic>, Layers), ConfigError> {
+        let layers = Self::layers(path)?;
+        let mut config: Config = layers.deserialize()?;
+
+        config.load_credentials_from_env();
+        let mut diagnostics: Vec<Diagnostic> = config
+            .resolve_secrets()
+            .into_iter()
+            .map(|message| Diagnostic {
+                severity: Severity::Error,
+                message,
+            })
+            .collect();
+        diagnostics.extend(config.diagnostics());
+
+        Ok((config, diagnostics, layers))
+    }
+
+    /// Loads the `.env` file, then reads the config file and its layers.
+    fn layers(path: &str) -> Result<Layers, ConfigError> {
+        // Load .env file if it exists (ignore error if not found)
+        dotenvy::dotenv().ok();
+
+        Layers::read(path, env::vars())
+    }
+
+    /// Resolves secret references, defaulting to the conventional environment
+    /// variables. Returns a message for every secret that could not be read.
+    fn resolve_secrets(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut providers = Providers::new();
+
+        if let Some(keystore) = self.secrets.as_mut().and_then(|s| s.keystore.as_mut()) {
+            let opened = keystore
+                .passphrase
+                .resolve(&providers, &SecretRef::env(KEYSTORE_PASSPHRASE_VAR))
+                .and_then(|()| Keystore::open(&keystore.path, &keystore.passphrase));
+            match opened {
+                Ok(store) => providers = providers.with(store),
+                Err(e) => errors.push(format!("secrets.keystore ({}): {}", keystore.path, e)),
+            }
+        }
+
+        let mut resolve = |path: String, secret: &mut Secret, default: SecretRef| {
+            if let Err(e) = secret.resolve(&providers, &default) {
+                errors.push(format!("{}: {}", path, e));
+            }
+        };
+
+        let mut exchanges: Vec<_> = self.exchanges.iter_mut().collect();
+        exchanges.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, exchange) in exchanges {
             if !exchange.enabled {
                 continue;
             }
 
-            let env_prefix = name.to_uppercase();
-            exchange.api_key = env::var(format!("{}_API_KEY", env_prefix)).unwrap_or_default();
-            exchange.api_secret =
-                env::var(format!("{}_API_SECRET", env_prefix)).unwrap_or_default();
+            let env_prefix = env_prefix(name);
+            resolve(
+                format!("exchanges.{}.api_key", name),
+                &mut exchange.api_key,
+                SecretRef::env(format!("{}_API_KEY", env_prefix)),
+            );
+            resolve(
+                format!("exchanges.{}.api_secret", name),
+                &mut exchange.api_secret,
+                SecretRef::env(format!("{}_API_SECRET", env_prefix)),
+            );
         }
 
-        // Load Telegram credentials
+        if let Some(telegram) = self.notification.as_mut().and_then(|n| n.telegram.as_mut())
+            && telegram.enabled
+        {
+            resolve(
+                "notification.telegram.bot_token".to_string(),
+                &mut telegram.bot_token,
+                SecretRef::env("TELEGRAM_BOT_TOKEN"),
+            );
+        }
+
+        if let Some(api) = self.api.as_mut()
+            && api.enabled
+        {
+            resolve(
+                "api.token".to_string(),
+                &mut api.token,
+                SecretRef::env("API_TOKEN"),
+            );
+        }
+
+        errors
+    }
+
+    /// Load the non-secret settings that come from environment variables.
+    fn load_credentials_from_env(&mut self) {
+        // Load Telegram chat IDs
         if let Some(ref mut notification) = self.notification {
             if let Some(ref mut telegram) = notification.telegram {
                 if telegram.enabled {
-                    telegram.bot_token = env::var("TELEGRAM_BOT_TOKEN").unwrap_or_default();
                     telegram.chat_id = env::var("TELEGRAM_CHAT_ID").unwrap_or_default();
                     telegram.error_chat_id = env::var("TELEGRAM_ERROR_CHAT_ID").unwrap_or_default();
                 }
             }
         }
-    }
-
-    /// Validate the configuration.
-    fn validate(&self) -> Result<(), ConfigError> {
-        if self.app.name.is_empty() {
-            return Err(ConfigError::Validation("app.name is required".into()));
-        }
 
-        if self.pairs.is_empty() {
-            return Err(ConfigError::Validation(
-                "at least one trading pair is required".into(),
-            ));
-        }
-
-        let is_production = self.app.env != "development";
-
-        let mut enabled_exchanges = 0;
-        for (name, exchange) in &self.exchanges {
-            if exchange.enabled {
-                enabled_exchanges += 1;
-
-                if exchange.fee_taker.is_none() {
-                    return Err(ConfigError::Validation(format!(
-                        "exchange {}: fee_taker is required",
-                        name
-                    )));
-                }
-
-                // Only require credentials in production/staging
-                if is_production && (exchange.api_key.is_empty() || exchange.api_secret.is_empty())
-                {
-                    return Err(ConfigError::Validation(format!(
-                        "exchange {}: API credentials not found (set {}_API_KEY and {}_API_SECRET env vars)",
-                        name,
-                        name.to_uppercase(),
-                        name.to_uppercase()
-                    )));
-                }
-            }
+        // Load the database URL, which usually embeds a password
+        if let Some(ref mut storage) = self.storage
+            && storage.backend == StorageBackend::Postgres
+            && let Ok(url) = env::var("DATABASE_URL")
+        {
+            storage.url = Some(url);
         }
+    }
 
-        if enabled_exchanges == 0 {
-            return Err(ConfigError::Validation(
-                "at least one exchange must be enabled".into(),
-            ));
-        }
+    /// Validate the configuration, reporting every error found.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let errors: Vec<String> = self
+            .diagnostics()
+            .into_iter()
+            .filter(Diagnostic::is_error)
+            .map(|d| d.message)
+            .collect();
 
-        if let Some(ref risk) = self.risk {
-            if let Some(max_open_orders) = risk.max_open_orders {
-                if max_open_orders <= 0 {
-                    return Err(ConfigError::Validation(
-                        "risk.max_open_orders must be positive".into(),
-                    ));
-                }
-            }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Validation(errors.join("; ")))
         }
-
-        Ok(())
     }
 }
 
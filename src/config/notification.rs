@@ -1,31 +1,35 @@
 //! Notification configuration.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-use super::duration;
+use super::{duration, redact};
+use crate::secrets::Secret;
+
+/// Default interval between overview notifications.
+pub const DEFAULT_OVERVIEW_INTERVAL: Duration = Duration::from_secs(3600);
 
 /// Notification settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NotificationConfig {
     /// Telegram bot notifications.
     pub telegram: Option<TelegramConfig>,
 }
 
 /// Telegram notification settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TelegramConfig {
     /// Whether Telegram notifications are active.
     #[serde(default)]
     pub enabled: bool,
-    /// Bot token (loaded from TELEGRAM_BOT_TOKEN env var).
-    #[serde(skip)]
-    pub bot_token: String,
+    /// Bot token reference (default: `env:TELEGRAM_BOT_TOKEN`).
+    #[serde(default, serialize_with = "redact::secret")]
+    pub bot_token: Secret,
     /// Target chat/channel ID (loaded from TELEGRAM_CHAT_ID env var).
-    #[serde(skip)]
+    #[serde(skip_deserializing)]
     pub chat_id: String,
     /// Target chat ID for error notifications (loaded from TELEGRAM_ERROR_CHAT_ID env var).
-    #[serde(skip)]
+    #[serde(skip_deserializing)]
     pub error_chat_id: String,
     /// Send alerts when arbitrage opportunities are detected.
     #[serde(default)]
@@ -40,6 +44,30 @@ pub struct TelegramConfig {
     #[serde(default)]
     pub notify_overview: bool,
     /// Interval between overview notifications (default: 1h).
-    #[serde(default, with = "duration")]
+    #[serde(default = "default_overview_interval", with = "duration")]
     pub overview_interval: Duration,
+    /// Answer bot commands (/status, /pause, ...) sent from authorised chats.
+    #[serde(default)]
+    pub commands: bool,
+    /// Chats allowed to send commands (default: chat_id and error_chat_id).
+    #[serde(default)]
+    pub command_chat_ids: Vec<String>,
+}
+
+impl TelegramConfig {
+    /// Returns the chats whose commands are answered.
+    pub fn authorised_chat_ids(&self) -> Vec<String> {
+        if !self.command_chat_ids.is_empty() {
+            return self.command_chat_ids.clone();
+        }
+        [&self.chat_id, &self.error_chat_id]
+            .into_iter()
+            .filter(|id| !id.is_empty())
+            .cloned()
+            .collect()
+    }
+}
+
+fn default_overview_interval() -> Duration {
+    DEFAULT_OVERVIEW_INTERVAL
 }
@@ -1,12 +1,12 @@
 //! Orderbook configuration.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 use super::duration;
 
 /// Orderbook caching settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OrderbookConfig {
     /// Maximum number of price levels to store per side.
     pub max_depth: Option<i32>,
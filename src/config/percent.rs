@@ -0,0 +1,60 @@
+//! Custom serde module for rates written as fractions ("0.003") or
+//! percentages ("0.3%"). Both deserialize to the fraction.
+
+use rust_decimal::Decimal;
+use serde::{Deserializer, Serializer};
+
+use super::decimal::{deserialize_option_with, deserialize_with, parse_decimal};
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_with(deserializer, parse_percent)
+}
+
+pub fn serialize<S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.to_string())
+}
+
+/// Same as the parent module, for optional rates.
+pub mod option {
+    use super::*;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_option_with(deserializer, parse_percent)
+    }
+
+    pub fn serialize<S>(value: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serializer.serialize_some(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+pub(crate) fn parse_percent(s: &str) -> Result<Decimal, String> {
+    let s = s.trim();
+    let invalid = || {
+        format!(
+            "invalid rate: {:?} (expected a fraction like \"0.003\" or a percentage like \"0.3%\")",
+            s
+        )
+    };
+
+    match s.strip_suffix('%') {
+        Some(percent) => parse_decimal(percent)
+            .map(|d| d / Decimal::ONE_HUNDRED)
+            .map_err(|_| invalid()),
+        None => parse_decimal(s).map_err(|_| invalid()),
+    }
+}
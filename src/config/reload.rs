@@ -0,0 +1,153 @@
+//! Classification of configuration changes for hot reload.
+//!
+//! Settings that only steer detection and alerting can be swapped into a
+//! running bot; everything else (exchanges, credentials, storage, ...) is
+//! wired up at startup and needs a restart.
+
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+
+use super::Config;
+
+/// Settings applied live, as dotted paths; a path covers everything below it.
+const LIVE_PATHS: &[&str] = &[
+    "pairs",
+    "arbitrage",
+    "risk",
+    "orderbook.max_age",
+    "notification.telegram.notify_opportunities",
+    "notification.telegram.notify_executions",
+    "notification.telegram.notify_errors",
+    "notification.telegram.notify_overview",
+];
+
+/// Differences between a running configuration and a reloaded one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigChanges {
+    /// Pairs to subscribe to.
+    pub pairs_added: Vec<String>,
+    /// Pairs to unsubscribe from.
+    pub pairs_removed: Vec<String>,
+    /// Changed settings that apply without a restart.
+    pub live: Vec<String>,
+    /// Changed settings that only take effect after a restart.
+    pub restart_required: Vec<String>,
+}
+
+impl ConfigChanges {
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty() && self.restart_required.is_empty()
+    }
+}
+
+impl Config {
+    /// Compares this (reloaded) configuration with the running one.
+    pub fn changes_from(&self, running: &Config) -> ConfigChanges {
+        let before = flatten(running);
+        let after = flatten(self);
+
+        let mut changes = ConfigChanges {
+            pairs_added: self
+                .pairs
+                .iter()
+                .filter(|p| !running.pairs.contains(p))
+                .cloned()
+                .collect(),
+            pairs_removed: running
+                .pairs
+                .iter()
+                .filter(|p| !self.pairs.contains(p))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+
+        let mut paths: Vec<&String> = before.keys().chain(after.keys()).collect();
+        paths.sort();
+        paths.dedup();
+
+        for path in paths {
+            if before.get(path) == after.get(path) {
+                continue;
+            }
+            if is_live(path) {
+                changes.live.push(path.clone());
+            } else {
+                changes.restart_required.push(path.clone());
+            }
+        }
+
+        changes
+    }
+
+    /// Returns the running configuration with the live settings of `reloaded`.
+    ///
+    /// Settings that need a restart keep their running values, so the result
+    /// describes what the bot actually uses.
+    pub fn with_live_changes(&self, reloaded: &Config) -> Config {
+        let mut config = self.clone();
+
+        config.pairs = reloaded.pairs.clone();
+        config.arbitrage = reloaded.arbitrage.clone();
+        config.risk = reloaded.risk.clone();
+
+        if let (Some(orderbook), Some(reloaded)) = (&mut config.orderbook, &reloaded.orderbook) {
+            orderbook.max_age = reloaded.max_age;
+        }
+
+        let telegram = |c: &Config| c.notification.as_ref().and_then(|n| n.telegram.clone());
+        if let (Some(notification), Some(reloaded)) = (&mut config.notification, telegram(reloaded))
+            && let Some(ref mut telegram) = notification.telegram
+        {
+            telegram.notify_opportunities = reloaded.notify_opportunities;
+            telegram.notify_executions = reloaded.notify_executions;
+            telegram.notify_errors = reloaded.notify_errors;
+            telegram.notify_overview = reloaded.notify_overview;
+        }
+
+        config
+    }
+}
+
+/// Returns true if a changed setting can be applied without a restart.
+fn is_live(path: &str) -> bool {
+    LIVE_PATHS.iter().any(|live| {
+        path == *live || path.strip_prefix(live).is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+/// Flattens the serialized configuration into leaf values by dotted path.
+fn flatten(config: &Config) -> BTreeMap<String, Value> {
+    serde_yaml::to_value(config)
+        .map(|value| flatten_value(&value))
+        .unwrap_or_default()
+}
+
+/// Flattens a YAML tree into leaf values by dotted path.
+pub(super) fn flatten_value(value: &Value) -> BTreeMap<String, Value> {
+    let mut leaves = BTreeMap::new();
+    flatten_into(value, String::new(), &mut leaves);
+    leaves
+}
+
+pub(super) fn flatten_into(value: &Value, path: String, leaves: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Mapping(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let key = key.as_str().map(str::to_string).unwrap_or_else(|| {
+                    serde_yaml::to_string(key).unwrap_or_default().trim().to_string()
+                });
+                let path = if path.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                flatten_into(value, path, leaves);
+            }
+        }
+        _ => {
+            leaves.insert(path, value.clone());
+        }
+    }
+}
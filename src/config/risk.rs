@@ -1,16 +1,22 @@
 //! Risk management configuration.
 
-use serde::Deserialize;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::percent;
 
 /// Risk management settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RiskConfig {
-    /// Maximum position size per exchange as a decimal (e.g., "0.20" for 20%).
-    pub max_position_per_exchange: Option<String>,
-    /// Maximum daily loss before stopping trading (e.g., "0.05" for 5%).
-    pub daily_loss_limit: Option<String>,
-    /// Drawdown threshold that triggers emergency stop (e.g., "0.05" for 5%).
-    pub kill_switch_drawdown: Option<String>,
+    /// Maximum position size per exchange (e.g., "0.20" or "20%").
+    #[serde(default, with = "percent::option")]
+    pub max_position_per_exchange: Option<Decimal>,
+    /// Maximum daily loss before stopping trading (e.g., "0.05" or "5%").
+    #[serde(default, with = "percent::option")]
+    pub daily_loss_limit: Option<Decimal>,
+    /// Drawdown threshold that triggers emergency stop (e.g., "0.05" or "5%").
+    #[serde(default, with = "percent::option")]
+    pub kill_switch_drawdown: Option<Decimal>,
     /// Maximum number of open orders allowed.
     pub max_open_orders: Option<i32>,
 }
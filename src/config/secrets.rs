@@ -0,0 +1,23 @@
+//! Secret provider configuration.
+
+use serde::{Deserialize, Serialize};
+
+use super::redact;
+use crate::secrets::Secret;
+
+/// Secret provider settings.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SecretsConfig {
+    /// Encrypted keystore serving `keystore:` references (optional).
+    pub keystore: Option<KeystoreConfig>,
+}
+
+/// Encrypted keystore settings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KeystoreConfig {
+    /// Path of the keystore file.
+    pub path: String,
+    /// Passphrase reference (default: `env:KEYSTORE_PASSPHRASE`).
+    #[serde(default, serialize_with = "redact::secret")]
+    pub passphrase: Secret,
+}
@@ -0,0 +1,28 @@
+//! Serializers that write hash maps in key order, so printed configs are stable.
+
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+
+/// Serializes a map sorted by key.
+pub fn serialize<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+/// Serializes a map of maps sorted by key at both levels.
+pub fn nested<S, V>(
+    map: &HashMap<String, HashMap<String, V>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    serializer.collect_map(
+        map.iter()
+            .map(|(key, inner)| (key, inner.iter().collect::<BTreeMap<_, _>>())),
+    )
+}
@@ -1,13 +1,86 @@
 //! Storage configuration.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{duration, redact};
+
+/// Default interval between retention runs.
+pub const DEFAULT_RETENTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Database backend used for storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Local SQLite file under `data/`.
+    #[default]
+    Sqlite,
+    /// PostgreSQL server, shareable between bot instances.
+    Postgres,
+}
 
 /// Opportunity storage settings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StorageConfig {
     /// Whether opportunity storage is active.
     #[serde(default)]
     pub enabled: bool,
+    /// Database backend (default sqlite).
+    #[serde(default)]
+    pub backend: StorageBackend,
     /// Path to the SQLite database file.
     pub path: Option<String>,
+    /// PostgreSQL connection URL; overridden by the `DATABASE_URL` env var.
+    #[serde(default, serialize_with = "redact::url")]
+    pub url: Option<String>,
+    /// Maximum number of pooled connections (default 5).
+    pub max_connections: Option<u32>,
+    /// Pruning and archival of old opportunities; disabled when absent.
+    pub retention: Option<RetentionConfig>,
+}
+
+/// Format of archive files written before opportunities are pruned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    /// One JSON object per line.
+    #[default]
+    Jsonl,
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+/// Archival of pruned opportunities to gzip-compressed files.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ArchiveConfig {
+    /// Directory archive files are written to.
+    pub dir: String,
+    /// File format (default jsonl).
+    #[serde(default)]
+    pub format: ArchiveFormat,
+}
+
+/// Retention of stored opportunities.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetentionConfig {
+    /// Interval between retention runs (default: 1h).
+    #[serde(default = "default_retention_interval", with = "duration")]
+    pub interval: Duration,
+    /// Opportunities detected longer ago are pruned (zero keeps them regardless of age).
+    #[serde(default, with = "duration")]
+    pub max_age: Duration,
+    /// Only the newest opportunities up to this count are kept.
+    pub max_rows: Option<u64>,
+    /// Rolls pruned opportunities up into hourly aggregates per route.
+    #[serde(default)]
+    pub rollup: bool,
+    /// Archives pruned opportunities before deleting them.
+    pub archive: Option<ArchiveConfig>,
+    /// Interval between database VACUUMs (zero never vacuums).
+    #[serde(default, with = "duration")]
+    pub vacuum_interval: Duration,
+}
+
+fn default_retention_interval() -> Duration {
+    DEFAULT_RETENTION_INTERVAL
 }
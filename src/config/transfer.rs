@@ -0,0 +1,47 @@
+//! Rebalancing transfer cost configuration.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{decimal, sorted};
+
+/// Transfer cost settings used to estimate rebalancing costs.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TransferConfig {
+    /// Amount of each asset one rebalancing transfer carries (e.g., `BTC: "0.5"`).
+    /// A trade pays the share of the transfer fee its quantity makes up.
+    #[serde(default, serialize_with = "sorted::serialize")]
+    pub rebalance_sizes: HashMap<String, Decimal>,
+    /// Number of trades a single rebalancing transfer is spread over, for assets
+    /// without a rebalance size (default: 1).
+    pub amortization_trades: Option<u32>,
+    /// Fetch withdrawal fees from exchange currency endpoints (default: true).
+    #[serde(default = "default_true")]
+    pub fetch_from_exchanges: bool,
+    /// Withdrawal fees keyed by asset, then exchange (e.g., `BTC: { poloniex: "0.0005" }`).
+    /// Configured values take precedence over fetched ones.
+    #[serde(default, serialize_with = "sorted::nested")]
+    pub withdrawal_fees: HashMap<String, HashMap<String, Decimal>>,
+    /// Costs for specific transfer routes, overriding withdrawal fees.
+    #[serde(default)]
+    pub routes: Vec<TransferRouteConfig>,
+}
+
+/// Cost of moving an asset along one route.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TransferRouteConfig {
+    /// Asset being transferred (e.g., "USDT").
+    pub asset: String,
+    /// Source exchange name.
+    pub from: String,
+    /// Destination exchange name.
+    pub to: String,
+    /// Fee in units of the asset (e.g., "1.0").
+    #[serde(with = "decimal")]
+    pub fee: Decimal,
+}
+
+fn default_true() -> bool {
+    true
+}
@@ -0,0 +1,365 @@
+//! Cross-exchange arbitrage detection: buy on one venue, sell on another.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+
+use super::{expired, generate_opportunity_id};
+use crate::config::{Config, DEFAULT_OPPORTUNITY_TTL};
+use crate::domain::{
+    Fees, Opportunity, OpportunityLeg, OpportunityType, OrderSide, Orderbook, TransferCosts,
+};
+
+/// CrossExchangeDetector finds price differences for the same pair across exchanges.
+#[derive(Debug, Clone)]
+pub struct CrossExchangeDetector {
+    /// Minimum net profit as a fraction of the buy cost (e.g., 0.003 for 0.3%).
+    min_profit_threshold: Decimal,
+    /// Minimum quantity worth trading.
+    min_quantity: Decimal,
+    /// How long a detected opportunity stays valid.
+    opportunity_ttl: Duration,
+    /// Orderbooks older than this are ignored (zero disables the check).
+    max_book_age: Duration,
+}
+
+impl CrossExchangeDetector {
+    /// Creates a new detector with explicit settings.
+    pub fn new(min_profit_threshold: Decimal, min_quantity: Decimal, opportunity_ttl: Duration) -> Self {
+        Self {
+            min_profit_threshold,
+            min_quantity,
+            opportunity_ttl,
+            max_book_age: Duration::ZERO,
+        }
+    }
+
+    /// Creates a detector from the `arbitrage.cross_exchange` and `orderbook` sections.
+    pub fn from_config(config: &Config) -> Self {
+        let cross = config
+            .arbitrage
+            .as_ref()
+            .and_then(|a| a.cross_exchange.as_ref());
+
+        let min_profit_threshold = cross.map(|c| c.min_profit_threshold).unwrap_or_default();
+        let min_quantity = cross.map(|c| c.min_quantity).unwrap_or_default();
+        let opportunity_ttl = cross
+            .map(|c| c.opportunity_ttl)
+            .unwrap_or(DEFAULT_OPPORTUNITY_TTL);
+
+        let mut detector = Self::new(min_profit_threshold, min_quantity, opportunity_ttl);
+        detector.max_book_age = config
+            .orderbook
+            .as_ref()
+            .map(|o| o.max_age)
+            .unwrap_or_default();
+        detector
+    }
+
+    /// Returns a copy of the detector with another minimum profit threshold.
+    pub fn with_min_profit_threshold(&self, min_profit_threshold: Decimal) -> Self {
+        Self {
+            min_profit_threshold,
+            ..self.clone()
+        }
+    }
+
+    /// Detects opportunities for one pair given the latest orderbook of each exchange.
+    ///
+    /// `fees` maps exchange names to their fees for this pair; exchanges without
+    /// an entry are skipped. `transfers` prices the rebalancing each route implies.
+    /// Every buy and sell venue pairing is evaluated, checking the deadline
+    /// before each buy venue; the search gives up and returns None once it passes.
+    pub fn detect_until(
+        &self,
+        pair: &str,
+        books: &[Orderbook],
+        fees: &HashMap<String, Fees>,
+        transfers: &TransferCosts,
+        deadline: Instant,
+    ) -> Option<Vec<Opportunity>> {
+        let now = SystemTime::now();
+        let fresh: Vec<&Orderbook> = books
+            .iter()
+            .filter(|b| b.pair == pair && self.is_fresh(b, now))
+            .collect();
+
+        let mut opportunities = Vec::new();
+        for buy in &fresh {
+            if expired(deadline) {
+                return None;
+            }
+            for sell in &fresh {
+                if buy.exchange == sell.exchange {
+                    continue;
+                }
+                let (Some(buy_fees), Some(sell_fees)) =
+                    (fees.get(&buy.exchange), fees.get(&sell.exchange))
+                else {
+                    continue;
+                };
+
+                if let Some(mut opp) = self.evaluate(pair, buy, sell, buy_fees.taker, sell_fees.taker) {
+                    opp.net_profit_after_rebalance = opp.net_profit
+                        - transfers.amortised_cost(
+                            pair,
+                            &opp.buy_exchange,
+                            &opp.sell_exchange,
+                            opp.buy_price,
+                            opp.quantity,
+                        );
+                    opportunities.push(opp);
+                }
+            }
+        }
+
+        Some(opportunities)
+    }
+
+    /// Returns true if the orderbook is recent enough to trade on.
+    fn is_fresh(&self, book: &Orderbook, now: SystemTime) -> bool {
+        if self.max_book_age.is_zero() {
+            return true;
+        }
+        now.duration_since(book.timestamp)
+            .map(|age| age <= self.max_book_age)
+            .unwrap_or(true)
+    }
+
+    /// Walks both books while the marginal unit is still profitable after fees.
+    fn evaluate(
+        &self,
+        pair: &str,
+        buy: &Orderbook,
+        sell: &Orderbook,
+        buy_fee: Decimal,
+        sell_fee: Decimal,
+    ) -> Option<Opportunity> {
+        let asks: Vec<_> = buy.asks.iter().filter(|l| !l.quantity.is_zero()).collect();
+        let bids: Vec<_> = sell.bids.iter().filter(|l| !l.quantity.is_zero()).collect();
+
+        let (mut ai, mut bi) = (0, 0);
+        let mut ask_left = asks.first()?.quantity;
+        let mut bid_left = bids.first()?.quantity;
+
+        let mut quantity = Decimal::ZERO;
+        let mut cost = Decimal::ZERO;
+        let mut revenue = Decimal::ZERO;
+
+        while ai < asks.len() && bi < bids.len() {
+            let ask = asks[ai].price;
+            let bid = bids[bi].price;
+
+            if bid * (Decimal::ONE - sell_fee) <= ask * (Decimal::ONE + buy_fee) {
+                break;
+            }
+
+            let qty = ask_left.min(bid_left);
+            quantity += qty;
+            cost += qty * ask;
+            revenue += qty * bid;
+            ask_left -= qty;
+            bid_left -= qty;
+
+            if ask_left.is_zero() {
+                ai += 1;
+                if let Some(level) = asks.get(ai) {
+                    ask_left = level.quantity;
+                }
+            }
+            if bid_left.is_zero() {
+                bi += 1;
+                if let Some(level) = bids.get(bi) {
+                    bid_left = level.quantity;
+                }
+            }
+        }
+
+        if quantity.is_zero() || quantity < self.min_quantity || cost.is_zero() {
+            return None;
+        }
+
+        let gross_profit = revenue - cost;
+        let net_profit = gross_profit - cost * buy_fee - revenue * sell_fee;
+        let profit_percent = net_profit / cost;
+
+        if profit_percent < self.min_profit_threshold {
+            return None;
+        }
+
+        let detected_at = Utc::now();
+        let expires_at =
+            detected_at + chrono::Duration::from_std(self.opportunity_ttl).unwrap_or_default();
+
+        let buy_price = cost / quantity;
+        let sell_price = revenue / quantity;
+        let legs = vec![
+            OpportunityLeg {
+                exchange: buy.exchange.clone(),
+                pair: pair.to_string(),
+                side: OrderSide::Buy,
+                price: buy_price,
+                quantity,
+                fee: buy_fee,
+            },
+            OpportunityLeg {
+                exchange: sell.exchange.clone(),
+                pair: pair.to_string(),
+                side: OrderSide::Sell,
+                price: sell_price,
+                quantity,
+                fee: sell_fee,
+            },
+        ];
+
+        Some(Opportunity {
+            id: generate_opportunity_id(detected_at),
+            opportunity_type: OpportunityType::CrossExchange,
+            pair: pair.to_string(),
+            buy_exchange: buy.exchange.clone(),
+            sell_exchange: sell.exchange.clone(),
+            buy_price,
+            sell_price,
+            quantity,
+            gross_profit,
+            net_profit,
+            net_profit_after_rebalance: net_profit,
+            profit_percent,
+            buy_fee,
+            sell_fee,
+            detected_at,
+            expires_at,
+            legs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::far_deadline;
+    use crate::domain::PriceLevel;
+    use std::str::FromStr;
+
+    /// Searches BTC/USDT without a deadline that could pass.
+    fn detect(
+        detector: &CrossExchangeDetector,
+        books: &[Orderbook],
+        fees: &HashMap<String, Fees>,
+        transfers: &TransferCosts,
+    ) -> Vec<Opportunity> {
+        detector
+            .detect_until("BTC/USDT", books, fees, transfers, far_deadline())
+            .unwrap()
+    }
+
+    fn level(price: i64, quantity: &str) -> PriceLevel {
+        PriceLevel {
+            price: Decimal::from(price),
+            quantity: Decimal::from_str(quantity).unwrap(),
+        }
+    }
+
+    fn book(exchange: &str, bids: Vec<PriceLevel>, asks: Vec<PriceLevel>) -> Orderbook {
+        Orderbook {
+            pair: "BTC/USDT".to_string(),
+            exchange: exchange.to_string(),
+            bids,
+            asks,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    fn fees(taker: &str) -> HashMap<String, Fees> {
+        let taker = Decimal::from_str(taker).unwrap();
+        HashMap::from([
+            ("a".to_string(), Fees::new(taker, taker)),
+            ("b".to_string(), Fees::new(taker, taker)),
+        ])
+    }
+
+    #[test]
+    fn test_detects_profitable_spread() {
+        let detector = CrossExchangeDetector::new(Decimal::ZERO, Decimal::ZERO, Duration::from_secs(5));
+        let books = vec![
+            book("a", vec![level(99, "1")], vec![level(100, "1")]),
+            book("b", vec![level(110, "0.5")], vec![level(111, "1")]),
+        ];
+
+        let opps = detect(&detector, &books, &fees("0.001"), &TransferCosts::default());
+        assert_eq!(opps.len(), 1);
+
+        let opp = &opps[0];
+        assert_eq!(opp.buy_exchange, "a");
+        assert_eq!(opp.sell_exchange, "b");
+        assert_eq!(opp.quantity, Decimal::from_str("0.5").unwrap());
+        assert_eq!(opp.gross_profit, Decimal::from(5));
+        // fees: 50 * 0.001 + 55 * 0.001 = 0.105
+        assert_eq!(opp.net_profit, Decimal::from_str("4.895").unwrap());
+        assert_eq!(opp.net_profit_after_rebalance, opp.net_profit);
+        assert_eq!(opp.legs.len(), 2);
+        assert_eq!(opp.legs[0].side, OrderSide::Buy);
+        assert_eq!(opp.legs[1].exchange, "b");
+    }
+
+    #[test]
+    fn test_net_profit_after_rebalance_subtracts_transfer_costs() {
+        let detector = CrossExchangeDetector::new(Decimal::ZERO, Decimal::ZERO, Duration::from_secs(5));
+        let books = vec![
+            book("a", vec![], vec![level(100, "1")]),
+            book("b", vec![level(110, "1")], vec![]),
+        ];
+
+        let mut transfers = TransferCosts::new(2);
+        transfers.set_withdrawal_fee("a", "BTC", Decimal::new(1, 2));
+        transfers.set_withdrawal_fee("b", "USDT", Decimal::ONE);
+
+        let opps = detect(&detector, &books, &fees("0"), &transfers);
+        assert_eq!(opps.len(), 1);
+        // (0.01 BTC * 100 + 1 USDT) / 2 trades = 1 USDT per trade
+        assert_eq!(opps[0].net_profit, Decimal::from(10));
+        assert_eq!(opps[0].net_profit_after_rebalance, Decimal::from(9));
+    }
+
+    #[test]
+    fn test_fees_eat_the_spread() {
+        let detector = CrossExchangeDetector::new(Decimal::ZERO, Decimal::ZERO, Duration::from_secs(5));
+        let books = vec![
+            book("a", vec![level(99, "1")], vec![level(100, "1")]),
+            book("b", vec![level(101, "1")], vec![level(102, "1")]),
+        ];
+
+        assert!(detect(&detector, &books, &fees("0.01"), &TransferCosts::default()).is_empty());
+        assert_eq!(detect(&detector, &books, &fees("0"), &TransferCosts::default()).len(), 1);
+    }
+
+    #[test]
+    fn test_walks_depth_until_unprofitable() {
+        let detector = CrossExchangeDetector::new(Decimal::ZERO, Decimal::ZERO, Duration::from_secs(5));
+        let books = vec![
+            book("a", vec![], vec![level(100, "1"), level(105, "1"), level(120, "1")]),
+            book("b", vec![level(110, "3")], vec![]),
+        ];
+
+        let opps = detect(&detector, &books, &fees("0"), &TransferCosts::default());
+        assert_eq!(opps.len(), 1);
+        assert_eq!(opps[0].quantity, Decimal::from(2));
+        assert_eq!(opps[0].gross_profit, Decimal::from(15));
+    }
+
+    #[test]
+    fn test_min_profit_threshold_and_quantity() {
+        let books = vec![
+            book("a", vec![], vec![level(100, "1")]),
+            book("b", vec![level(101, "1")], vec![]),
+        ];
+
+        let strict = CrossExchangeDetector::new(Decimal::new(2, 2), Decimal::ZERO, Duration::from_secs(5));
+        assert!(detect(&strict, &books, &fees("0"), &TransferCosts::default()).is_empty());
+
+        let min_qty = CrossExchangeDetector::new(Decimal::ZERO, Decimal::from(2), Duration::from_secs(5));
+        assert!(detect(&min_qty, &books, &fees("0"), &TransferCosts::default()).is_empty());
+    }
+}
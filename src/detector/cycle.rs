@@ -0,0 +1,194 @@
+//! Depth-aware execution of a conversion cycle across orderbooks.
+
+use rust_decimal::Decimal;
+
+use crate::domain::{OpportunityLeg, OrderSide, Orderbook};
+
+/// One conversion step of a cycle.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Step<'a> {
+    /// Trade on a book: buy spends quote at the asks, sell spends base at the bids.
+    Trade {
+        book: &'a Orderbook,
+        side: OrderSide,
+        fee: Decimal,
+    },
+    /// Move an asset between exchanges out of pre-positioned inventory.
+    /// Converts one-to-one; the transfer fee is charged when rebalancing.
+    Transfer {
+        asset: &'a str,
+        from: &'a str,
+        to: &'a str,
+    },
+}
+
+/// Result of walking a cycle through the books.
+#[derive(Debug)]
+pub(super) struct CycleFill {
+    /// Amount of the start asset spent.
+    pub amount_in: Decimal,
+    /// Amount of the start asset received after fees.
+    pub amount_out: Decimal,
+    /// Amount of the start asset received at the same prices without fees.
+    pub gross_out: Decimal,
+    /// Total amount entering each step, in that step's input asset.
+    pub step_inputs: Vec<Decimal>,
+    /// Orders placed by the trade steps, in execution order.
+    pub legs: Vec<OpportunityLeg>,
+}
+
+/// Position of one trade step in its orderbook while walking depth.
+#[derive(Debug, Default)]
+struct Cursor {
+    level: usize,
+    /// Base quantity left on the current level.
+    left: Decimal,
+    /// Base quantity consumed so far.
+    quantity: Decimal,
+    /// Quote value of the consumed quantity.
+    notional: Decimal,
+}
+
+/// Walks the depth of every book in the cycle while the marginal round trip
+/// is still profitable after fees. Returns None if nothing can be traded.
+pub(super) fn walk(steps: &[Step]) -> Option<CycleFill> {
+    let mut cursors: Vec<Cursor> = Vec::with_capacity(steps.len());
+    for step in steps {
+        let mut cursor = Cursor::default();
+        if let Step::Trade { .. } = step {
+            cursor.left = level(step, 0)?.1;
+        }
+        cursors.push(cursor);
+    }
+
+    let mut amount_in = Decimal::ZERO;
+    let mut amount_out = Decimal::ZERO;
+    let mut step_inputs = vec![Decimal::ZERO; steps.len()];
+
+    'walk: loop {
+        // Marginal conversion rate and input capacity of each step's current level
+        let mut rates = Vec::with_capacity(steps.len());
+        let mut capacities = Vec::with_capacity(steps.len());
+        for (step, cursor) in steps.iter().zip(&cursors) {
+            match *step {
+                Step::Trade { side, fee, .. } => {
+                    let Some((price, _)) = level(step, cursor.level) else {
+                        break 'walk;
+                    };
+                    let (rate, capacity) = match side {
+                        OrderSide::Buy => ((Decimal::ONE - fee) / price, cursor.left * price),
+                        OrderSide::Sell => (price * (Decimal::ONE - fee), cursor.left),
+                    };
+                    rates.push(rate);
+                    capacities.push(Some(capacity));
+                }
+                Step::Transfer { .. } => {
+                    rates.push(Decimal::ONE);
+                    capacities.push(None);
+                }
+            }
+        }
+
+        if rates.iter().product::<Decimal>() <= Decimal::ONE {
+            break;
+        }
+
+        // Largest starting amount that fits every step's current level
+        let mut bottleneck = None;
+        let mut size = Decimal::MAX;
+        let mut carried = Decimal::ONE;
+        for (k, capacity) in capacities.iter().enumerate() {
+            if let Some(capacity) = capacity {
+                let limit = capacity / carried;
+                if limit < size {
+                    size = limit;
+                    bottleneck = Some(k);
+                }
+            }
+            carried *= rates[k];
+        }
+        bottleneck?;
+
+        let mut amount = size;
+        for (k, (step, cursor)) in steps.iter().zip(cursors.iter_mut()).enumerate() {
+            step_inputs[k] += amount;
+
+            if let Step::Trade { side, .. } = *step {
+                let (price, _) = level(step, cursor.level)?;
+                let base = match side {
+                    OrderSide::Buy => amount / price,
+                    OrderSide::Sell => amount,
+                };
+                let base = if bottleneck == Some(k) {
+                    cursor.left
+                } else {
+                    base.min(cursor.left)
+                };
+
+                cursor.quantity += base;
+                cursor.notional += base * price;
+                cursor.left -= base;
+
+                if cursor.left.is_zero() {
+                    cursor.level += 1;
+                    cursor.left = level(step, cursor.level).map(|(_, q)| q).unwrap_or_default();
+                }
+            }
+
+            amount *= rates[k];
+        }
+
+        amount_in += size;
+        amount_out += amount;
+    }
+
+    if amount_in.is_zero() {
+        return None;
+    }
+
+    let legs: Vec<OpportunityLeg> = steps
+        .iter()
+        .zip(&cursors)
+        .filter_map(|(step, cursor)| match *step {
+            Step::Trade { book, side, fee } => Some(OpportunityLeg {
+                exchange: book.exchange.clone(),
+                pair: book.pair.clone(),
+                side,
+                price: cursor.notional / cursor.quantity,
+                quantity: cursor.quantity,
+                fee,
+            }),
+            Step::Transfer { .. } => None,
+        })
+        .collect();
+
+    // Same cycle at the average prices without fees
+    let gross_out = legs.iter().fold(amount_in, |amount, leg| match leg.side {
+        OrderSide::Buy => amount / leg.price,
+        OrderSide::Sell => amount * leg.price,
+    });
+
+    Some(CycleFill {
+        amount_in,
+        amount_out,
+        gross_out,
+        step_inputs,
+        legs,
+    })
+}
+
+/// Returns the (price, quantity) of a trade step's n-th non-empty level.
+fn level(step: &Step, n: usize) -> Option<(Decimal, Decimal)> {
+    let Step::Trade { book, side, .. } = step else {
+        return None;
+    };
+    let levels = match side {
+        OrderSide::Buy => &book.asks,
+        OrderSide::Sell => &book.bids,
+    };
+    levels
+        .iter()
+        .filter(|l| !l.quantity.is_zero())
+        .nth(n)
+        .map(|l| (l.price, l.quantity))
+}
@@ -0,0 +1,39 @@
+//! Arbitrage opportunity detection over cached orderbooks.
+
+mod cross_exchange;
+mod cycle;
+mod multi_hop;
+mod triangular;
+
+pub use cross_exchange::CrossExchangeDetector;
+pub use multi_hop::MultiHopDetector;
+pub use triangular::TriangularDetector;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+
+/// Sequence number that keeps ids unique within the same nanosecond.
+static OPPORTUNITY_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Generates a unique opportunity id from the detection time.
+fn generate_opportunity_id(detected_at: DateTime<Utc>) -> String {
+    let seq = OPPORTUNITY_SEQ.fetch_add(1, Ordering::Relaxed);
+    format!(
+        "{:x}-{:04x}",
+        detected_at.timestamp_nanos_opt().unwrap_or_default(),
+        seq & 0xffff
+    )
+}
+
+/// Returns true once the search deadline has passed.
+fn expired(deadline: Instant) -> bool {
+    Instant::now() >= deadline
+}
+
+/// Returns a deadline no test search reaches.
+#[cfg(test)]
+fn far_deadline() -> Instant {
+    Instant::now() + std::time::Duration::from_secs(3600)
+}
@@ -0,0 +1,470 @@
+//! Multi-hop arbitrage detection: profitable cycles in the (exchange, asset) graph.
+//!
+//! Every orderbook contributes a buy and a sell edge between its base and quote
+//! on its exchange; transfers optionally connect the same asset across exchanges.
+//! Edge weights are `-ln(rate)`, so a profitable cycle is a negative cycle and is
+//! found with a hop-limited Bellman-Ford from each start node.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use super::cycle::{self, Step};
+use super::{expired, generate_opportunity_id};
+use crate::config::Config;
+use crate::domain::{Fees, Opportunity, OpportunityType, OrderSide, Orderbook, TransferCosts};
+
+/// Cycles whose total log-weight is above this are treated as break-even.
+const WEIGHT_EPSILON: f64 = -1e-9;
+
+/// Graph node: an asset held on an exchange.
+type Node<'a> = (&'a str, &'a str);
+
+/// Directed conversion between two nodes.
+#[derive(Debug, Clone, Copy)]
+struct Edge<'a> {
+    from: usize,
+    to: usize,
+    /// `-ln(rate)` at the top of the book.
+    weight: f64,
+    step: Step<'a>,
+}
+
+/// MultiHopDetector searches the whole orderbook graph for cycles of up to
+/// `max_hops` conversions, optionally moving assets between exchanges.
+#[derive(Debug, Clone)]
+pub struct MultiHopDetector {
+    /// Maximum number of conversions (trades and transfers) in a cycle.
+    max_hops: usize,
+    /// Minimum net profit as a fraction of the starting amount.
+    min_profit_threshold: Decimal,
+    /// Assets a cycle may start in; empty means any asset.
+    start_assets: Vec<String>,
+    /// Whether the same asset on two exchanges is connected by a transfer edge.
+    include_transfers: bool,
+    /// How long a detected opportunity stays valid.
+    opportunity_ttl: Duration,
+    /// Orderbooks older than this are ignored (zero disables the check).
+    max_book_age: Duration,
+}
+
+impl MultiHopDetector {
+    /// Creates a new detector with explicit settings.
+    pub fn new(
+        max_hops: usize,
+        min_profit_threshold: Decimal,
+        start_assets: Vec<String>,
+        include_transfers: bool,
+        opportunity_ttl: Duration,
+    ) -> Self {
+        Self {
+            max_hops,
+            min_profit_threshold,
+            start_assets,
+            include_transfers,
+            opportunity_ttl,
+            max_book_age: Duration::ZERO,
+        }
+    }
+
+    /// Creates a detector from the `arbitrage.multi_hop` section.
+    /// Returns None when multi-hop detection is not configured.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let multi_hop = config.arbitrage.as_ref()?.multi_hop.as_ref()?;
+
+        let mut detector = Self::new(
+            multi_hop.max_hops,
+            multi_hop.min_profit_threshold,
+            multi_hop.start_assets.clone(),
+            multi_hop.include_transfers,
+            multi_hop.opportunity_ttl,
+        );
+        detector.max_book_age = config
+            .orderbook
+            .as_ref()
+            .map(|o| o.max_age)
+            .unwrap_or_default();
+        Some(detector)
+    }
+
+    /// Detects cycles over the latest orderbooks of all exchanges.
+    ///
+    /// `fees` maps exchange names to pair fees; books without fees are skipped.
+    /// `transfers` prices the transfer edges a cycle uses. The graph is searched
+    /// from every start node, checking the deadline before each one; the search
+    /// gives up and returns None once it passes.
+    pub fn detect_until(
+        &self,
+        books: &[Orderbook],
+        fees: &HashMap<String, HashMap<String, Fees>>,
+        transfers: &TransferCosts,
+        deadline: Instant,
+    ) -> Option<Vec<Opportunity>> {
+        let now = SystemTime::now();
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut index: HashMap<Node, usize> = HashMap::new();
+        let mut edges: Vec<Edge> = Vec::new();
+
+        for book in books {
+            if !self.is_fresh(book, now) {
+                continue;
+            }
+            let Some((base, quote)) = book.pair.split_once('/') else {
+                continue;
+            };
+            let Some(pair_fees) = fees.get(&book.exchange).and_then(|f| f.get(&book.pair)) else {
+                continue;
+            };
+
+            let fee = pair_fees.taker;
+            let base_node = node_index(&mut index, &mut nodes, (&book.exchange, base));
+            let quote_node = node_index(&mut index, &mut nodes, (&book.exchange, quote));
+
+            if let Some(ask) = book.asks.iter().find(|l| !l.quantity.is_zero()) {
+                let rate = (Decimal::ONE - fee) / ask.price;
+                if let Some(weight) = log_weight(rate) {
+                    edges.push(Edge {
+                        from: quote_node,
+                        to: base_node,
+                        weight,
+                        step: Step::Trade { book, side: OrderSide::Buy, fee },
+                    });
+                }
+            }
+            if let Some(bid) = book.bids.iter().find(|l| !l.quantity.is_zero()) {
+                let rate = bid.price * (Decimal::ONE - fee);
+                if let Some(weight) = log_weight(rate) {
+                    edges.push(Edge {
+                        from: base_node,
+                        to: quote_node,
+                        weight,
+                        step: Step::Trade { book, side: OrderSide::Sell, fee },
+                    });
+                }
+            }
+        }
+
+        if self.include_transfers {
+            for (from, &(from_exchange, asset)) in nodes.iter().enumerate() {
+                for (to, &(to_exchange, other)) in nodes.iter().enumerate() {
+                    if from != to && asset == other {
+                        edges.push(Edge {
+                            from,
+                            to,
+                            weight: 0.0,
+                            step: Step::Transfer { asset, from: from_exchange, to: to_exchange },
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut opportunities = Vec::new();
+
+        for start in 0..nodes.len() {
+            if expired(deadline) {
+                return None;
+            }
+            let asset = nodes[start].1;
+            if !self.start_assets.is_empty() && !self.start_assets.iter().any(|a| a == asset) {
+                continue;
+            }
+
+            let Some(cycle) = self.best_cycle(start, nodes.len(), &edges) else {
+                continue;
+            };
+
+            // The same cycle is found from every node on it
+            let mut key = cycle.clone();
+            key.sort_unstable();
+            if !seen.insert(key) {
+                continue;
+            }
+
+            if let Some(opp) = self.evaluate(&nodes, &edges, &cycle, transfers) {
+                opportunities.push(opp);
+            }
+        }
+
+        Some(opportunities)
+    }
+
+    /// Runs a hop-limited Bellman-Ford from `start` and returns the edge indices
+    /// of the most negative simple cycle through it, if any.
+    fn best_cycle(&self, start: usize, node_count: usize, edges: &[Edge]) -> Option<Vec<usize>> {
+        let mut dist = vec![f64::INFINITY; node_count];
+        dist[start] = 0.0;
+
+        // preds[k][v] is the last edge of the best k+1 hop path to v
+        let mut preds: Vec<Vec<Option<usize>>> = Vec::with_capacity(self.max_hops);
+        let mut best: Option<(f64, Vec<usize>)> = None;
+
+        for hops in 1..=self.max_hops {
+            let mut next = vec![f64::INFINITY; node_count];
+            let mut pred = vec![None; node_count];
+
+            for (i, edge) in edges.iter().enumerate() {
+                let candidate = dist[edge.from] + edge.weight;
+                if candidate < next[edge.to] {
+                    next[edge.to] = candidate;
+                    pred[edge.to] = Some(i);
+                }
+            }
+            preds.push(pred);
+
+            let weight = next[start];
+            if weight < WEIGHT_EPSILON
+                && best.as_ref().is_none_or(|(w, _)| weight < *w)
+                && let Some(cycle) = reconstruct(start, hops, &preds, edges)
+            {
+                best = Some((weight, cycle));
+            }
+
+            dist = next;
+        }
+
+        best.map(|(_, cycle)| cycle)
+    }
+
+    /// Returns true if the orderbook is recent enough to trade on.
+    fn is_fresh(&self, book: &Orderbook, now: SystemTime) -> bool {
+        if self.max_book_age.is_zero() {
+            return true;
+        }
+        now.duration_since(book.timestamp)
+            .map(|age| age <= self.max_book_age)
+            .unwrap_or(true)
+    }
+
+    /// Walks the books along the cycle and builds the opportunity if it clears the threshold.
+    fn evaluate(
+        &self,
+        nodes: &[Node],
+        edges: &[Edge],
+        cycle: &[usize],
+        transfers: &TransferCosts,
+    ) -> Option<Opportunity> {
+        let steps: Vec<Step> = cycle.iter().map(|&i| edges[i].step).collect();
+        let fill = cycle::walk(&steps)?;
+
+        let net_profit = fill.amount_out - fill.amount_in;
+        let profit_percent = net_profit / fill.amount_in;
+        if profit_percent < self.min_profit_threshold {
+            return None;
+        }
+
+        // Transfer fees are in the moved asset; value them at what the rest of the cycle realises
+        let transfer_cost: Decimal = steps
+            .iter()
+            .zip(&fill.step_inputs)
+            .filter_map(|(step, input)| match *step {
+                Step::Transfer { asset, from, to } if !input.is_zero() => {
+                    Some(transfers.amortised_transfer_fee(asset, from, to, *input) * fill.amount_out / input)
+                }
+                _ => None,
+            })
+            .sum();
+
+        let mut assets: Vec<&str> = cycle.iter().map(|&i| nodes[edges[i].from].1).collect();
+        assets.dedup();
+        if assets.len() > 1 && assets.first() == assets.last() {
+            assets.pop();
+        }
+
+        let detected_at = Utc::now();
+        let expires_at =
+            detected_at + chrono::Duration::from_std(self.opportunity_ttl).unwrap_or_default();
+
+        let legs = fill.legs;
+        let (first, last) = (legs.first()?, legs.last()?);
+        Some(Opportunity {
+            id: generate_opportunity_id(detected_at),
+            opportunity_type: OpportunityType::MultiHop,
+            pair: assets.join("/"),
+            buy_exchange: first.exchange.clone(),
+            sell_exchange: last.exchange.clone(),
+            buy_price: first.price,
+            sell_price: last.price,
+            quantity: fill.amount_in,
+            gross_profit: fill.gross_out - fill.amount_in,
+            net_profit,
+            net_profit_after_rebalance: net_profit - transfer_cost,
+            profit_percent,
+            buy_fee: first.fee,
+            sell_fee: last.fee,
+            detected_at,
+            expires_at,
+            legs,
+        })
+    }
+}
+
+/// Returns the index of a node, adding it on first use.
+fn node_index<'a>(index: &mut HashMap<Node<'a>, usize>, nodes: &mut Vec<Node<'a>>, node: Node<'a>) -> usize {
+    *index.entry(node).or_insert_with(|| {
+        nodes.push(node);
+        nodes.len() - 1
+    })
+}
+
+/// Converts a conversion rate into an edge weight.
+fn log_weight(rate: Decimal) -> Option<f64> {
+    let rate = rate.to_f64()?;
+    (rate > 0.0).then(|| -rate.ln())
+}
+
+/// Follows predecessors back from `start` at `hops` hops and returns the cycle's
+/// edges in order, or None if the walk revisits a node.
+fn reconstruct(
+    start: usize,
+    hops: usize,
+    preds: &[Vec<Option<usize>>],
+    edges: &[Edge],
+) -> Option<Vec<usize>> {
+    let mut cycle = Vec::with_capacity(hops);
+    let mut visited = HashSet::new();
+    let mut node = start;
+
+    for k in (0..hops).rev() {
+        let edge = preds[k][node]?;
+        cycle.push(edge);
+        node = edges[edge].from;
+        if k > 0 && (node == start || !visited.insert(node)) {
+            return None;
+        }
+    }
+
+    (node == start).then(|| {
+        cycle.reverse();
+        cycle
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::far_deadline;
+    use crate::domain::PriceLevel;
+    use std::str::FromStr;
+
+    /// Searches without a deadline that could pass.
+    fn detect(
+        detector: MultiHopDetector,
+        books: &[Orderbook],
+        fees: &HashMap<String, HashMap<String, Fees>>,
+        transfers: &TransferCosts,
+    ) -> Vec<Opportunity> {
+        detector.detect_until(books, fees, transfers, far_deadline()).unwrap()
+    }
+
+    fn level(price: &str, quantity: &str) -> PriceLevel {
+        PriceLevel {
+            price: Decimal::from_str(price).unwrap(),
+            quantity: Decimal::from_str(quantity).unwrap(),
+        }
+    }
+
+    fn book(exchange: &str, pair: &str, bids: Vec<PriceLevel>, asks: Vec<PriceLevel>) -> Orderbook {
+        Orderbook {
+            pair: pair.to_string(),
+            exchange: exchange.to_string(),
+            bids,
+            asks,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    fn zero_fees(books: &[Orderbook]) -> HashMap<String, HashMap<String, Fees>> {
+        let mut fees: HashMap<String, HashMap<String, Fees>> = HashMap::new();
+        for b in books {
+            fees.entry(b.exchange.clone())
+                .or_default()
+                .insert(b.pair.clone(), Fees::new(Decimal::ZERO, Decimal::ZERO));
+        }
+        fees
+    }
+
+    fn detector(max_hops: usize, include_transfers: bool) -> MultiHopDetector {
+        MultiHopDetector::new(
+            max_hops,
+            Decimal::ZERO,
+            vec!["USDT".to_string()],
+            include_transfers,
+            Duration::from_secs(5),
+        )
+    }
+
+    fn cross_books() -> Vec<Orderbook> {
+        vec![
+            book("a", "BTC/USDT", vec![], vec![level("100", "1")]),
+            book("b", "BTC/USDT", vec![level("110", "1")], vec![]),
+        ]
+    }
+
+    #[test]
+    fn test_finds_cross_exchange_path_through_transfers() {
+        let books = cross_books();
+        let mut transfers = TransferCosts::default();
+        transfers.set_withdrawal_fee("a", "BTC", Decimal::new(1, 2));
+        transfers.set_withdrawal_fee("b", "USDT", Decimal::ONE);
+
+        let opps = detect(detector(4, true), &books, &zero_fees(&books), &transfers);
+        assert_eq!(opps.len(), 1);
+
+        let opp = &opps[0];
+        assert_eq!(opp.opportunity_type, OpportunityType::MultiHop);
+        assert_eq!(opp.pair, "USDT/BTC");
+        assert_eq!(opp.legs.len(), 2);
+        assert_eq!(opp.buy_exchange, "a");
+        assert_eq!(opp.sell_exchange, "b");
+        assert_eq!(opp.net_profit, Decimal::from(10));
+        // 0.01 BTC sold at 110 + 1 USDT
+        assert_eq!(opp.net_profit_after_rebalance, Decimal::new(79, 1));
+    }
+
+    #[test]
+    fn test_transfers_and_hop_limit_constrain_search() {
+        let books = cross_books();
+        let fees = zero_fees(&books);
+
+        assert!(detect(detector(4, false), &books, &fees, &TransferCosts::default()).is_empty());
+        assert!(detect(detector(3, true), &books, &fees, &TransferCosts::default()).is_empty());
+    }
+
+    #[test]
+    fn test_finds_four_asset_cycle_on_one_exchange() {
+        // 40000 USDT -> 1 BTC -> 20 ETH -> 200 SOL -> 42000 USDT
+        let books = vec![
+            book("a", "BTC/USDT", vec![], vec![level("40000", "1")]),
+            book("a", "ETH/BTC", vec![], vec![level("0.05", "100")]),
+            book("a", "SOL/ETH", vec![], vec![level("0.1", "1000")]),
+            book("a", "SOL/USDT", vec![level("210", "1000")], vec![]),
+        ];
+
+        let opps = detect(detector(4, false), &books, &zero_fees(&books), &TransferCosts::default());
+        assert_eq!(opps.len(), 1);
+        assert_eq!(opps[0].pair, "USDT/BTC/ETH/SOL");
+        assert_eq!(opps[0].legs.len(), 4);
+        assert_eq!(opps[0].quantity, Decimal::from(40000));
+        assert_eq!(opps[0].net_profit, Decimal::from(2000));
+
+        assert!(detect(detector(3, false), &books, &zero_fees(&books), &TransferCosts::default()).is_empty());
+    }
+
+    #[test]
+    fn test_detect_until_gives_up_at_the_deadline() {
+        let books = cross_books();
+        let fees = zero_fees(&books);
+        let transfers = TransferCosts::default();
+
+        let deadline = far_deadline();
+        let opps = detector(4, true).detect_until(&books, &fees, &transfers, deadline).unwrap();
+        assert_eq!(opps.len(), 1);
+
+        assert!(detector(4, true).detect_until(&books, &fees, &transfers, Instant::now()).is_none());
+    }
+}
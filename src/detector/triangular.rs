@@ -0,0 +1,304 @@
+//! Triangular arbitrage detection: trade around a three-asset cycle on one exchange.
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+
+use super::cycle::{self, Step};
+use super::{expired, generate_opportunity_id};
+use crate::config::Config;
+use crate::domain::{Fees, Opportunity, OpportunityType, OrderSide, Orderbook};
+
+/// TriangularDetector finds profitable three-pair cycles on a single exchange,
+/// e.g. USDT -> BTC -> ETH -> USDT over BTC/USDT, ETH/BTC and ETH/USDT.
+#[derive(Debug, Clone)]
+pub struct TriangularDetector {
+    /// Minimum net profit as a fraction of the starting amount.
+    min_profit_threshold: Decimal,
+    /// Assets a cycle may start in; empty means the alphabetically first asset.
+    start_assets: Vec<String>,
+    /// How long a detected opportunity stays valid.
+    opportunity_ttl: Duration,
+    /// Orderbooks older than this are ignored (zero disables the check).
+    max_book_age: Duration,
+}
+
+impl TriangularDetector {
+    /// Creates a new detector with explicit settings.
+    pub fn new(min_profit_threshold: Decimal, start_assets: Vec<String>, opportunity_ttl: Duration) -> Self {
+        Self {
+            min_profit_threshold,
+            start_assets,
+            opportunity_ttl,
+            max_book_age: Duration::ZERO,
+        }
+    }
+
+    /// Creates a detector from the `arbitrage.triangular` section.
+    /// Returns None when triangular detection is not configured.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let triangular = config.arbitrage.as_ref()?.triangular.as_ref()?;
+
+        let mut detector = Self::new(
+            triangular.min_profit_threshold,
+            triangular.start_assets.clone(),
+            triangular.opportunity_ttl,
+        );
+        detector.max_book_age = config
+            .orderbook
+            .as_ref()
+            .map(|o| o.max_age)
+            .unwrap_or_default();
+        Some(detector)
+    }
+
+    /// Detects opportunities on one exchange given its latest orderbook per pair.
+    ///
+    /// `fees` maps pairs to their fees on this exchange; pairs without an entry are skipped.
+    /// Every triangle is evaluated in both directions, checking the deadline
+    /// before each pair of its first two assets; the search gives up and
+    /// returns None once it passes.
+    pub fn detect_until(
+        &self,
+        exchange: &str,
+        books: &[Orderbook],
+        fees: &HashMap<String, Fees>,
+        deadline: Instant,
+    ) -> Option<Vec<Opportunity>> {
+        let now = SystemTime::now();
+        let mut edges: HashMap<(&str, &str), Step> = HashMap::new();
+        let mut assets = BTreeSet::new();
+
+        for book in books {
+            if book.exchange != exchange || !self.is_fresh(book, now) {
+                continue;
+            }
+            let Some((base, quote)) = book.pair.split_once('/') else {
+                continue;
+            };
+            let Some(pair_fees) = fees.get(&book.pair) else {
+                continue;
+            };
+
+            let fee = pair_fees.taker;
+            edges.insert((quote, base), Step::Trade { book, side: OrderSide::Buy, fee });
+            edges.insert((base, quote), Step::Trade { book, side: OrderSide::Sell, fee });
+            assets.insert(base);
+            assets.insert(quote);
+        }
+
+        let assets: Vec<&str> = assets.into_iter().collect();
+        let mut opportunities = Vec::new();
+
+        for (i, a) in assets.iter().enumerate() {
+            for (j, b) in assets.iter().enumerate().skip(i + 1) {
+                if expired(deadline) {
+                    return None;
+                }
+                if !edges.contains_key(&(*a, *b)) {
+                    continue;
+                }
+                for c in assets.iter().skip(j + 1) {
+                    if !edges.contains_key(&(*b, *c)) || !edges.contains_key(&(*a, *c)) {
+                        continue;
+                    }
+
+                    for cycle in [[*a, *b, *c], [*a, *c, *b]] {
+                        let Some(cycle) = self.rotate_to_start(cycle) else {
+                            continue;
+                        };
+                        let legs = [
+                            edges[&(cycle[0], cycle[1])],
+                            edges[&(cycle[1], cycle[2])],
+                            edges[&(cycle[2], cycle[0])],
+                        ];
+                        if let Some(opp) = self.evaluate(exchange, &cycle, &legs) {
+                            opportunities.push(opp);
+                        }
+                    }
+                }
+            }
+        }
+
+        Some(opportunities)
+    }
+
+    /// Rotates a cycle so it starts in a configured start asset.
+    fn rotate_to_start<'a>(&self, cycle: [&'a str; 3]) -> Option<[&'a str; 3]> {
+        if self.start_assets.is_empty() {
+            return Some(cycle);
+        }
+        let start = self
+            .start_assets
+            .iter()
+            .find_map(|asset| cycle.iter().position(|a| a == asset))?;
+        Some([cycle[start], cycle[(start + 1) % 3], cycle[(start + 2) % 3]])
+    }
+
+    /// Returns true if the orderbook is recent enough to trade on.
+    fn is_fresh(&self, book: &Orderbook, now: SystemTime) -> bool {
+        if self.max_book_age.is_zero() {
+            return true;
+        }
+        now.duration_since(book.timestamp)
+            .map(|age| age <= self.max_book_age)
+            .unwrap_or(true)
+    }
+
+    /// Walks the three books and builds the opportunity if it clears the threshold.
+    fn evaluate(&self, exchange: &str, cycle: &[&str; 3], legs: &[Step; 3]) -> Option<Opportunity> {
+        let fill = cycle::walk(legs)?;
+
+        let net_profit = fill.amount_out - fill.amount_in;
+        let profit_percent = net_profit / fill.amount_in;
+        if profit_percent < self.min_profit_threshold {
+            return None;
+        }
+
+        let detected_at = Utc::now();
+        let expires_at =
+            detected_at + chrono::Duration::from_std(self.opportunity_ttl).unwrap_or_default();
+
+        let legs = fill.legs;
+        Some(Opportunity {
+            id: generate_opportunity_id(detected_at),
+            opportunity_type: OpportunityType::Triangular,
+            pair: cycle.join("/"),
+            buy_exchange: exchange.to_string(),
+            sell_exchange: exchange.to_string(),
+            buy_price: legs[0].price,
+            sell_price: legs[2].price,
+            quantity: fill.amount_in,
+            gross_profit: fill.gross_out - fill.amount_in,
+            net_profit,
+            // No transfers are needed when every leg is on the same exchange
+            net_profit_after_rebalance: net_profit,
+            profit_percent,
+            buy_fee: legs[0].fee,
+            sell_fee: legs[2].fee,
+            detected_at,
+            expires_at,
+            legs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::far_deadline;
+    use crate::domain::PriceLevel;
+    use std::str::FromStr;
+
+    /// Searches one exchange without a deadline that could pass.
+    fn detect(exchange: &str, books: &[Orderbook], fees: &HashMap<String, Fees>) -> Vec<Opportunity> {
+        detector().detect_until(exchange, books, fees, far_deadline()).unwrap()
+    }
+
+    fn level(price: &str, quantity: &str) -> PriceLevel {
+        PriceLevel {
+            price: Decimal::from_str(price).unwrap(),
+            quantity: Decimal::from_str(quantity).unwrap(),
+        }
+    }
+
+    fn book(pair: &str, bids: Vec<PriceLevel>, asks: Vec<PriceLevel>) -> Orderbook {
+        Orderbook {
+            pair: pair.to_string(),
+            exchange: "ex".to_string(),
+            bids,
+            asks,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    fn fees(taker: &str) -> HashMap<String, Fees> {
+        let taker = Decimal::from_str(taker).unwrap();
+        ["BTC/USDT", "ETH/BTC", "ETH/USDT"]
+            .into_iter()
+            .map(|pair| (pair.to_string(), Fees::new(taker, taker)))
+            .collect()
+    }
+
+    fn detector() -> TriangularDetector {
+        TriangularDetector::new(Decimal::ZERO, vec!["USDT".to_string()], Duration::from_secs(5))
+    }
+
+    /// ETH is cheap against BTC: USDT -> BTC -> ETH -> USDT is profitable.
+    fn books() -> Vec<Orderbook> {
+        vec![
+            book("BTC/USDT", vec![level("39990", "1")], vec![level("40000", "1")]),
+            book("ETH/BTC", vec![level("0.0499", "10")], vec![level("0.05", "10")]),
+            book("ETH/USDT", vec![level("2100", "4")], vec![level("2110", "10")]),
+        ]
+    }
+
+    #[test]
+    fn test_detects_forward_cycle() {
+        let opps = detect("ex", &books(), &fees("0"));
+        assert_eq!(opps.len(), 1);
+
+        let opp = &opps[0];
+        assert_eq!(opp.opportunity_type, OpportunityType::Triangular);
+        assert_eq!(opp.pair, "USDT/BTC/ETH");
+        assert_eq!(opp.legs.len(), 3);
+        assert_eq!(opp.legs[0].side, OrderSide::Buy);
+        assert_eq!(opp.legs[1].side, OrderSide::Buy);
+        assert_eq!(opp.legs[2].side, OrderSide::Sell);
+
+        // ETH/USDT bids (4 ETH) are the bottleneck: 4 ETH = 0.2 BTC = 8000 USDT
+        assert_eq!(opp.quantity, Decimal::from(8000));
+        assert_eq!(opp.legs[2].quantity, Decimal::from(4));
+        // 4 ETH * 2100 = 8400 USDT
+        assert_eq!(opp.net_profit, Decimal::from(400));
+        assert_eq!(opp.gross_profit, opp.net_profit);
+    }
+
+    #[test]
+    fn test_evaluates_reverse_direction() {
+        // ETH is expensive against BTC: USDT -> ETH -> BTC -> USDT
+        let books = vec![
+            book("BTC/USDT", vec![level("40000", "1")], vec![level("40010", "1")]),
+            book("ETH/BTC", vec![level("0.06", "10")], vec![level("0.061", "10")]),
+            book("ETH/USDT", vec![level("2000", "10")], vec![level("2000", "1")]),
+        ];
+
+        let opps = detect("ex", &books, &fees("0"));
+        assert_eq!(opps.len(), 1);
+        assert_eq!(opps[0].pair, "USDT/ETH/BTC");
+        // 1 ETH -> 0.06 BTC -> 2400 USDT for 2000 USDT
+        assert_eq!(opps[0].quantity, Decimal::from(2000));
+        assert_eq!(opps[0].net_profit, Decimal::from(400));
+    }
+
+    #[test]
+    fn test_fees_are_charged_per_leg() {
+        // Three 2% fees cost ~5.9%, which exceeds the 5% edge
+        assert!(detect("ex", &books(), &fees("0.02")).is_empty());
+
+        let opps = detect("ex", &books(), &fees("0.001"));
+        assert_eq!(opps.len(), 1);
+        assert!(opps[0].net_profit < opps[0].gross_profit);
+    }
+
+    #[test]
+    fn test_requires_all_three_pairs() {
+        let mut books = books();
+        books.pop();
+        assert!(detect("ex", &books, &fees("0")).is_empty());
+
+        // Books of other exchanges are ignored
+        assert!(detect("other", &self::books(), &fees("0")).is_empty());
+    }
+
+    #[test]
+    fn test_detect_until_gives_up_at_the_deadline() {
+        let deadline = far_deadline();
+        let opps = detector().detect_until("ex", &books(), &fees("0"), deadline).unwrap();
+        assert_eq!(opps.len(), 1);
+
+        assert!(detector().detect_until("ex", &books(), &fees("0"), Instant::now()).is_none());
+    }
+}
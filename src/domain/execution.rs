@@ -0,0 +1,78 @@
+//! Execution of an arbitrage opportunity.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// ExecutionStatus represents the outcome of executing an opportunity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionStatus {
+    /// Orders are being placed or filled.
+    Pending,
+    /// Every leg was filled.
+    Completed,
+    /// Some legs were filled and others were not.
+    Partial,
+    /// No leg was filled.
+    Failed,
+}
+
+impl std::fmt::Display for ExecutionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionStatus::Pending => write!(f, "pending"),
+            ExecutionStatus::Completed => write!(f, "completed"),
+            ExecutionStatus::Partial => write!(f, "partial"),
+            ExecutionStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl std::str::FromStr for ExecutionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(ExecutionStatus::Pending),
+            "completed" => Ok(ExecutionStatus::Completed),
+            "partial" => Ok(ExecutionStatus::Partial),
+            "failed" => Ok(ExecutionStatus::Failed),
+            _ => Err(format!("Unknown execution status: {}", s)),
+        }
+    }
+}
+
+/// ExecutionLeg references the order placed for one leg of an opportunity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionLeg {
+    /// Exchange where the order was placed.
+    pub exchange: String,
+    /// Order ID assigned by the exchange.
+    pub order_id: String,
+}
+
+/// Execution links an opportunity to the orders that realised it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Execution {
+    /// Unique identifier for this execution.
+    pub id: String,
+    /// ID of the executed opportunity.
+    pub opportunity_id: String,
+    /// Pair (or asset cycle) of the executed opportunity.
+    pub pair: String,
+    /// Current outcome.
+    pub status: ExecutionStatus,
+    /// Orders placed, in leg order.
+    pub legs: Vec<ExecutionLeg>,
+    /// Realised profit in quote currency, net of fees.
+    pub realized_pnl: Decimal,
+    /// Total fees paid, in quote currency.
+    pub fees: Decimal,
+    /// When the first order was placed.
+    pub started_at: DateTime<Utc>,
+    /// When the last leg settled; None while pending.
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Error that stopped the execution, if any.
+    pub error: Option<String>,
+}
@@ -0,0 +1,105 @@
+//! Lifecycle of an arbitrage opportunity across detection cycles.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::Opportunity;
+
+/// CloseReason indicates why an opportunity stopped being tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// No fresh data confirmed the opportunity before its TTL ran out.
+    Expired,
+    /// Fresh orderbooks no longer show a profitable spread.
+    SpreadCollapsed,
+    /// The opportunity was executed.
+    // TODO: Produced once the executor reports results (see Bot::detect_and_execute)
+    Executed,
+}
+
+impl std::fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CloseReason::Expired => write!(f, "expired"),
+            CloseReason::SpreadCollapsed => write!(f, "spread_collapsed"),
+            CloseReason::Executed => write!(f, "executed"),
+        }
+    }
+}
+
+impl std::str::FromStr for CloseReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "expired" => Ok(CloseReason::Expired),
+            "spread_collapsed" => Ok(CloseReason::SpreadCollapsed),
+            "executed" => Ok(CloseReason::Executed),
+            _ => Err(format!("Unknown close reason: {}", s)),
+        }
+    }
+}
+
+/// OpportunityLifecycle summarises one opportunity from first to last detection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpportunityLifecycle {
+    /// ID of the detection that opened the lifecycle.
+    pub opportunity_id: String,
+    /// When the opportunity was first detected.
+    pub first_seen: DateTime<Utc>,
+    /// When the opportunity was last detected.
+    pub last_seen: DateTime<Utc>,
+    /// Number of detection cycles that reported the opportunity.
+    pub observations: u32,
+    /// Highest profit percentage observed.
+    pub peak_profit_percent: Decimal,
+    /// Mean profit percentage over all observations.
+    pub avg_profit_percent: Decimal,
+    /// Profit percentage at the last observation.
+    pub last_profit_percent: Decimal,
+    /// When the lifecycle was closed; None while it is open.
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Why the lifecycle was closed.
+    pub close_reason: Option<CloseReason>,
+}
+
+impl OpportunityLifecycle {
+    /// Opens a lifecycle from its first detection.
+    pub fn open(opportunity: &Opportunity) -> Self {
+        Self {
+            opportunity_id: opportunity.id.clone(),
+            first_seen: opportunity.detected_at,
+            last_seen: opportunity.detected_at,
+            observations: 1,
+            peak_profit_percent: opportunity.profit_percent,
+            avg_profit_percent: opportunity.profit_percent,
+            last_profit_percent: opportunity.profit_percent,
+            closed_at: None,
+            close_reason: None,
+        }
+    }
+
+    /// Records another detection of the same opportunity.
+    pub fn observe(&mut self, opportunity: &Opportunity) {
+        let profit = opportunity.profit_percent;
+        self.observations += 1;
+        self.last_seen = opportunity.detected_at;
+        self.peak_profit_percent = self.peak_profit_percent.max(profit);
+        self.avg_profit_percent +=
+            (profit - self.avg_profit_percent) / Decimal::from(self.observations);
+        self.last_profit_percent = profit;
+    }
+
+    /// Closes the lifecycle.
+    pub fn close(&mut self, reason: CloseReason, at: DateTime<Utc>) {
+        self.closed_at = Some(at);
+        self.close_reason = Some(reason);
+    }
+
+    /// Returns how long the opportunity was observed.
+    pub fn duration(&self) -> chrono::Duration {
+        self.last_seen - self.first_seen
+    }
+}
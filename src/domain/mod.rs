@@ -1,11 +1,17 @@
 //! Domain models for arbitrage opportunities.
 
+pub(crate) mod execution;
 mod fees;
+mod lifecycle;
 mod opportunity;
 mod order;
 mod orderbook;
+mod transfer;
 
+pub use execution::{Execution, ExecutionLeg};
 pub use fees::Fees;
-pub use opportunity::{Opportunity, OpportunityType};
+pub use lifecycle::{CloseReason, OpportunityLifecycle};
+pub use opportunity::{Opportunity, OpportunityLeg, OpportunityType};
 pub use order::{Order, OrderSide, OrderStatus, OrderType, Trade};
 pub use orderbook::{Orderbook, PriceLevel};
+pub use transfer::TransferCosts;
@@ -4,18 +4,26 @@ use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use super::OrderSide;
+
 /// OpportunityType indicates the type of arbitrage opportunity.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum OpportunityType {
     /// Cross-exchange arbitrage between two different exchanges.
     CrossExchange,
+    /// Triangular arbitrage through three pairs on a single exchange.
+    Triangular,
+    /// Cycle of conversions over several exchanges and assets.
+    MultiHop,
 }
 
 impl std::fmt::Display for OpportunityType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             OpportunityType::CrossExchange => write!(f, "cross_exchange"),
+            OpportunityType::Triangular => write!(f, "triangular"),
+            OpportunityType::MultiHop => write!(f, "multi_hop"),
         }
     }
 }
@@ -26,24 +34,44 @@ impl std::str::FromStr for OpportunityType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "cross_exchange" => Ok(OpportunityType::CrossExchange),
+            "triangular" => Ok(OpportunityType::Triangular),
+            "multi_hop" => Ok(OpportunityType::MultiHop),
             _ => Err(format!("Unknown opportunity type: {}", s)),
         }
     }
 }
 
+/// OpportunityLeg is a single order that is part of an opportunity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpportunityLeg {
+    /// Exchange where the order is placed.
+    pub exchange: String,
+    /// Trading pair in "BASE/QUOTE" format.
+    pub pair: String,
+    /// Whether the base currency is bought or sold.
+    pub side: OrderSide,
+    /// Average execution price across the consumed levels.
+    pub price: Decimal,
+    /// Quantity of base currency traded.
+    pub quantity: Decimal,
+    /// Taker fee charged on this leg.
+    pub fee: Decimal,
+}
+
 /// Opportunity represents a detected arbitrage opportunity.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Opportunity {
     /// Unique identifier for this opportunity.
     pub id: String,
-    /// Type indicates cross-exchange or triangular arbitrage.
+    /// Type indicates cross-exchange, triangular or multi-hop arbitrage.
     #[serde(rename = "type")]
     pub opportunity_type: OpportunityType,
-    /// Trading pair (e.g., "BTC/USDT").
+    /// Trading pair (e.g., "BTC/USDT"); for triangular and multi-hop opportunities
+    /// the asset cycle starting with the profit currency (e.g., "USDT/BTC/ETH").
     pub pair: String,
-    /// Exchange where to buy (for cross-exchange).
+    /// Exchange where to buy (for paths, the exchange of the first leg).
     pub buy_exchange: String,
-    /// Exchange where to sell (for cross-exchange).
+    /// Exchange where to sell (for paths, the exchange of the last leg).
     pub sell_exchange: String,
     /// Ask price on the buy exchange.
     pub buy_price: Decimal,
@@ -55,6 +83,9 @@ pub struct Opportunity {
     pub gross_profit: Decimal,
     /// Profit after all fees.
     pub net_profit: Decimal,
+    /// Net profit minus the amortised cost of rebalancing transfers.
+    #[serde(default)]
+    pub net_profit_after_rebalance: Decimal,
     /// Net profit as a percentage of the trade value.
     pub profit_percent: Decimal,
     /// Taker fee on the buy exchange.
@@ -65,16 +96,8 @@ pub struct Opportunity {
     pub detected_at: DateTime<Utc>,
     /// When this opportunity is considered stale.
     pub expires_at: DateTime<Utc>,
-}
-
-impl Opportunity {
-    /// Returns true if the opportunity has expired.
-    pub fn is_expired(&self) -> bool {
-        Utc::now() > self.expires_at
-    }
-
-    /// Returns true if the net profit is positive.
-    pub fn is_profitable(&self) -> bool {
-        self.net_profit > Decimal::ZERO
-    }
+    /// Orders that realise the opportunity, in execution order.
+    /// Empty for opportunities stored before legs were recorded.
+    #[serde(default)]
+    pub legs: Vec<OpportunityLeg>,
 }
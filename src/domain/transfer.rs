@@ -0,0 +1,192 @@
+//! Transfer cost model for rebalancing inventory between exchanges.
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+
+/// TransferCosts holds withdrawal fees per (exchange, asset) and explicit
+/// per-route costs, and attributes a share of each rebalance to a trade.
+///
+/// A trade's share of a transfer is the amount it moves relative to the
+/// asset's rebalance size (the amount one transfer carries), so a trade of a
+/// tenth of the rebalance size pays a tenth of the fee. Assets without a
+/// rebalance size fall back to a flat share of `1 / amortization_trades`.
+///
+/// Exchanges here are accounts; accounts on the same venue move funds between
+/// each other with free internal transfers.
+#[derive(Debug, Clone)]
+pub struct TransferCosts {
+    /// Withdrawal fee in units of the asset, keyed by (exchange, asset).
+    withdrawal_fees: HashMap<(String, String), Decimal>,
+    /// Route-specific fee in units of the asset, keyed by (asset, from, to).
+    routes: HashMap<(String, String, String), Decimal>,
+    /// Venue of each account, for accounts that share one.
+    venues: HashMap<String, String>,
+    /// Amount of an asset one rebalancing transfer carries, keyed by asset.
+    rebalance_sizes: HashMap<String, Decimal>,
+    /// Number of trades one rebalancing transfer is spread over, for assets
+    /// without a rebalance size.
+    amortization_trades: u32,
+}
+
+impl Default for TransferCosts {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl TransferCosts {
+    /// Creates an empty cost model; without rebalance sizes, costs are spread
+    /// over `amortization_trades` trades.
+    pub fn new(amortization_trades: u32) -> Self {
+        Self {
+            withdrawal_fees: HashMap::new(),
+            routes: HashMap::new(),
+            venues: HashMap::new(),
+            rebalance_sizes: HashMap::new(),
+            amortization_trades: amortization_trades.max(1),
+        }
+    }
+
+    /// Sets the withdrawal fee of an asset on an exchange.
+    pub fn set_withdrawal_fee(&mut self, exchange: &str, asset: &str, fee: Decimal) {
+        self.withdrawal_fees
+            .insert((exchange.to_string(), asset.to_string()), fee);
+    }
+
+    /// Sets the cost of moving an asset along a specific route.
+    /// Route costs take precedence over the source exchange's withdrawal fee.
+    pub fn set_route_fee(&mut self, asset: &str, from: &str, to: &str, fee: Decimal) {
+        self.routes
+            .insert((asset.to_string(), from.to_string(), to.to_string()), fee);
+    }
+
+    /// Sets the amount of an asset one rebalancing transfer carries.
+    pub fn set_rebalance_size(&mut self, asset: &str, size: Decimal) {
+        self.rebalance_sizes.insert(asset.to_string(), size);
+    }
+
+    /// Sets the venue an account trades on.
+    pub fn set_venue(&mut self, account: &str, venue: &str) {
+        self.venues.insert(account.to_string(), venue.to_string());
+    }
+
+    /// Returns the cost in units of `asset` of moving it from one exchange to another.
+    /// Transfers between accounts on the same venue are free unless a route says otherwise.
+    pub fn transfer_fee(&self, asset: &str, from: &str, to: &str) -> Option<Decimal> {
+        if let Some(fee) = self
+            .routes
+            .get(&(asset.to_string(), from.to_string(), to.to_string()))
+        {
+            return Some(*fee);
+        }
+        if self.same_venue(from, to) {
+            return Some(Decimal::ZERO);
+        }
+        self.withdrawal_fees
+            .get(&(from.to_string(), asset.to_string()))
+            .copied()
+    }
+
+    /// Returns true if both accounts are known to trade on the same venue.
+    fn same_venue(&self, a: &str, b: &str) -> bool {
+        matches!((self.venues.get(a), self.venues.get(b)), (Some(a), Some(b)) if a == b)
+    }
+
+    /// Returns the fraction of one transfer of `asset` used up by a trade that
+    /// moves `amount` of it.
+    fn share(&self, asset: &str, amount: Decimal) -> Decimal {
+        match self.rebalance_sizes.get(asset) {
+            Some(size) if size.is_sign_positive() && !size.is_zero() => amount / size,
+            _ => Decimal::ONE / Decimal::from(self.amortization_trades),
+        }
+    }
+
+    /// Returns the share of a single transfer's fee attributed to a trade that
+    /// moves `amount` of `asset`, in units of `asset`. Unknown fees count as zero.
+    pub fn amortised_transfer_fee(&self, asset: &str, from: &str, to: &str, amount: Decimal) -> Decimal {
+        self.transfer_fee(asset, from, to).unwrap_or_default() * self.share(asset, amount)
+    }
+
+    /// Returns the quote-currency share of a rebalance attributed to a trade of
+    /// `quantity` base units bought on `buy_exchange` and sold on `sell_exchange`.
+    pub fn amortised_cost(
+        &self,
+        pair: &str,
+        buy_exchange: &str,
+        sell_exchange: &str,
+        base_price: Decimal,
+        quantity: Decimal,
+    ) -> Decimal {
+        let Some((base, quote)) = pair.split_once('/') else {
+            return Decimal::ZERO;
+        };
+
+        let base_fee = self.amortised_transfer_fee(base, buy_exchange, sell_exchange, quantity);
+        let quote_fee =
+            self.amortised_transfer_fee(quote, sell_exchange, buy_exchange, quantity * base_price);
+
+        base_fee * base_price + quote_fee
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_fee_overrides_withdrawal_fee() {
+        let mut costs = TransferCosts::default();
+        costs.set_withdrawal_fee("poloniex", "BTC", Decimal::new(5, 4));
+        assert_eq!(costs.transfer_fee("BTC", "poloniex", "gate"), Some(Decimal::new(5, 4)));
+
+        costs.set_route_fee("BTC", "poloniex", "gate", Decimal::new(1, 4));
+        assert_eq!(costs.transfer_fee("BTC", "poloniex", "gate"), Some(Decimal::new(1, 4)));
+        assert_eq!(costs.transfer_fee("BTC", "gate", "poloniex"), None);
+    }
+
+    #[test]
+    fn test_amortised_cost_without_rebalance_size() {
+        let mut costs = TransferCosts::new(10);
+        costs.set_withdrawal_fee("poloniex", "BTC", Decimal::new(5, 4));
+        costs.set_withdrawal_fee("gate", "USDT", Decimal::ONE);
+
+        // 0.0005 BTC * 40000 + 1 USDT = 21 USDT per rebalance, split over 10 trades
+        let per_trade =
+            costs.amortised_cost("BTC/USDT", "poloniex", "gate", Decimal::from(40000), Decimal::ONE);
+        assert_eq!(per_trade, Decimal::new(21, 1));
+    }
+
+    #[test]
+    fn test_amortised_cost_scales_with_quantity() {
+        let mut costs = TransferCosts::new(10);
+        costs.set_withdrawal_fee("poloniex", "BTC", Decimal::new(5, 4));
+        costs.set_withdrawal_fee("gate", "USDT", Decimal::ONE);
+        costs.set_rebalance_size("BTC", Decimal::ONE);
+        costs.set_rebalance_size("USDT", Decimal::from(40000));
+
+        let cost = |quantity| {
+            costs.amortised_cost("BTC/USDT", "poloniex", "gate", Decimal::from(40000), quantity)
+        };
+
+        // A full rebalance size pays the whole 21 USDT, a tenth pays a tenth
+        assert_eq!(cost(Decimal::ONE), Decimal::from(21));
+        assert_eq!(cost(Decimal::new(1, 1)), Decimal::new(21, 1));
+        assert_eq!(cost(Decimal::new(1, 3)), Decimal::new(21, 3));
+    }
+
+    #[test]
+    fn test_transfers_between_sub_accounts_are_free() {
+        let mut costs = TransferCosts::default();
+        costs.set_venue("poloniex", "poloniex");
+        costs.set_venue("poloniex-sub1", "poloniex");
+        costs.set_venue("gate", "gate");
+        costs.set_withdrawal_fee("poloniex", "BTC", Decimal::new(5, 4));
+
+        assert_eq!(costs.transfer_fee("BTC", "poloniex", "poloniex-sub1"), Some(Decimal::ZERO));
+        assert_eq!(costs.transfer_fee("BTC", "poloniex", "gate"), Some(Decimal::new(5, 4)));
+
+        costs.set_route_fee("BTC", "poloniex", "poloniex-sub1", Decimal::new(1, 4));
+        assert_eq!(costs.transfer_fee("BTC", "poloniex", "poloniex-sub1"), Some(Decimal::new(1, 4)));
+    }
+}
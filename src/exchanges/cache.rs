@@ -0,0 +1,114 @@
+//! In-memory cache of the latest orderbook per exchange and pair.
+
+use std::collections::{HashMap, HashSet};
+
+use tokio::sync::{Mutex, Notify, RwLock};
+
+use crate::domain::Orderbook;
+
+/// OrderbookCache keeps the most recent orderbook snapshot for every
+/// (exchange, pair) combination received from the streams, and records which
+/// ones changed so that detection can run on updates.
+#[derive(Default)]
+pub struct OrderbookCache {
+    /// Map of pair to exchange name to orderbook.
+    books: RwLock<HashMap<String, HashMap<String, Orderbook>>>,
+    /// (exchange, pair) of the books updated since the last `take_changed`.
+    changed: Mutex<HashSet<(String, String)>>,
+    /// Signalled when a book is updated.
+    notify: Notify,
+}
+
+impl OrderbookCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores an orderbook, replacing the previous snapshot for its exchange and pair.
+    pub async fn update(&self, orderbook: Orderbook) {
+        let key = (orderbook.exchange.clone(), orderbook.pair.clone());
+        {
+            let mut books = self.books.write().await;
+            books
+                .entry(orderbook.pair.clone())
+                .or_default()
+                .insert(orderbook.exchange.clone(), orderbook);
+        }
+
+        self.changed.lock().await.insert(key);
+        self.notify.notify_one();
+    }
+
+    /// Waits until a book has been updated since the last `take_changed`.
+    /// An update made while nobody waits wakes the next call immediately.
+    pub async fn changed(&self) {
+        self.notify.notified().await;
+    }
+
+    /// Returns the (exchange, pair) of the books updated since the last call.
+    /// Repeated updates of one book are reported once.
+    pub async fn take_changed(&self) -> HashSet<(String, String)> {
+        std::mem::take(&mut *self.changed.lock().await)
+    }
+
+    /// Returns the latest orderbooks for a pair across all exchanges.
+    pub async fn get_pair(&self, pair: &str) -> Vec<Orderbook> {
+        let books = self.books.read().await;
+        books
+            .get(pair)
+            .map(|by_exchange| by_exchange.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns every cached orderbook.
+    pub async fn get_all(&self) -> Vec<Orderbook> {
+        let books = self.books.read().await;
+        books
+            .values()
+            .flat_map(|by_exchange| by_exchange.values().cloned())
+            .collect()
+    }
+
+    /// Drops the orderbooks of a pair that is no longer monitored.
+    pub async fn remove_pair(&self, pair: &str) {
+        self.books.write().await.remove(pair);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn book(exchange: &str, pair: &str) -> Orderbook {
+        Orderbook {
+            exchange: exchange.to_string(),
+            pair: pair.to_string(),
+            bids: Vec::new(),
+            asks: Vec::new(),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changed_coalesces_repeated_updates() {
+        let cache = OrderbookCache::new();
+        cache.update(book("poloniex", "BTC/USDT")).await;
+        cache.update(book("poloniex", "BTC/USDT")).await;
+        cache.update(book("gate", "ETH/USDT")).await;
+
+        // Updates made before anyone waits are not lost
+        cache.changed().await;
+        let changed = cache.take_changed().await;
+        assert_eq!(
+            changed,
+            HashSet::from([
+                ("poloniex".to_string(), "BTC/USDT".to_string()),
+                ("gate".to_string(), "ETH/USDT".to_string()),
+            ])
+        );
+        assert!(cache.take_changed().await.is_empty());
+        assert_eq!(cache.get_pair("BTC/USDT").await.len(), 1);
+    }
+}
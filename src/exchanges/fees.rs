@@ -0,0 +1,235 @@
+//! Per-pair fee schedule with periodic refresh and config overrides.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use rust_decimal::Decimal;
+
+use crate::config::{DEFAULT_REFRESH_INTERVAL, ExchangeConfig};
+use crate::domain::Fees;
+
+/// Discount that applies while the account holds enough of an asset.
+#[derive(Debug, Clone)]
+struct FeeDiscount {
+    asset: String,
+    min_balance: Decimal,
+    multiplier: Decimal,
+}
+
+/// Fee rates fetched from the exchange.
+#[derive(Debug, Default)]
+struct FetchedFees {
+    /// Account-wide rates (e.g., Poloniex `/feeinfo`).
+    account: Option<Fees>,
+    /// Pair-specific rates, where the exchange reports them.
+    pairs: HashMap<String, Fees>,
+    /// Combined multiplier of the currently active discounts.
+    discount: Decimal,
+    /// When the fetched rates were last updated.
+    refreshed_at: Option<Instant>,
+}
+
+/// FeeSchedule resolves the fees for each pair on one exchange.
+///
+/// Resolution order: config override, fetched pair rate, fetched account rate,
+/// configured `fee_maker`/`fee_taker`. Active discounts are applied last.
+#[derive(Debug)]
+pub struct FeeSchedule {
+    default: Fees,
+    overrides: HashMap<String, (Option<Decimal>, Option<Decimal>)>,
+    discounts: Vec<FeeDiscount>,
+    refresh_interval: Duration,
+    fetched: RwLock<FetchedFees>,
+}
+
+impl FeeSchedule {
+    /// Creates a schedule that always returns the given fees.
+    pub fn new(default: Fees) -> Self {
+        Self {
+            default,
+            overrides: HashMap::new(),
+            discounts: Vec::new(),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            fetched: RwLock::new(FetchedFees {
+                discount: Decimal::ONE,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Creates a schedule from exchange configuration.
+    pub fn from_config(config: &ExchangeConfig) -> Self {
+        let taker = config.fee_taker.unwrap_or_default();
+        let maker = config.fee_maker.unwrap_or(taker);
+
+        let mut schedule = Self::new(Fees::new(maker, taker));
+
+        if let Some(ref fees) = config.fees {
+            schedule.refresh_interval = fees.refresh_interval;
+
+            schedule.overrides = fees
+                .overrides
+                .iter()
+                .map(|(pair, o)| (pair.clone(), (o.maker, o.taker)))
+                .collect();
+
+            schedule.discounts = fees
+                .discounts
+                .iter()
+                .map(|d| FeeDiscount {
+                    asset: d.asset.clone(),
+                    min_balance: d.min_balance,
+                    multiplier: d.multiplier,
+                })
+                .collect();
+        }
+
+        schedule
+    }
+
+    /// Returns the effective fees for a pair.
+    pub fn fees(&self, pair: &str) -> Fees {
+        let fetched = self.fetched.read().unwrap();
+
+        let base = fetched
+            .pairs
+            .get(pair)
+            .copied()
+            .or(fetched.account)
+            .unwrap_or(self.default);
+
+        let (maker, taker) = match self.overrides.get(pair) {
+            Some((maker, taker)) => (maker.unwrap_or(base.maker), taker.unwrap_or(base.taker)),
+            None => (base.maker, base.taker),
+        };
+
+        Fees::new(maker * fetched.discount, taker * fetched.discount)
+    }
+
+    /// Stores account-wide rates fetched from the exchange.
+    pub fn set_account_fees(&self, fees: Fees) {
+        let mut fetched = self.fetched.write().unwrap();
+        fetched.account = Some(fees);
+        fetched.refreshed_at = Some(Instant::now());
+    }
+
+    /// Stores rates for a single pair fetched from the exchange.
+    pub fn set_pair_fees(&self, pair: &str, fees: Fees) {
+        let mut fetched = self.fetched.write().unwrap();
+        fetched.pairs.insert(pair.to_string(), fees);
+        fetched.refreshed_at = Some(Instant::now());
+    }
+
+    /// Re-evaluates conditional discounts against current balances.
+    pub fn apply_balances(&self, balances: &HashMap<String, Decimal>) {
+        let discount = self
+            .discounts
+            .iter()
+            .filter(|d| {
+                balances
+                    .get(&d.asset)
+                    .is_some_and(|balance| *balance >= d.min_balance)
+            })
+            .fold(Decimal::ONE, |acc, d| acc * d.multiplier);
+
+        self.fetched.write().unwrap().discount = discount;
+    }
+
+    /// Returns true if any discount depends on account balances.
+    pub fn has_discounts(&self) -> bool {
+        !self.discounts.is_empty()
+    }
+
+    /// Returns true if rates were never fetched or the refresh interval has elapsed.
+    pub fn is_stale(&self) -> bool {
+        self.fetched
+            .read()
+            .unwrap()
+            .refreshed_at
+            .is_none_or(|at| at.elapsed() >= self.refresh_interval)
+    }
+}
+
+/// Parses an optional decimal config string, ignoring invalid values.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::fees::{FeeConfig, FeeDiscountConfig, FeeOverrideConfig};
+    use crate::secrets::Secret;
+
+    fn exchange_config(fees: Option<FeeConfig>) -> ExchangeConfig {
+        ExchangeConfig {
+            enabled: true,
+            exchange: None,
+            testnet: false,
+            api_key: Secret::default(),
+            api_secret: Secret::default(),
+            fee_taker: Some(Decimal::new(1, 3)),
+            fee_maker: None,
+            fees,
+            rate_limit: None,
+            websocket: None,
+        }
+    }
+
+    #[test]
+    fn test_defaults_from_config() {
+        let schedule = FeeSchedule::from_config(&exchange_config(None));
+        let fees = schedule.fees("BTC/USDT");
+        assert_eq!(fees.taker, Decimal::new(1, 3));
+        // Maker falls back to taker when not configured
+        assert_eq!(fees.maker, Decimal::new(1, 3));
+    }
+
+    #[test]
+    fn test_fetched_rates_take_precedence_over_config() {
+        let schedule = FeeSchedule::from_config(&exchange_config(None));
+        schedule.set_account_fees(Fees::new(Decimal::new(9, 4), Decimal::new(12, 4)));
+        schedule.set_pair_fees("ETH/USDT", Fees::new(Decimal::ZERO, Decimal::new(5, 4)));
+
+        assert_eq!(schedule.fees("BTC/USDT").taker, Decimal::new(12, 4));
+        assert_eq!(schedule.fees("ETH/USDT").taker, Decimal::new(5, 4));
+        assert!(!schedule.is_stale());
+    }
+
+    #[test]
+    fn test_override_wins_over_fetched_rates() {
+        let fees = FeeConfig {
+            overrides: HashMap::from([(
+                "BTC/USDT".to_string(),
+                FeeOverrideConfig {
+                    maker: None,
+                    taker: Some(Decimal::new(2, 4)),
+                },
+            )]),
+            ..Default::default()
+        };
+        let schedule = FeeSchedule::from_config(&exchange_config(Some(fees)));
+        schedule.set_account_fees(Fees::new(Decimal::new(9, 4), Decimal::new(12, 4)));
+
+        let fees = schedule.fees("BTC/USDT");
+        assert_eq!(fees.taker, Decimal::new(2, 4));
+        assert_eq!(fees.maker, Decimal::new(9, 4));
+    }
+
+    #[test]
+    fn test_discount_applies_only_with_balance() {
+        let fees = FeeConfig {
+            discounts: vec![FeeDiscountConfig {
+                asset: "GT".to_string(),
+                min_balance: Decimal::ONE,
+                multiplier: Decimal::new(9, 1),
+            }],
+            ..Default::default()
+        };
+        let schedule = FeeSchedule::from_config(&exchange_config(Some(fees)));
+        assert!(schedule.has_discounts());
+
+        schedule.apply_balances(&HashMap::from([("GT".to_string(), Decimal::new(5, 1))]));
+        assert_eq!(schedule.fees("BTC/USDT").taker, Decimal::new(1, 3));
+
+        schedule.apply_balances(&HashMap::from([("GT".to_string(), Decimal::ONE)]));
+        assert_eq!(schedule.fees("BTC/USDT").taker, Decimal::new(9, 4));
+    }
+}
@@ -1,16 +1,20 @@
 //! Manager for handling multiple exchange connections.
 
 use super::{Exchange, ExchangeError, Result};
-use crate::config::{Config, ExchangeConfig};
+use crate::config::Config;
+use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 use super::poloniex;
 
+/// Venues accounts can be created on, in lowercase.
+pub const SUPPORTED_VENUES: &[&str] = &["poloniex"];
+
 /// Manager coordinates multiple exchange connections.
 pub struct Manager {
-    /// Map of exchange name to exchange instance.
+    /// Map of account id to exchange instance.
     exchanges: Arc<RwLock<HashMap<String, Arc<dyn Exchange>>>>,
 }
 
@@ -35,36 +39,34 @@ impl Manager {
 
             info!(exchange = %name, "Loading exchange from config");
 
-            let exchange = Self::create_exchange(name, exchange_config)?;
+            let exchange = Self::create_account(config, name)?;
             manager.register(exchange).await;
         }
 
         Ok(manager)
     }
 
-    /// Factory method to create an exchange instance based on name and config.
-    fn create_exchange(
-        name: &str,
-        config: &ExchangeConfig,
+    /// Factory method to create the account configured under `id`, dispatching
+    /// on its venue. The instance is named after the account, so several
+    /// accounts on one venue are kept apart.
+    ///
+    /// Every venue in `SUPPORTED_VENUES` must be dispatched here.
+    pub fn create_account(
+        config: &Config,
+        id: &str,
     ) -> Result<Arc<dyn Exchange>> {
-        match name.to_lowercase().as_str() {
-            "poloniex" => {
-                // TODO: Implement Poloniex exchange
-                Err(ExchangeError::Internal(format!(
-                    "exchange {} is not yet implemented",
-                    name
-                )))
-            }
-            "gate" | "gateio" | "gate.io" => {
-                // TODO: Implement Gate.io exchange
-                Err(ExchangeError::Internal(format!(
-                    "exchange {} is not yet implemented",
-                    name
-                )))
-            }
+        let venue = config
+            .exchanges
+            .get(id)
+            .map(|c| c.venue(id))
+            .unwrap_or(id);
+
+        match venue.to_lowercase().as_str() {
+            "poloniex" => Ok(Arc::new(poloniex::PoloniexExchange::from_config(id, config)?)),
             _ => Err(ExchangeError::Internal(format!(
-                "unknown exchange: {}",
-                name
+                "unknown exchange: {} (supported: {})",
+                venue,
+                SUPPORTED_VENUES.join(", ")
             ))),
         }
     }
@@ -130,6 +132,41 @@ impl Manager {
         Ok(())
     }
 
+    /// Refreshes fee schedules of all connected exchanges.
+    /// Failures are logged and the previous fees stay in effect.
+    pub async fn refresh_fees_all(&self) {
+        let exchanges = self.exchanges.read().await;
+        for (name, exchange) in exchanges.iter() {
+            if !exchange.is_connected() {
+                continue;
+            }
+            if let Err(e) = exchange.refresh_fees().await {
+                warn!(exchange = %name, error = %e, "Failed to refresh fees");
+            }
+        }
+    }
+
+    /// Fetches withdrawal fees from all connected exchanges, keyed by exchange name.
+    /// Exchanges that fail are logged and left out.
+    pub async fn withdrawal_fees_all(&self) -> HashMap<String, HashMap<String, Decimal>> {
+        let exchanges = self.exchanges.read().await;
+        let mut fees = HashMap::new();
+        for (name, exchange) in exchanges.iter() {
+            if !exchange.is_connected() {
+                continue;
+            }
+            match exchange.get_withdrawal_fees().await {
+                Ok(exchange_fees) => {
+                    fees.insert(name.clone(), exchange_fees);
+                }
+                Err(e) => {
+                    warn!(exchange = %name, error = %e, "Failed to fetch withdrawal fees");
+                }
+            }
+        }
+        fees
+    }
+
     /// Returns connection status for all exchanges.
     pub async fn status(&self) -> HashMap<String, bool> {
         let exchanges = self.exchanges.read().await;
@@ -151,7 +188,6 @@ mod tests {
     use super::*;
     use crate::domain::{Fees, Order, Orderbook, Trade};
     use async_trait::async_trait;
-    use rust_decimal::Decimal;
     use std::sync::atomic::{AtomicBool, Ordering};
     use tokio::sync::mpsc;
 
@@ -402,22 +438,26 @@ mod tests {
 
     #[tokio::test]
     async fn test_from_config_with_no_enabled_exchanges() {
-        use crate::config::{AppConfig, Config, ExchangeConfig};
+        use crate::config::{AppConfig, AppEnv, Config, ExchangeConfig};
+        use crate::secrets::Secret;
 
         let config = Config {
             app: AppConfig {
                 name: "test".to_string(),
-                env: "test".to_string(),
+                env: AppEnv::Development,
                 log_level: None,
             },
             exchanges: HashMap::from([(
                 "binance".to_string(),
                 ExchangeConfig {
                     enabled: false,
+                    exchange: None,
                     testnet: false,
-                    api_key: String::new(),
-                    api_secret: String::new(),
-                    fee_taker: Some("0.001".to_string()),
+                    api_key: Secret::default(),
+                    api_secret: Secret::default(),
+                    fee_taker: Some(Decimal::new(1, 3)),
+                    fee_maker: None,
+                    fees: None,
                     rate_limit: None,
                     websocket: None,
                 },
@@ -430,6 +470,9 @@ mod tests {
             notification: None,
             storage: None,
             balance: None,
+            secrets: None,
+            metrics: None,
+            api: None,
         };
 
         let manager = Manager::from_config(&config).await.unwrap();
@@ -439,22 +482,26 @@ mod tests {
 
     #[tokio::test]
     async fn test_from_config_with_unknown_exchange() {
-        use crate::config::{AppConfig, Config, ExchangeConfig};
+        use crate::config::{AppConfig, AppEnv, Config, ExchangeConfig};
+        use crate::secrets::Secret;
 
         let config = Config {
             app: AppConfig {
                 name: "test".to_string(),
-                env: "test".to_string(),
+                env: AppEnv::Development,
                 log_level: None,
             },
             exchanges: HashMap::from([(
                 "unknown_exchange".to_string(),
                 ExchangeConfig {
                     enabled: true,
+                    exchange: None,
                     testnet: false,
-                    api_key: String::new(),
-                    api_secret: String::new(),
-                    fee_taker: Some("0.001".to_string()),
+                    api_key: Secret::default(),
+                    api_secret: Secret::default(),
+                    fee_taker: Some(Decimal::new(1, 3)),
+                    fee_maker: None,
+                    fees: None,
                     rate_limit: None,
                     websocket: None,
                 },
@@ -467,10 +514,69 @@ mod tests {
             notification: None,
             storage: None,
             balance: None,
+            secrets: None,
+            metrics: None,
+            api: None,
         };
 
         let result = Manager::from_config(&config).await;
         assert!(result.is_err());
         assert!(matches!(result, Err(ExchangeError::Internal(_))));
     }
+
+    #[tokio::test]
+    async fn test_from_config_registers_sub_accounts_by_id() {
+        use crate::config::{AppConfig, AppEnv, Config, ExchangeConfig};
+        use crate::secrets::Secret;
+
+        let account = |venue: Option<&str>| ExchangeConfig {
+            enabled: true,
+            exchange: venue.map(str::to_string),
+            testnet: false,
+            api_key: Secret::default(),
+            api_secret: Secret::default(),
+            fee_taker: Some(Decimal::new(1, 3)),
+            fee_maker: None,
+            fees: None,
+            rate_limit: None,
+            websocket: None,
+        };
+        let config = Config {
+            app: AppConfig {
+                name: "test".to_string(),
+                env: AppEnv::Development,
+                log_level: None,
+            },
+            exchanges: HashMap::from([
+                ("poloniex".to_string(), account(None)),
+                ("poloniex-sub1".to_string(), account(Some("poloniex"))),
+            ]),
+            orderbook: None,
+            arbitrage: None,
+            execution: None,
+            risk: None,
+            pairs: vec!["BTC/USDT".to_string()],
+            notification: None,
+            storage: None,
+            balance: None,
+            secrets: None,
+            metrics: None,
+            api: None,
+        };
+
+        let manager = Manager::from_config(&config).await.unwrap();
+        let mut exchanges = manager.list().await;
+        exchanges.sort();
+        assert_eq!(exchanges, vec!["poloniex", "poloniex-sub1"]);
+        assert_eq!(manager.get("poloniex-sub1").await.unwrap().name(), "poloniex-sub1");
+
+        // Every supported venue can be created, and only those
+        let mut config = config;
+        for venue in SUPPORTED_VENUES {
+            config.exchanges.insert(venue.to_string(), account(Some(venue)));
+            assert_eq!(Manager::create_account(&config, venue).unwrap().name(), *venue);
+        }
+        config.exchanges.insert("kraken".to_string(), account(None));
+        assert!(Manager::create_account(&config, "kraken").is_err());
+    }
 }
@@ -1,5 +1,7 @@
 //! Exchange integration abstractions and implementations.
 
+mod cache;
+mod fees;
 mod manager;
 pub mod poloniex;
 pub(crate) mod utils;
@@ -11,7 +13,9 @@ use std::collections::HashMap;
 use thiserror::Error;
 use tokio::sync::mpsc;
 
-pub use manager::Manager;
+pub use cache::OrderbookCache;
+pub use fees::FeeSchedule;
+pub use manager::{Manager, SUPPORTED_VENUES};
 
 /// Exchange errors.
 #[derive(Debug, Error)]
@@ -44,6 +48,19 @@ pub enum ExchangeError {
 /// Result type for exchange operations.
 pub type Result<T> = std::result::Result<T, ExchangeError>;
 
+/// Request and connection counters of an exchange account, exported as metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExchangeUsage {
+    /// REST requests sent since startup.
+    pub rest_requests: u64,
+    /// REST requests counted in the current rate limit window.
+    pub rest_window_requests: u64,
+    /// REST requests allowed per rate limit window.
+    pub rest_window_limit: u64,
+    /// WebSocket reconnections since startup.
+    pub websocket_reconnects: u64,
+}
+
 /// Exchange trait defines the interface for cryptocurrency exchange integrations.
 #[async_trait]
 pub trait Exchange: Send + Sync {
@@ -74,10 +91,27 @@ pub trait Exchange: Send + Sync {
         pairs: Vec<String>,
     ) -> Result<mpsc::UnboundedReceiver<Orderbook>>;
 
+    /// UpdateOrderbookSubscription adds and removes pairs on the stream opened by
+    /// SubscribeOrderbook, keeping the connection and its channel.
+    /// Exchanges that cannot change a live subscription return an error.
+    async fn update_orderbook_subscription(
+        &self,
+        added: Vec<String>,
+        removed: Vec<String>,
+    ) -> Result<()> {
+        let _ = (added, removed);
+        Err(ExchangeError::Internal(format!(
+            "{} cannot change orderbook subscriptions",
+            self.name()
+        )))
+    }
+
     /// PlaceOrder submits a new order to the exchange.
     /// Returns the resulting trade if the order is filled immediately (market orders),
     /// or a trade with zero quantity if the order is placed but not yet filled (limit orders).
     /// Returns ErrInsufficientFunds if balance is not enough.
+    /// Nothing places orders until trade execution is implemented.
+    #[allow(dead_code)]
     async fn place_order(&self, order: Order) -> Result<Trade>;
 
     /// CancelOrder cancels an open order by its ID.
@@ -97,7 +131,27 @@ pub trait Exchange: Send + Sync {
     /// Fees are expressed as decimals (e.g., 0.001 for 0.1%).
     fn get_fees(&self, pair: &str) -> Fees;
 
-    /// Name returns the unique identifier of this exchange (e.g., "binance", "bybit").
+    /// RefreshFees re-fetches fee rates from the exchange and updates the cached schedule.
+    /// Implementations may skip the request while cached rates are still fresh.
+    /// Exchanges without a fee endpoint keep using the configured fees.
+    async fn refresh_fees(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// GetWithdrawalFees returns the flat withdrawal fee per asset in units of that asset.
+    /// Exchanges without a currency endpoint return an empty map.
+    async fn get_withdrawal_fees(&self) -> Result<HashMap<String, Decimal>> {
+        Ok(HashMap::new())
+    }
+
+    /// Usage returns the request and connection counters of this account.
+    /// Exchanges that do not track them report zeros.
+    fn usage(&self) -> ExchangeUsage {
+        ExchangeUsage::default()
+    }
+
+    /// Name returns the unique identifier of this exchange account: its key under
+    /// `exchanges` in the config (e.g., "poloniex", "poloniex-sub1").
     fn name(&self) -> &str;
 
     /// SupportedPairs returns a list of trading pairs available on this exchange.
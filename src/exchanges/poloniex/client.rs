@@ -1,7 +1,7 @@
 //! HTTP client for the Poloniex Spot API.
 
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
@@ -14,6 +14,7 @@ use sha2::Sha256;
 use thiserror::Error;
 use tracing::{debug, warn};
 use crate::config::ExchangeConfig;
+use crate::secrets::Secret;
 
 /// Default receive window for signed requests in milliseconds.
 const DEFAULT_RECEIVE_WINDOW: i64 = 5000;
@@ -57,14 +58,14 @@ pub type Result<T> = std::result::Result<T, ClientError>;
 /// Configuration for creating a new Client.
 pub struct ClientConfig {
     pub base_url: String,
-    pub api_key: String,
-    pub api_secret: String,
+    pub api_key: Secret,
+    pub api_secret: Secret,
     pub rate_limit: i64,
     pub receive_window: i64,
 }
 
 impl ClientConfig {
-    pub fn new(api_key: String, api_secret: String, rate_limit: i64) -> Self {
+    pub fn new(api_key: Secret, api_secret: Secret, rate_limit: i64) -> Self {
         Self {
             base_url: BASE_HTTP_API_URL.to_string(),
             api_key,
@@ -89,6 +90,8 @@ pub struct Client {
     config: ClientConfig,
     http_client: HttpClient,
     request_count: AtomicI64,
+    /// Requests sent since the client was created.
+    total_requests: AtomicU64,
     rate_limit_state: Mutex<RateLimitState>,
 }
 
@@ -104,6 +107,7 @@ impl Client {
             config,
             http_client,
             request_count: AtomicI64::new(0),
+            total_requests: AtomicU64::new(0),
             rate_limit_state: Mutex::new(RateLimitState {
                 window_start: Instant::now(),
             }),
@@ -148,7 +152,7 @@ impl Client {
             }
         };
 
-        let mut mac = Hmac::<Sha256>::new_from_slice(self.config.api_secret.as_bytes())
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.config.api_secret.expose().as_bytes())
             .expect("HMAC can take key of any size");
         mac.update(sign_payload.as_bytes());
         let result = mac.finalize();
@@ -210,7 +214,7 @@ impl Client {
         if signed {
             let signature = self.sign(&method, endpoint, timestamp, &payload);
             let mut headers = HeaderMap::new();
-            headers.insert("key", HeaderValue::from_str(&self.config.api_key).unwrap());
+            headers.insert("key", HeaderValue::from_str(self.config.api_key.expose()).unwrap());
             headers.insert(
                 "signTimestamp",
                 HeaderValue::from_str(&timestamp.to_string()).unwrap(),
@@ -269,6 +273,17 @@ impl Client {
     /// Increments the request counter.
     fn increment_request_count(&self) {
         self.request_count.fetch_add(1, Ordering::SeqCst);
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the requests sent since the client was created.
+    pub fn total_requests(&self) -> u64 {
+        self.total_requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns the requests counted in the current rate limit window and the limit.
+    pub fn window_requests(&self) -> (i64, i64) {
+        (self.request_count.load(Ordering::SeqCst), self.config.rate_limit)
     }
 
     /// Creates a ClientError from an error response.
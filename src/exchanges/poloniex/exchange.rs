@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, UNIX_EPOCH};
 
@@ -15,50 +15,45 @@ use crate::config::{Config, ExchangeConfig};
 use crate::domain::{Fees, Order, OrderSide, Orderbook, Trade};
 use crate::exchanges::poloniex::{Client, WebSocketManager};
 use crate::exchanges::utils::{pair_to_symbol, parse_order_side, parse_order_status, parse_order_type, parse_price_levels, symbol_to_pair};
-use crate::exchanges::{Exchange, ExchangeError, Result};
-
-const EXCHANGE_NAME: &str = "poloniex";
+use crate::exchanges::{Exchange, ExchangeError, ExchangeUsage, FeeSchedule, Result};
 
 /// Maximum acceptable clock drift between local and server time.
 const MAX_CLOCK_DRIFT: Duration = Duration::from_secs(5);
 
 /// Poloniex exchange implementation.
 pub struct PoloniexExchange {
+    /// Account id: the key of this account under `exchanges` in the config.
+    id: String,
     client: Client,
     config: ExchangeConfig,
-    fees: Fees,
+    fees: FeeSchedule,
     orderbook_depth: i32,
     pairs: Vec<String>,
     connected: AtomicBool,
     websocket_manager: Mutex<Option<Arc<WebSocketManager>>>,
+    /// WebSocket reconnections across subscriptions.
+    websocket_reconnects: Arc<AtomicU64>,
 }
 
 impl PoloniexExchange {
-    /// Creates a new PoloniexExchange from the application config.
+    /// Creates the Poloniex account configured under `id` in the application config.
     ///
-    /// Returns an error if Poloniex is not configured or not enabled.
-    pub fn from_config(config: &Config) -> Result<Self> {
+    /// Returns an error if the account is not configured or not enabled.
+    pub fn from_config(id: &str, config: &Config) -> Result<Self> {
         let exchange_config = config
             .exchanges
-            .get(EXCHANGE_NAME)
-            .ok_or_else(|| ExchangeError::Internal(format!("{} not found in config", EXCHANGE_NAME)))?;
+            .get(id)
+            .ok_or_else(|| ExchangeError::Internal(format!("{} not found in config", id)))?;
 
         if !exchange_config.enabled {
-            return Err(ExchangeError::Internal(format!("{} is not enabled", EXCHANGE_NAME)));
+            return Err(ExchangeError::Internal(format!("{} is not enabled", id)));
         }
 
         let client = Client::from_config(exchange_config);
 
         let pairs = config.pairs.clone();
 
-        // Parse taker fee from config, default to 0
-        let taker_fee = exchange_config
-            .fee_taker
-            .as_ref()
-            .and_then(|s| Decimal::from_str(s).ok())
-            .unwrap_or_default();
-
-        let fees = Fees::new(taker_fee, taker_fee);
+        let fees = FeeSchedule::from_config(exchange_config);
 
         let orderbook_depth = config
             .orderbook
@@ -67,6 +62,7 @@ impl PoloniexExchange {
             .unwrap_or(DEFAULT_ORDERBOOK_DEPTH);
 
         Ok(Self {
+            id: id.to_string(),
             client,
             config: exchange_config.clone(),
             fees,
@@ -74,6 +70,7 @@ impl PoloniexExchange {
             pairs,
             connected: AtomicBool::new(false),
             websocket_manager: Mutex::new(None),
+            websocket_reconnects: Arc::new(AtomicU64::new(0)),
         })
     }
 }
@@ -92,6 +89,7 @@ impl Exchange for PoloniexExchange {
         let drift = (local_time - server_time).abs();
 
         info!(
+            account = %self.id,
             server_time = %server_time,
             clock_drift = ?drift,
             "connected to poloniex"
@@ -114,7 +112,7 @@ impl Exchange for PoloniexExchange {
             manager.close().await;
         }
 
-        debug!("disconnected from {}", EXCHANGE_NAME);
+        debug!(account = %self.id, "disconnected from poloniex");
         Ok(())
     }
 
@@ -144,7 +142,7 @@ impl Exchange for PoloniexExchange {
         let resp: OrderbookResponse = serde_json::from_slice(&body)
             .map_err(|e| ExchangeError::Api(format!("parse orderbook: {}", e)))?;
 
-        Ok(resp.to_orderbook(pair))
+        Ok(resp.to_orderbook(&self.id, pair))
     }
 
     async fn subscribe_orderbook(
@@ -156,7 +154,12 @@ impl Exchange for PoloniexExchange {
         }
 
         // Create a WebSocket manager
-        let (manager, orderbook_rx) = WebSocketManager::new(&self.config, pairs);
+        let (manager, orderbook_rx) = WebSocketManager::new(
+            &self.id,
+            &self.config,
+            pairs,
+            Arc::clone(&self.websocket_reconnects),
+        );
         let manager = Arc::new(manager);
 
         // Store manager for later cleanup
@@ -176,6 +179,22 @@ impl Exchange for PoloniexExchange {
         Ok(orderbook_rx)
     }
 
+    async fn update_orderbook_subscription(
+        &self,
+        added: Vec<String>,
+        removed: Vec<String>,
+    ) -> Result<()> {
+        let manager = self.websocket_manager.lock().await.clone();
+        let Some(manager) = manager else {
+            return Err(ExchangeError::Connection("not subscribed".to_string()));
+        };
+
+        manager
+            .update_pairs(added, removed)
+            .await
+            .map_err(|e| ExchangeError::Connection(format!("update subscription: {}", e)))
+    }
+
     // TODO: мне не нравится концепция IOC, надо вернуться и покрутить логику ордеров
     // https://docs.poloniex.com/#order-types
     async fn place_order(&self, order: Order) -> Result<Trade> {
@@ -215,7 +234,7 @@ impl Exchange for PoloniexExchange {
         Ok(Trade {
             id: resp.id.clone(),
             order_id: resp.id,
-            exchange: EXCHANGE_NAME.to_string(),
+            exchange: self.id.clone(),
             pair: order.pair,
             side: order.side,
             price: avg_price,
@@ -226,17 +245,18 @@ impl Exchange for PoloniexExchange {
         })
     }
 
-    async fn cancel_order(&self, _order_id: &str) -> Result<()> {
+    async fn cancel_order(&self, order_id: &str) -> Result<()> {
         if !self.is_connected() {
             return Err(ExchangeError::Connection("not connected".to_string()));
         }
 
-        let endpoint = format!("/orders/{}", _order_id);
+        let endpoint = format!("/orders/{}", order_id);
 
+        // An unknown order maps to OrderNotFound: filled or cancelled already
         self.client
             .request(Method::DELETE, &endpoint, None, true)
             .await
-            .map_err(|e| ExchangeError::Api(format!("cancel order: {}", e)))?;
+            .map_err(|e| map_client_error(e, order_id))?;
 
         Ok(())
     }
@@ -251,12 +271,12 @@ impl Exchange for PoloniexExchange {
             .client
             .request(Method::GET, &endpoint, None, true)
             .await
-            .map_err(|e| ExchangeError::Api(format!("get order: {}", e)))?;
+            .map_err(|e| map_client_error(e, order_id))?;
 
         let order_info: OrderInfo = serde_json::from_slice(&body)
             .map_err(|e| ExchangeError::Api(format!("parse order: {}", e)))?;
 
-        Ok(order_info.to_order())
+        Ok(order_info.to_order(&self.id))
     }
 
     async fn get_balances(&self) -> Result<HashMap<String, Decimal>> {
@@ -291,12 +311,85 @@ impl Exchange for PoloniexExchange {
         Ok(balances)
     }
 
-    fn get_fees(&self, _pair: &str) -> Fees {
-        self.fees
+    fn get_fees(&self, pair: &str) -> Fees {
+        self.fees.fees(pair)
+    }
+
+    async fn refresh_fees(&self) -> Result<()> {
+        if !self.is_connected() {
+            return Err(ExchangeError::Connection("not connected".to_string()));
+        }
+
+        if !self.fees.is_stale() {
+            return Ok(());
+        }
+
+        let body = self
+            .client
+            .request(Method::GET, "/feeinfo", None, true)
+            .await
+            .map_err(|e| ExchangeError::Api(format!("get fee info: {}", e)))?;
+
+        let info: FeeInfoResponse = serde_json::from_slice(&body)
+            .map_err(|e| ExchangeError::Api(format!("parse fee info: {}", e)))?;
+
+        let fees = info.to_fees()?;
+        self.fees.set_account_fees(fees);
+        for special in &info.special_fee_rates {
+            self.fees
+                .set_pair_fees(&symbol_to_pair(&special.symbol), special.to_fees()?);
+        }
+
+        if self.fees.has_discounts() {
+            let balances = self.get_balances().await?;
+            self.fees.apply_balances(&balances);
+        }
+
+        debug!(
+            maker = %fees.maker,
+            taker = %fees.taker,
+            special_pairs = info.special_fee_rates.len(),
+            "refreshed fee rates"
+        );
+
+        Ok(())
+    }
+
+    async fn get_withdrawal_fees(&self) -> Result<HashMap<String, Decimal>> {
+        let body = self
+            .client
+            .request(Method::GET, "/currencies", None, false)
+            .await
+            .map_err(|e| ExchangeError::Api(format!("get currencies: {}", e)))?;
+
+        let currencies: Vec<HashMap<String, CurrencyInfo>> = serde_json::from_slice(&body)
+            .map_err(|e| ExchangeError::Api(format!("parse currencies: {}", e)))?;
+
+        let fees = currencies
+            .into_iter()
+            .flatten()
+            .filter(|(_, info)| !info.delisted)
+            .filter_map(|(currency, info)| {
+                let fee = Decimal::from_str(info.withdrawal_fee.as_deref()?).ok()?;
+                Some((currency, fee))
+            })
+            .collect();
+
+        Ok(fees)
+    }
+
+    fn usage(&self) -> ExchangeUsage {
+        let (window_requests, limit) = self.client.window_requests();
+        ExchangeUsage {
+            rest_requests: self.client.total_requests(),
+            rest_window_requests: u64::try_from(window_requests).unwrap_or_default(),
+            rest_window_limit: u64::try_from(limit).unwrap_or_default(),
+            websocket_reconnects: self.websocket_reconnects.load(Ordering::Relaxed),
+        }
     }
 
     fn name(&self) -> &str {
-        EXCHANGE_NAME
+        &self.id
     }
 
     fn supported_pairs(&self) -> Vec<String> {
@@ -332,13 +425,13 @@ struct PlaceOrderResponse {
 }
 
 impl OrderbookResponse {
-    fn to_orderbook(&self, pair: &str) -> Orderbook {
+    fn to_orderbook(&self, exchange: &str, pair: &str) -> Orderbook {
         let bids = parse_price_levels(&self.bids);
         let asks = parse_price_levels(&self.asks);
         let timestamp_ms = if self.ts != 0 { self.ts } else { self.time };
 
         Orderbook {
-            exchange: EXCHANGE_NAME.to_string(),
+            exchange: exchange.to_string(),
             pair: pair.to_string(),
             bids,
             asks,
@@ -347,6 +440,60 @@ impl OrderbookResponse {
     }
 }
 
+/// Poloniex fee info response (`/feeinfo`).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeInfoResponse {
+    #[allow(dead_code)]
+    trx_discount: bool,
+    maker_rate: String,
+    taker_rate: String,
+    #[allow(dead_code)]
+    #[serde(rename = "volume30D")]
+    volume_30d: String,
+    /// Pairs the account trades at rates other than the account-wide ones.
+    #[serde(default)]
+    special_fee_rates: Vec<SpecialFeeRate>,
+}
+
+impl FeeInfoResponse {
+    fn to_fees(&self) -> Result<Fees> {
+        parse_fee_rates(&self.maker_rate, &self.taker_rate)
+    }
+}
+
+/// Pair-specific rates in the fee info response.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpecialFeeRate {
+    symbol: String,
+    maker_rate: String,
+    taker_rate: String,
+}
+
+impl SpecialFeeRate {
+    fn to_fees(&self) -> Result<Fees> {
+        parse_fee_rates(&self.maker_rate, &self.taker_rate)
+    }
+}
+
+fn parse_fee_rates(maker_rate: &str, taker_rate: &str) -> Result<Fees> {
+    let maker = Decimal::from_str(maker_rate)
+        .map_err(|e| ExchangeError::Api(format!("invalid maker rate: {}", e)))?;
+    let taker = Decimal::from_str(taker_rate)
+        .map_err(|e| ExchangeError::Api(format!("invalid taker rate: {}", e)))?;
+    Ok(Fees::new(maker, taker))
+}
+
+/// Poloniex currency info (`/currencies`).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurrencyInfo {
+    withdrawal_fee: Option<String>,
+    #[serde(default)]
+    delisted: bool,
+}
+
 /// Poloniex account balance response.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -394,13 +541,13 @@ struct OrderInfo {
 }
 
 impl OrderInfo {
-    fn to_order(&self) -> Order {
+    fn to_order(&self, exchange: &str) -> Order {
         let price = Decimal::from_str(&self.price).unwrap_or_default();
         let quantity = Decimal::from_str(&self.quantity).unwrap_or_default();
 
         Order {
             id: self.id.clone(),
-            exchange: EXCHANGE_NAME.to_string(),
+            exchange: exchange.to_string(),
             pair: symbol_to_pair(&self.symbol),
             side: parse_order_side(&self.side),
             order_type: parse_order_type(&self.order_type),
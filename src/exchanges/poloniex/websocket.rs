@@ -1,5 +1,5 @@
 use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, UNIX_EPOCH};
 
@@ -14,21 +14,13 @@ use tokio_tungstenite::tungstenite::Message as WsMessage;
 use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 use tracing::{debug, error, info, warn};
 
-use crate::config::ExchangeConfig;
+use crate::config::{DEFAULT_PING_INTERVAL, DEFAULT_RECONNECT_DELAY, ExchangeConfig};
 use crate::domain::{Orderbook, PriceLevel};
 use crate::exchanges::utils::{pair_to_symbol, symbol_to_pair};
 
 /// Poloniex WebSocket URL.
 const WEBSOCKET_URL: &str = "wss://ws.poloniex.com/ws/public";
 
-/// Default interval to send ping messages.
-/// Poloniex requires a message or ping every 30 seconds.
-/// Using 20 seconds for reliability margin.
-const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(20);
-
-/// Default delay before reconnecting.
-const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(5);
-
 /// Default orderbook depth.
 const DEFAULT_DEPTH: u8 = 10;
 
@@ -37,10 +29,13 @@ type OnReconnectFailed = Box<dyn Fn(&str, &str) + Send + Sync>;
 
 /// WebSocket configuration for Poloniex exchange.
 struct WebSocketConfig {
+    /// Account id stamped on the orderbooks received.
+    account: String,
     /// WebSocket server URL.
     url: String,
-    /// List of trading pairs to subscribe (e.g., "BTC_USDT").
-    pairs: Vec<String>,
+    /// Trading pairs to subscribe (e.g., "BTC/USDT"); updated by `update_pairs`
+    /// so that reconnects resubscribe to the current set.
+    pairs: Mutex<Vec<String>>,
     /// Orderbook depth (number of price levels). Poloniex supports: 5, 10, 20.
     depth: u8,
     /// Interval between ping messages.
@@ -53,7 +48,7 @@ struct WebSocketConfig {
 
 impl WebSocketConfig {
     /// Creates a new WebSocketConfig from ExchangeConfig.
-    fn from_config(config: &ExchangeConfig, pairs: Vec<String>) -> Self {
+    fn from_config(account: &str, config: &ExchangeConfig, pairs: Vec<String>) -> Self {
         let (ping_interval, reconnect_delay) = config
             .websocket
             .as_ref()
@@ -61,8 +56,9 @@ impl WebSocketConfig {
             .unwrap_or((DEFAULT_PING_INTERVAL, DEFAULT_RECONNECT_DELAY));
 
         Self {
+            account: account.to_string(),
             url: WEBSOCKET_URL.to_string(),
-            pairs,
+            pairs: Mutex::new(pairs),
             depth: DEFAULT_DEPTH,
             ping_interval,
             reconnect_delay,
@@ -85,12 +81,20 @@ pub struct WebSocketManager {
     sink: Arc<Mutex<Option<WsSink>>>,
     orderbooks_tx: mpsc::UnboundedSender<Orderbook>,
     closed: Arc<AtomicBool>,
+    /// Successful reconnections, shared with the exchange across managers.
+    reconnects: Arc<AtomicU64>,
 }
 
 impl WebSocketManager {
-    /// Creates a new WebSocket manager.
-    pub fn new(exchange_config: &ExchangeConfig, pairs: Vec<String>) -> (Self, mpsc::UnboundedReceiver<Orderbook>) {
-        let config = WebSocketConfig::from_config(exchange_config, pairs);
+    /// Creates a new WebSocket manager for an account. Successful reconnections
+    /// are counted in `reconnects`.
+    pub fn new(
+        account: &str,
+        exchange_config: &ExchangeConfig,
+        pairs: Vec<String>,
+        reconnects: Arc<AtomicU64>,
+    ) -> (Self, mpsc::UnboundedReceiver<Orderbook>) {
+        let config = WebSocketConfig::from_config(account, exchange_config, pairs);
         let (orderbooks_tx, orderbooks_rx) = mpsc::unbounded_channel();
 
         let manager = Self {
@@ -98,6 +102,7 @@ impl WebSocketManager {
             sink: Arc::new(Mutex::new(None)),
             orderbooks_tx,
             closed: Arc::new(AtomicBool::new(false)),
+            reconnects,
         };
 
         (manager, orderbooks_rx)
@@ -185,11 +190,14 @@ impl WebSocketManager {
     /// Logs error and invokes callback on failure.
     async fn try_reconnect(&self) -> Option<WsSource> {
         match self.reconnect().await {
-            Ok(new_stream) => Some(new_stream),
+            Ok(new_stream) => {
+                self.reconnects.fetch_add(1, Ordering::Relaxed);
+                Some(new_stream)
+            }
             Err(e) => {
                 error!(error = %e, "reconnect failed");
                 if let Some(ref callback) = self.config.on_reconnect_failed {
-                    callback("poloniex", &e.to_string());
+                    callback(&self.config.account, &e.to_string());
                 }
                 None
             }
@@ -216,25 +224,57 @@ impl WebSocketManager {
 
     /// Sends the subscription message to the WebSocket.
     async fn send_subscribe_message(&self) -> Result<(), WsError> {
+        let pairs = self.config.pairs.lock().await.clone();
+
         let mut guard = self.sink.lock().await;
         let sink = guard.as_mut().ok_or_else(|| {
             tokio_tungstenite::tungstenite::Error::AlreadyClosed
         })?;
 
+        self.send_book_event(sink, "subscribe", &pairs).await
+    }
+
+    /// Subscribes to added pairs and unsubscribes from removed ones on the open
+    /// connection. While reconnecting, the new set applies on resubscription.
+    pub async fn update_pairs(&self, added: Vec<String>, removed: Vec<String>) -> Result<(), WsError> {
+        {
+            let mut pairs = self.config.pairs.lock().await;
+            pairs.retain(|p| !removed.contains(p));
+            for pair in &added {
+                if !pairs.contains(pair) {
+                    pairs.push(pair.clone());
+                }
+            }
+        }
+
+        let mut guard = self.sink.lock().await;
+        let Some(sink) = guard.as_mut() else {
+            debug!("websocket not connected, pairs apply on reconnect");
+            return Ok(());
+        };
+
+        if !removed.is_empty() {
+            self.send_book_event(sink, "unsubscribe", &removed).await?;
+        }
+        if !added.is_empty() {
+            self.send_book_event(sink, "subscribe", &added).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends a subscribe or unsubscribe message for the orderbook channel.
+    async fn send_book_event(&self, sink: &mut WsSink, event: &str, pairs: &[String]) -> Result<(), WsError> {
         // Normalize depth to supported values: 5, 10, 20
         let depth = normalize_depth(self.config.depth);
 
         // Convert pairs to Poloniex symbol format (BTC/USDT -> BTC_USDT)
-        let symbols: Vec<String> = self.config
-            .pairs
-            .iter()
-            .map(|p| pair_to_symbol(p))
-            .collect();
+        let symbols: Vec<String> = pairs.iter().map(|p| pair_to_symbol(p)).collect();
 
         // Poloniex subscription format:
         // {"event": "subscribe", "channel": ["book"], "symbols": ["BTC_USDT"], "depth": 10}
         let sub_msg = json!({
-            "event": "subscribe",
+            "event": event,
             "channel": ["book"],
             "symbols": symbols,
             "depth": depth
@@ -242,11 +282,11 @@ impl WebSocketManager {
 
         let msg = WsMessage::Text(sub_msg.to_string().into());
         sink.send(msg).await.map_err(|e| {
-            error!(error = %e, "failed to subscribe");
+            error!(error = %e, event = event, "failed to send orderbook subscription");
             e
         })?;
 
-        info!(symbols = ?symbols, depth = depth, "subscribed to orderbook");
+        info!(symbols = ?symbols, depth = depth, event = event, "orderbook subscription updated");
 
         Ok(())
     }
@@ -264,11 +304,11 @@ impl WebSocketManager {
                 msg = stream.next() => {
                     match msg {
                         Some(Ok(WsMessage::Text(text))) => {
-                            if let Some(orderbook) = parse_message(&text) {
-                                if self.orderbooks_tx.send(orderbook).is_err() {
-                                    warn!("orderbook channel closed");
-                                    break;
-                                }
+                            if let Some(orderbook) = parse_message(&self.config.account, &text)
+                                && self.orderbooks_tx.send(orderbook).is_err()
+                            {
+                                warn!("orderbook channel closed");
+                                break;
                             }
                         }
                         Some(Ok(WsMessage::Close(_))) => {
@@ -393,7 +433,7 @@ struct OrderbookData {
 
 /// Parses a WebSocket message into an Orderbook.
 /// Returns None for non-orderbook messages (pong, subscribe confirmation, etc.)
-fn parse_message(data: &str) -> Option<Orderbook> {
+fn parse_message(account: &str, data: &str) -> Option<Orderbook> {
     let msg: OrderbookMessage = serde_json::from_str(data).ok()?;
 
     // Check if it's an orderbook message
@@ -403,11 +443,11 @@ fn parse_message(data: &str) -> Option<Orderbook> {
     }
 
     // Skip subscription confirmation or pong
-    if let Some(event) = &msg.event {
-        if event == "subscribe" || event == "pong" {
-            debug!(event = %event, "control message");
-            return None;
-        }
+    if let Some(event) = &msg.event
+        && (event == "subscribe" || event == "unsubscribe" || event == "pong")
+    {
+        debug!(event = %event, "control message");
+        return None;
     }
 
     let data = msg.data?.into_iter().next()?;
@@ -419,7 +459,7 @@ fn parse_message(data: &str) -> Option<Orderbook> {
     let asks = parse_levels(&data.asks);
 
     Some(Orderbook {
-        exchange: "poloniex".to_string(),
+        exchange: account.to_string(),
         pair: symbol_to_pair(&data.symbol),
         bids,
         asks,
@@ -1,29 +1,24 @@
+mod api;
 mod bot;
+mod cli;
 mod config;
+mod detector;
 mod domain;
 mod exchanges;
+mod metrics;
 mod notification;
+mod secrets;
 mod storage;
 
 use bot::Bot;
-use config::Config;
-use exchanges::poloniex::{Client, ClientConfig, WebSocketManager, PoloniexExchange};
-use exchanges::Exchange;
-use std::env;
-use tracing::{Level, debug, error, info};
+use clap::Parser;
+use cli::{Cli, CliError, Command};
+use config::{Config, ConfigError};
+use std::process::ExitCode;
+use std::sync::Arc;
+use tracing::{Level, error, info, warn};
 use tracing_subscriber::{EnvFilter, fmt};
 
-const DEFAULT_CONFIG_PATH: &str = "configs/config.yaml";
-
-fn parse_config_path() -> String {
-    for arg in env::args().skip(1) {
-        if let Some(path) = arg.strip_prefix("--config=") {
-            return path.to_string();
-        }
-    }
-    DEFAULT_CONFIG_PATH.to_string()
-}
-
 fn init_tracing(log_level: Option<&str>) {
     let level = match log_level {
         Some("debug") => Level::DEBUG,
@@ -47,244 +42,135 @@ fn init_tracing(log_level: Option<&str>) {
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     dotenvy::dotenv().ok();
 
-    // Check for test mode
-    if env::args().any(|arg| arg == "--test-ws") {
-        test_poloniex_ws().await;
-        return;
-    }
-
-    if env::args().any(|arg| arg == "--test-client") {
-        test_poloniex_client().await;
-        return;
-    }
-
-    if env::args().any(|arg| arg == "--test-exchange") {
-        test_poloniex_exchange().await;
-        return;
-    }
-
-    // Initialize tracing early so we can see logs from bot initialization
-    init_tracing(Some("info"));
+    let cli = Cli::parse();
 
-    let config_path = parse_config_path();
-
-    let bot = match Bot::from_config_path(&config_path).await {
-        Ok(bot) => bot,
+    match execute(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
-            eprintln!("Failed to create bot: {}", e);
-            return;
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
         }
-    };
-
-    info!(config = %config_path, "Bot initialized");
-
-    if let Err(e) = bot.start().await {
-        error!(error = %e, "Bot error");
     }
-
-    let _ = bot.stop().await;
 }
 
-/// Test function for Poloniex WebSocket manager.
-async fn test_poloniex_ws() {
-    let config_path = parse_config_path();
-    let config = match Config::load(&config_path) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Failed to load config: {}", e);
-            return;
+/// Runs the selected command.
+async fn execute(cli: Cli) -> Result<(), CliError> {
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => run_bot(&cli.config).await?,
+        Command::CheckConfig => check_config(&cli.config)?,
+        Command::StreamBooks {
+            exchange,
+            pair,
+            count,
+        } => {
+            let config = load_with_tracing(&cli.config)?;
+            cli::stream_books(&config, &exchange, pair, count).await?;
         }
-    };
-
-    init_tracing(config.app.log_level.as_deref());
-
-    let poloniex_config = match config.exchanges.get("poloniex") {
-        Some(c) => c,
-        None => {
-            error!("Poloniex exchange not found in config");
-            return;
+        Command::Balances { exchange } => {
+            let config = load_with_tracing(&cli.config)?;
+            cli::balances(&config, &exchange).await?;
         }
-    };
-
-    let pairs = config.pairs.clone();
-    let (manager, mut orderbooks_rx) = WebSocketManager::new(poloniex_config, pairs);
-    let manager = std::sync::Arc::new(manager);
-
-    info!("Starting Poloniex WebSocket...");
-
-    // Spawn subscribe which does: connect + subscribe + ping_loop + read_loop
-    let manager_clone = std::sync::Arc::clone(&manager);
-    tokio::spawn(async move {
-        if let Err(e) = manager_clone.subscribe().await {
-            error!(error = %e, "WebSocket error");
+        Command::Ping { exchange } => {
+            let config = load_with_tracing(&cli.config)?;
+            cli::ping(&config, &exchange).await?;
         }
-    });
-
-    // Receive orderbooks from the channel
-    info!("Listening for orderbook updates (press Ctrl+C to stop)...");
-
-    let mut count = 0;
-    while let Some(orderbook) = orderbooks_rx.recv().await {
-        info!(
-            pair = %orderbook.pair,
-            best_bid = ?orderbook.best_bid().map(|l| l.price),
-            best_ask = ?orderbook.best_ask().map(|l| l.price),
-            spread = ?orderbook.spread(),
-            "Orderbook update"
-        );
-
-        count += 1;
-        if count >= 100 {
-            break;
+        Command::History(args) => {
+            // No tracing: logs would interleave with records written to stdout
+            let config = Config::load(&cli.config)?;
+            let exported = cli::export_history(&config, &args).await?;
+            eprintln!("Exported {} opportunities", exported);
         }
+        Command::Keystore(args) => cli::keystore(&args)?,
     }
 
-    info!("Test completed, received {} orderbooks", count);
+    Ok(())
 }
 
-/// Test function for Poloniex HTTP client.
-async fn test_poloniex_client() {
-    let config_path = parse_config_path();
-    let config = match Config::load(&config_path) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Failed to load config: {}", e);
-            return;
-        }
-    };
-
-    init_tracing(config.app.log_level.as_deref());
-
-    let poloniex_config = match config.exchanges.get("poloniex") {
-        Some(c) => c,
-        None => {
-            error!("Poloniex exchange not found in config");
-            return;
-        }
-    };
-
-    let client_config = ClientConfig::new(
-        poloniex_config.api_key.clone(),
-        poloniex_config.api_secret.clone(),
-        // poloniex_config.rate_limit.unwrap_or(200) as i64,
-        2
-    );
-
-    let client = Client::new(client_config);
-
-    info!("Testing Poloniex HTTP client...");
-
-    info!(
-        "Rate limit: {}/{}",
-        client.request_count(),
-        client.rate_limit()
-    );
-
-    match client.ping().await {
-        Ok(()) => info!("Ping successful"),
-        Err(e) => {
-            error!(error = %e, "Ping failed");
-            return;
-        }
-    }
+/// Prints the effective configuration, secrets redacted, the layer that set
+/// each value, and every problem found in it. Fails if any problem is an error.
+fn check_config(config_path: &str) -> Result<(), CliError> {
+    let (config, diagnostics, layers) = Config::check(config_path)?;
 
-    info!(
-        "Rate limit: {}/{}",
-        client.request_count(),
-        client.rate_limit()
-    );
+    let effective = serde_yaml::to_string(&config).map_err(ConfigError::from)?;
+    println!("# effective configuration of {}", config_path);
+    print!("{}", effective);
+    println!();
 
-    match client.get_server_time().await {
-        Ok(time) => info!(server_time = %time, "Server time received"),
-        Err(e) => error!(error = %e, "Failed to get server time"),
+    println!("# value sources (unlisted values are defaults)");
+    for (path, source) in layers.sources() {
+        println!("{}: {}", path, source);
     }
+    println!();
 
-    info!(
-        "Rate limit: {}/{}",
-        client.request_count(),
-        client.rate_limit()
-    );
-
-    tokio::time::sleep(
-        std::time::Duration::from_secs(6)
-    ).await;
-
-    info!(
-        "Rate limit: {}/{}",
-        client.request_count(),
-        client.rate_limit()
-    );
-
-    match client.get_server_time().await {
-        Ok(time) => info!(server_time = %time, "Server time received"),
-        Err(e) => error!(error = %e, "Failed to get server time"),
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
     }
 
-    info!(
-        "Rate limit: {}/{}",
-        client.request_count(),
-        client.rate_limit()
-    );
-
-    match client.get_server_time().await {
-        Ok(time) => info!(server_time = %time, "Server time received"),
-        Err(e) => error!(error = %e, "Failed to get server time"),
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+    if errors > 0 {
+        return Err(ConfigError::Validation(format!(
+            "{} error(s), {} warning(s) in {}",
+            errors, warnings, config_path
+        ))
+        .into());
     }
 
-    info!(
-        "Rate limit: {}/{}",
-        client.request_count(),
-        client.rate_limit()
+    println!(
+        "{}: configuration is valid ({} warning(s))",
+        config_path, warnings
     );
-
-    info!("Balance testing");
-
-    info!("Test completed");
+    Ok(())
 }
 
-async fn test_poloniex_exchange() {
-    let config_path = parse_config_path();
-    let config = match Config::load(&config_path) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Failed to load config: {}", e);
-            return;
-        }
-    };
+/// Loads the config and initializes tracing at its log level.
+fn load_with_tracing(config_path: &str) -> Result<Config, CliError> {
+    let config = Config::load(config_path)?;
     init_tracing(config.app.log_level.as_deref());
+    Ok(config)
+}
 
-    info!("Testing Poloniex exchange...");
+/// Runs the bot until it stops.
+async fn run_bot(config_path: &str) -> Result<(), CliError> {
+    // Initialize tracing early so we can see logs from bot initialization
+    init_tracing(Some("info"));
 
-    let exchange = PoloniexExchange::from_config(&config).unwrap();
+    let bot = Arc::new(Bot::from_config_path(config_path).await?);
 
-    info!("Exchange name: {}", exchange.name().to_string());
+    info!(config = %config_path, "Bot initialized");
 
-    match exchange.connect().await {
-        Ok(_) => info!("Exchange connected"),
-        Err(e) => {
-            error!("Exchange connection failed: {}", e);
-            return;
-        }
-    }
+    let api = api::spawn(Arc::clone(&bot)).await?;
+    bot.listen_for_commands().await;
+
+    // The first signal stops the bot gracefully, a second one exits at once
+    let signals = tokio::spawn({
+        let bot = Arc::clone(&bot);
+        async move {
+            let signal = bot::wait_for_signal().await;
+            info!(signal = signal, "Shutdown requested");
+            bot.request_shutdown(format!("received {}", signal)).await;
 
-    match exchange.get_balances().await {
-        Ok(balances) => {
-            info!("Balances received: {} currencies", balances.len());
-            for (currency, amount) in &balances {
-                debug!(currency = %currency, amount = %amount, "balance");
-            }
+            let signal = bot::wait_for_signal().await;
+            warn!(signal = signal, "Second signal, exiting without cleanup");
+            std::process::exit(130);
         }
-        Err(e) => error!("Failed to get balances: {}", e),
-    }
+    });
 
-    match exchange.disconnect().await {
-        Ok(_) => info!("Exchange disconnected"),
-        Err(e) => error!("Exchange disconnection failed: {}", e)
+    let result = bot.start().await;
+    if let Err(ref e) = result {
+        error!(error = %e, "Bot error");
+        bot.request_shutdown(format!("error: {}", e)).await;
     }
 
-    info!("Test completed");
+    if let Err(e) = bot.stop().await {
+        error!(error = %e, "Failed to stop cleanly");
+    }
+    signals.abort();
+    if let Some(api) = api {
+        api.abort();
+    }
+    Ok(result?)
 }
-
@@ -0,0 +1,178 @@
+//! Prometheus metrics of the bot.
+//!
+//! Counters recorded as things happen (detection passes, opportunities,
+//! orderbook updates, balances) live in [`Metrics`]; exchange usage and
+//! orderbook staleness are read when the endpoint is scraped.
+//!
+//! TODO: Export executions by outcome once the executor places orders.
+
+mod registry;
+mod server;
+
+pub use server::serve;
+
+use std::time::{Duration, SystemTime};
+
+use crate::domain::Opportunity;
+use crate::exchanges::{Manager, OrderbookCache};
+
+use registry::{CounterVec, GaugeVec, HistogramVec};
+
+/// Buckets of the detection latency histogram, in seconds.
+const DETECTION_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/// Metrics exported on the Prometheus endpoint.
+pub struct Metrics {
+    detection_duration: HistogramVec,
+    detection_timeouts: CounterVec,
+    opportunities: CounterVec,
+    orderbook_updates: CounterVec,
+    orderbook_age: GaugeVec,
+    websocket_reconnects: CounterVec,
+    rest_requests: CounterVec,
+    rest_window_requests: GaugeVec,
+    rest_window_limit: GaugeVec,
+    balances: GaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            detection_duration: HistogramVec::new(
+                "arbitrage_detection_duration_seconds",
+                "Duration of detection passes; pass is \"event\" for orderbook updates, \"full\" for the fallback timer.",
+                &["pass"],
+                DETECTION_BUCKETS,
+            ),
+            detection_timeouts: CounterVec::new(
+                "arbitrage_detection_timeouts_total",
+                "Pairs and cycle searches cancelled at the detection timeout.",
+                &[],
+            ),
+            opportunities: CounterVec::new(
+                "arbitrage_opportunities_total",
+                "Opportunities found by detection passes, per route.",
+                &["type", "pair", "buy_exchange", "sell_exchange"],
+            ),
+            orderbook_updates: CounterVec::new(
+                "arbitrage_orderbook_updates_total",
+                "Orderbook updates received from the streams.",
+                &["exchange", "pair"],
+            ),
+            orderbook_age: GaugeVec::new(
+                "arbitrage_orderbook_age_seconds",
+                "Age of the cached orderbook.",
+                &["exchange", "pair"],
+            ),
+            websocket_reconnects: CounterVec::new(
+                "arbitrage_websocket_reconnects_total",
+                "WebSocket reconnections.",
+                &["exchange"],
+            ),
+            rest_requests: CounterVec::new(
+                "arbitrage_rest_requests_total",
+                "REST requests sent.",
+                &["exchange"],
+            ),
+            rest_window_requests: GaugeVec::new(
+                "arbitrage_rest_window_requests",
+                "REST requests counted in the current rate limit window.",
+                &["exchange"],
+            ),
+            rest_window_limit: GaugeVec::new(
+                "arbitrage_rest_window_limit",
+                "REST requests allowed per rate limit window.",
+                &["exchange"],
+            ),
+            balances: GaugeVec::new(
+                "arbitrage_balance",
+                "Available balance per asset, as of the last balance sync.",
+                &["exchange", "asset"],
+            ),
+        }
+    }
+
+    /// Records the duration of a detection pass.
+    pub fn detection(&self, full_pass: bool, duration: Duration) {
+        let pass = if full_pass { "full" } else { "event" };
+        self.detection_duration
+            .observe(&[pass], duration.as_secs_f64());
+    }
+
+    /// Counts a pair or cycle search cancelled at the detection timeout.
+    pub fn detection_timeout(&self) {
+        self.detection_timeouts.inc(&[]);
+    }
+
+    /// Counts a detected opportunity under its route.
+    pub fn opportunity(&self, opportunity: &Opportunity) {
+        self.opportunities.inc(&[
+            &opportunity.opportunity_type.to_string(),
+            &opportunity.pair,
+            &opportunity.buy_exchange,
+            &opportunity.sell_exchange,
+        ]);
+    }
+
+    /// Counts an orderbook update received from a stream.
+    pub fn orderbook_update(&self, exchange: &str, pair: &str) {
+        self.orderbook_updates.inc(&[exchange, pair]);
+    }
+
+    /// Replaces the balances of an exchange.
+    pub fn balances(&self, exchange: &str, balances: impl IntoIterator<Item = (String, f64)>) {
+        self.balances.remove_prefix(&[exchange]);
+        for (asset, amount) in balances {
+            self.balances.set(&[exchange, &asset], amount);
+        }
+    }
+
+    /// Reads the scrape-time values and encodes every family.
+    pub async fn render(&self, orderbooks: &OrderbookCache, exchanges: &Manager) -> String {
+        let now = SystemTime::now();
+        self.orderbook_age.remove_prefix(&[]);
+        for book in orderbooks.get_all().await {
+            let age = now
+                .duration_since(book.timestamp)
+                .unwrap_or_default()
+                .as_secs_f64();
+            self.orderbook_age.set(&[&book.exchange, &book.pair], age);
+        }
+
+        for name in exchanges.list().await {
+            let Some(exchange) = exchanges.get(&name).await else {
+                continue;
+            };
+            let usage = exchange.usage();
+            self.websocket_reconnects
+                .set_total(&[&name], usage.websocket_reconnects as f64);
+            self.rest_requests
+                .set_total(&[&name], usage.rest_requests as f64);
+            self.rest_window_requests
+                .set(&[&name], usage.rest_window_requests as f64);
+            self.rest_window_limit
+                .set(&[&name], usage.rest_window_limit as f64);
+        }
+
+        let mut out = String::new();
+        self.detection_duration.encode(&mut out);
+        self.detection_timeouts.encode(&mut out);
+        self.opportunities.encode(&mut out);
+        self.orderbook_updates.encode(&mut out);
+        self.orderbook_age.encode(&mut out);
+        self.websocket_reconnects.encode(&mut out);
+        self.rest_requests.encode(&mut out);
+        self.rest_window_requests.encode(&mut out);
+        self.rest_window_limit.encode(&mut out);
+        self.balances.encode(&mut out);
+        out
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,279 @@
+//! Labelled metric families and their Prometheus text encoding.
+//!
+//! Samples are keyed by their label values, in the order of the family's
+//! label names. Families are encoded in the text exposition format 0.0.4.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Name, help text and label names shared by the samples of a family.
+struct Desc {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+}
+
+impl Desc {
+    fn encode_header(&self, out: &mut String, kind: &str) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} {}", self.name, kind);
+    }
+
+    /// Writes one sample line; `extra` is appended to the family labels.
+    fn encode_sample(
+        &self,
+        out: &mut String,
+        suffix: &str,
+        values: &[String],
+        extra: Option<(&str, &str)>,
+        value: f64,
+    ) {
+        out.push_str(self.name);
+        out.push_str(suffix);
+
+        let labels: Vec<(&str, &str)> = self
+            .labels
+            .iter()
+            .copied()
+            .zip(values.iter().map(String::as_str))
+            .chain(extra)
+            .collect();
+        if !labels.is_empty() {
+            out.push('{');
+            for (i, (name, value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let _ = write!(out, "{}=\"{}\"", name, escape(value));
+            }
+            out.push('}');
+        }
+
+        let _ = writeln!(out, " {}", format_value(value));
+    }
+
+    fn key(&self, values: &[&str]) -> Vec<String> {
+        debug_assert_eq!(values.len(), self.labels.len(), "label count of {}", self.name);
+        values.iter().map(|v| v.to_string()).collect()
+    }
+}
+
+/// Monotonically increasing counts.
+pub struct CounterVec {
+    desc: Desc,
+    samples: Mutex<BTreeMap<Vec<String>, f64>>,
+}
+
+impl CounterVec {
+    pub fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self {
+            desc: Desc { name, help, labels },
+            samples: Mutex::default(),
+        }
+    }
+
+    /// Adds one to the sample with the given label values.
+    pub fn inc(&self, values: &[&str]) {
+        self.inc_by(values, 1.0);
+    }
+
+    /// Adds `delta` to the sample with the given label values.
+    pub fn inc_by(&self, values: &[&str], delta: f64) {
+        let key = self.desc.key(values);
+        *self.samples.lock().unwrap().entry(key).or_default() += delta;
+    }
+
+    /// Sets the sample to a total counted elsewhere.
+    pub fn set_total(&self, values: &[&str], total: f64) {
+        let key = self.desc.key(values);
+        self.samples.lock().unwrap().insert(key, total);
+    }
+
+    pub fn encode(&self, out: &mut String) {
+        self.desc.encode_header(out, "counter");
+        for (values, value) in self.samples.lock().unwrap().iter() {
+            self.desc.encode_sample(out, "", values, None, *value);
+        }
+    }
+}
+
+/// Values that go up and down.
+pub struct GaugeVec {
+    desc: Desc,
+    samples: Mutex<BTreeMap<Vec<String>, f64>>,
+}
+
+impl GaugeVec {
+    pub fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self {
+            desc: Desc { name, help, labels },
+            samples: Mutex::default(),
+        }
+    }
+
+    /// Sets the sample with the given label values.
+    pub fn set(&self, values: &[&str], value: f64) {
+        let key = self.desc.key(values);
+        self.samples.lock().unwrap().insert(key, value);
+    }
+
+    /// Removes the samples whose leading label values equal `prefix`.
+    pub fn remove_prefix(&self, prefix: &[&str]) {
+        self.samples.lock().unwrap().retain(|values, _| {
+            values.len() < prefix.len() || values.iter().zip(prefix).any(|(v, p)| v != p)
+        });
+    }
+
+    pub fn encode(&self, out: &mut String) {
+        self.desc.encode_header(out, "gauge");
+        for (values, value) in self.samples.lock().unwrap().iter() {
+            self.desc.encode_sample(out, "", values, None, *value);
+        }
+    }
+}
+
+/// Observation counts in cumulative buckets.
+pub struct HistogramVec {
+    desc: Desc,
+    /// Upper bounds of the buckets, ascending; +Inf is implied.
+    buckets: &'static [f64],
+    samples: Mutex<BTreeMap<Vec<String>, Histogram>>,
+}
+
+/// Observations of one label set.
+struct Histogram {
+    /// Observations per bucket, not cumulative.
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl HistogramVec {
+    pub fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+        buckets: &'static [f64],
+    ) -> Self {
+        Self {
+            desc: Desc { name, help, labels },
+            buckets,
+            samples: Mutex::default(),
+        }
+    }
+
+    /// Records an observation for the given label values.
+    pub fn observe(&self, values: &[&str], value: f64) {
+        let key = self.desc.key(values);
+        let mut samples = self.samples.lock().unwrap();
+        let histogram = samples.entry(key).or_insert_with(|| Histogram {
+            counts: vec![0; self.buckets.len()],
+            sum: 0.0,
+            count: 0,
+        });
+
+        if let Some(bucket) = self.buckets.iter().position(|bound| value <= *bound) {
+            histogram.counts[bucket] += 1;
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    pub fn encode(&self, out: &mut String) {
+        self.desc.encode_header(out, "histogram");
+        for (values, histogram) in self.samples.lock().unwrap().iter() {
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(&histogram.counts) {
+                cumulative += count;
+                let le = format_value(*bound);
+                self.desc
+                    .encode_sample(out, "_bucket", values, Some(("le", &le)), cumulative as f64);
+            }
+            self.desc.encode_sample(
+                out,
+                "_bucket",
+                values,
+                Some(("le", "+Inf")),
+                histogram.count as f64,
+            );
+            self.desc.encode_sample(out, "_sum", values, None, histogram.sum);
+            self.desc
+                .encode_sample(out, "_count", values, None, histogram.count as f64);
+        }
+    }
+}
+
+/// Escapes a label value: backslash, double quote and line feed.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_families() {
+        let updates = CounterVec::new("updates_total", "Updates.", &["exchange", "pair"]);
+        updates.inc(&["poloniex", "BTC/USDT"]);
+        updates.inc(&["poloniex", "BTC/USDT"]);
+        updates.inc(&["gate", "say \"hi\""]);
+
+        let mut out = String::new();
+        updates.encode(&mut out);
+        assert_eq!(
+            out,
+            "# HELP updates_total Updates.\n\
+             # TYPE updates_total counter\n\
+             updates_total{exchange=\"gate\",pair=\"say \\\"hi\\\"\"} 1\n\
+             updates_total{exchange=\"poloniex\",pair=\"BTC/USDT\"} 2\n"
+        );
+
+        let latency = HistogramVec::new("latency_seconds", "Latency.", &[], &[0.1, 1.0]);
+        latency.observe(&[], 0.05);
+        latency.observe(&[], 0.5);
+        latency.observe(&[], 2.0);
+
+        let mut out = String::new();
+        latency.encode(&mut out);
+        assert_eq!(
+            out,
+            "# HELP latency_seconds Latency.\n\
+             # TYPE latency_seconds histogram\n\
+             latency_seconds_bucket{le=\"0.1\"} 1\n\
+             latency_seconds_bucket{le=\"1\"} 2\n\
+             latency_seconds_bucket{le=\"+Inf\"} 3\n\
+             latency_seconds_sum 2.55\n\
+             latency_seconds_count 3\n"
+        );
+    }
+
+    #[test]
+    fn test_gauge_remove_prefix() {
+        let balances = GaugeVec::new("balance", "Balances.", &["exchange", "asset"]);
+        balances.set(&["poloniex", "BTC"], 1.5);
+        balances.set(&["poloniex", "USDT"], 100.0);
+        balances.set(&["gate", "BTC"], 0.5);
+        balances.remove_prefix(&["poloniex"]);
+
+        let mut out = String::new();
+        balances.encode(&mut out);
+        assert!(
+            out.ends_with("# TYPE balance gauge\nbalance{exchange=\"gate\",asset=\"BTC\"} 0.5\n"),
+            "{}",
+            out
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! HTTP endpoint serving the metrics in the Prometheus text format.
+
+use std::sync::Arc;
+
+use axum::Router;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use super::Metrics;
+use crate::exchanges::{Manager, OrderbookCache};
+
+/// Content type of the text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// What a scrape reads.
+#[derive(Clone)]
+struct Scrape {
+    metrics: Arc<Metrics>,
+    orderbooks: Arc<OrderbookCache>,
+    exchanges: Arc<Manager>,
+}
+
+/// Serves `GET /metrics` on the listener until the task is aborted.
+pub async fn serve(
+    listener: TcpListener,
+    metrics: Arc<Metrics>,
+    orderbooks: Arc<OrderbookCache>,
+    exchanges: Arc<Manager>,
+) {
+    let app = Router::new()
+        .route("/metrics", get(scrape))
+        .with_state(Scrape {
+            metrics,
+            orderbooks,
+            exchanges,
+        });
+
+    if let Ok(addr) = listener.local_addr() {
+        info!(address = %addr, "Serving metrics");
+    }
+    if let Err(e) = axum::serve(listener, app).await {
+        warn!(error = %e, "Metrics endpoint stopped");
+    }
+}
+
+async fn scrape(State(scrape): State<Scrape>) -> impl IntoResponse {
+    let body = scrape
+        .metrics
+        .render(&scrape.orderbooks, &scrape.exchanges)
+        .await;
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], body)
+}
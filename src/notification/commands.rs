@@ -0,0 +1,123 @@
+use crate::notification::{
+    BalancesData, OverviewData, PairData, StatusData, format_balances, format_overview,
+    format_pairs, format_status,
+};
+
+/// Команда, полученная от пользователя в чате
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// /status - состояние бота и подключений к биржам
+    Status,
+    /// /stats - статистика торговли
+    Stats,
+    /// /balances - балансы по биржам
+    Balances,
+    /// /pause - приостановить торговлю
+    Pause,
+    /// /resume - возобновить торговлю
+    Resume,
+    /// /pairs - пары, их спреды и пороги
+    Pairs,
+    /// /threshold <pair> <value> - минимальная прибыль для пары ("0.003" или "0.3%")
+    Threshold { pair: String, value: String },
+    /// /kill - аварийная остановка торговли
+    Kill,
+}
+
+impl Command {
+    /// Разбирает текст сообщения.
+    ///
+    /// Возвращает None, если сообщение не команда, и текст подсказки, если
+    /// команда неизвестна или у нее не хватает аргументов. Суффикс `@имя_бота`
+    /// (так команды приходят в группах) отбрасывается.
+    pub fn parse(text: &str) -> Option<Result<Command, String>> {
+        let mut words = text.split_whitespace();
+        let name = words.next()?.strip_prefix('/')?;
+        let name = name.split('@').next().unwrap_or(name).to_lowercase();
+
+        let command = match name.as_str() {
+            "status" => Command::Status,
+            "stats" => Command::Stats,
+            "balances" => Command::Balances,
+            "pause" => Command::Pause,
+            "resume" => Command::Resume,
+            "pairs" => Command::Pairs,
+            "kill" => Command::Kill,
+            "threshold" => match (words.next(), words.next()) {
+                (Some(pair), Some(value)) => Command::Threshold {
+                    pair: pair.to_uppercase(),
+                    value: value.to_string(),
+                },
+                _ => return Some(Err(format_usage("Использование: /threshold <пара> <значение>"))),
+            },
+            _ => return Some(Err(format_usage("Неизвестная команда"))),
+        };
+        Some(Ok(command))
+    }
+}
+
+/// Результат выполнения команды
+#[derive(Debug, Clone)]
+pub enum Reply {
+    Status(StatusData),
+    Stats(OverviewData),
+    Balances(BalancesData),
+    Pairs(Vec<PairData>),
+    Paused,
+    Resumed,
+    /// Порог прибыли установлен, в процентах
+    ThresholdSet { pair: String, percent: f64 },
+    /// Сработал kill switch; false если часть ордеров не удалось отменить
+    Killed { orders_cancelled: bool },
+    /// Команда отклонена (причина от бота)
+    Refused(String),
+}
+
+/// Обработчик команд, реализуется ботом
+#[async_trait::async_trait]
+pub trait CommandHandler: Send + Sync {
+    /// Выполняет команду из чата `chat_id`
+    async fn handle(&self, command: Command, chat_id: &str) -> Reply;
+}
+
+/// Форматирует ответ на команду
+pub fn format_reply(reply: &Reply) -> String {
+    match reply {
+        Reply::Status(data) => format_status(data),
+        Reply::Stats(data) => format_overview(data),
+        Reply::Balances(data) => format_balances(data),
+        Reply::Pairs(pairs) => format_pairs(pairs),
+        Reply::Paused => "⏸ *Торговля приостановлена*\n\nВозобновить: /resume".to_string(),
+        Reply::Resumed => "▶️ *Торговля возобновлена*".to_string(),
+        Reply::ThresholdSet { pair, percent } => {
+            format!("✅ Порог прибыли для {}: *{:.4}%*", pair, percent)
+        }
+        Reply::Killed { orders_cancelled } => {
+            let orders = if *orders_cancelled {
+                "Открытые ордера отменены"
+            } else {
+                "⚠️ Часть ордеров отменить не удалось"
+            };
+            format!(
+                "🛑 *Kill switch*\n\nТорговля остановлена до перезапуска.\n{}",
+                orders
+            )
+        }
+        Reply::Refused(reason) => format!("⚠️ Команда отклонена: {}", reason),
+    }
+}
+
+/// Подсказка со списком команд
+fn format_usage(title: &str) -> String {
+    format!(
+        "{}\n\n\
+         /status - состояние бота\n\
+         /stats - статистика\n\
+         /balances - балансы\n\
+         /pause, /resume - приостановить или возобновить торговлю\n\
+         /pairs - пары и спреды\n\
+         /threshold <пара> <значение> - порог прибыли (\"0.003\" или \"0.3%\")\n\
+         /kill - аварийная остановка",
+        title
+    )
+}
@@ -1,5 +1,7 @@
+mod commands;
 mod notifier;
 mod telegram;
 
+pub use commands::*;
 pub use notifier::*;
 pub use telegram::*;
@@ -35,9 +35,34 @@ impl fmt::Display for EventType {
     }
 }
 
+/// Вид арбитражной возможности
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpportunityKind {
+    /// Покупка и продажа одной пары на разных биржах
+    #[default]
+    CrossExchange,
+    /// Цикл из трёх пар на одной бирже
+    Triangular,
+    /// Цикл из нескольких сделок и переводов между биржами
+    MultiHop,
+}
+
+/// Одна сделка (нога) арбитражной возможности
+#[derive(Debug, Clone)]
+pub struct LegData {
+    pub exchange: String,
+    pub pair: String,
+    /// true - покупка базовой валюты, false - продажа
+    pub buy: bool,
+    pub price: f64,
+    pub quantity: f64,
+}
+
 /// Данные об арбитражной возможности
 #[derive(Debug, Clone)]
 pub struct OpportunityData {
+    pub kind: OpportunityKind,
+    /// Пара, а для циклов - последовательность валют (например, "USDT/BTC/ETH")
     pub pair: String,
     pub buy_exchange: String,
     pub sell_exchange: String,
@@ -46,6 +71,8 @@ pub struct OpportunityData {
     pub spread_percent: f64,
     pub potential_profit: f64,
     pub quantity: f64,
+    /// Сделки в порядке исполнения
+    pub legs: Vec<LegData>,
 }
 
 /// Данные о выполнении сделки
@@ -98,6 +125,43 @@ pub struct OverviewData {
     pub dry_run: bool,
 }
 
+/// Состояние бота для команды /status
+#[derive(Debug, Clone)]
+pub struct StatusData {
+    pub version: String,
+    pub running: bool,
+    pub paused: bool,
+    /// Причина срабатывания kill switch
+    pub kill_switch: Option<String>,
+    pub dry_run: bool,
+    pub uptime: Duration,
+    /// Подключение к биржам
+    pub exchanges: Vec<(String, bool)>,
+    pub disabled_pairs: Vec<String>,
+    pub disabled_exchanges: Vec<String>,
+}
+
+/// Балансы по биржам для команды /balances
+#[derive(Debug, Clone)]
+pub struct BalancesData {
+    /// Биржа и балансы активов на ней
+    pub exchanges: Vec<(String, Vec<(String, f64)>)>,
+}
+
+/// Состояние пары для команды /pairs
+#[derive(Debug, Clone)]
+pub struct PairData {
+    pub pair: String,
+    pub enabled: bool,
+    /// Порог прибыли в процентах, если переопределен
+    pub threshold_percent: Option<f64>,
+    /// Лучший спред между биржами в процентах
+    pub spread_percent: Option<f64>,
+    /// Биржи лучшей покупки и лучшей продажи
+    pub buy_exchange: Option<String>,
+    pub sell_exchange: Option<String>,
+}
+
 /// Данные события
 #[derive(Debug, Clone)]
 pub enum EventData {
@@ -205,10 +269,10 @@ impl Notifier for MultiNotifier {
     async fn send(&self, event: &Event) -> Result<(), NotificationError> {
         let mut errors = Vec::new();
         for notifier in &self.notifiers {
-            if notifier.is_enabled(event.event_type) {
-                if let Err(e) = notifier.send(event).await {
-                    errors.push(e.message);
-                }
+            if notifier.is_enabled(event.event_type)
+                && let Err(e) = notifier.send(event).await
+            {
+                errors.push(e.message);
             }
         }
         if errors.is_empty() {
@@ -281,6 +345,10 @@ impl Notifier for NoopNotifier {
 
 /// Форматирует арбитражную возможность
 pub fn format_opportunity(data: &OpportunityData) -> String {
+    if data.kind != OpportunityKind::CrossExchange {
+        return format_path(data);
+    }
+
     let base = parse_pair_base(&data.pair);
     let pair_tag = format_pair_tag(&data.pair);
 
@@ -307,6 +375,54 @@ pub fn format_opportunity(data: &OpportunityData) -> String {
     )
 }
 
+/// Форматирует арбитражный цикл (треугольный или многоходовой)
+fn format_path(data: &OpportunityData) -> String {
+    let title = match data.kind {
+        OpportunityKind::MultiHop => "🔀 *Многоходовой арбитраж*",
+        _ => "🔺 *Треугольный арбитраж*",
+    };
+    // Цикл начинается и заканчивается в валюте прибыли
+    let asset = parse_pair_base(&data.pair);
+    let cycle_tag = format_pair_tag(&data.pair);
+
+    let legs: Vec<String> = data
+        .legs
+        .iter()
+        .enumerate()
+        .map(|(i, leg)| {
+            format!(
+                "{}. {}: {} {:.6} {} @ {:.8}",
+                i + 1,
+                leg.exchange,
+                if leg.buy { "Покупка" } else { "Продажа" },
+                leg.quantity,
+                leg.pair,
+                leg.price
+            )
+        })
+        .collect();
+
+    format!(
+        "{}\n\n\
+         💰 Прибыль: *{:.2}%*\n\
+         📈 Потенциальная прибыль: *{:.6} {}*\n\n\
+         Цикл: {} #{}\n\
+         {}\n\
+         Объём: {:.6} {}\n\n\
+         ⏰ {}",
+        title,
+        data.spread_percent,
+        data.potential_profit,
+        asset,
+        data.pair,
+        cycle_tag,
+        legs.join("\n"),
+        data.quantity,
+        asset,
+        Utc::now().format("%H:%M:%S UTC")
+    )
+}
+
 /// Форматирует результат выполнения сделки
 pub fn format_execution(data: &ExecutionData) -> String {
     if data.success {
@@ -436,6 +552,112 @@ pub fn format_overview(data: &OverviewData) -> String {
     )
 }
 
+/// Форматирует состояние бота
+pub fn format_status(data: &StatusData) -> String {
+    let mode = if data.dry_run {
+        "🧪 DRY RUN"
+    } else {
+        "🚀 LIVE"
+    };
+    let state = if let Some(ref reason) = data.kill_switch {
+        format!("🛑 Kill switch: {}", reason)
+    } else if data.paused {
+        "⏸ Приостановлен".to_string()
+    } else if data.running {
+        "▶️ Работает".to_string()
+    } else {
+        "⏹ Остановлен".to_string()
+    };
+
+    let exchanges: Vec<String> = data
+        .exchanges
+        .iter()
+        .map(|(name, connected)| {
+            let mark = if data.disabled_exchanges.contains(name) {
+                "⏸"
+            } else if *connected {
+                "✅"
+            } else {
+                "❌"
+            };
+            format!("{} {}", mark, name)
+        })
+        .collect();
+
+    let disabled_pairs = if data.disabled_pairs.is_empty() {
+        String::new()
+    } else {
+        format!("\nОтключенные пары: {}\n", data.disabled_pairs.join(", "))
+    };
+
+    format!(
+        "🤖 *Статус* {}\n\n\
+         Состояние: {}\n\
+         Версия: {}\n\
+         ⏱ Время работы: {}\n\n\
+         Биржи:\n{}\n{}\n\
+         ⏰ {}",
+        mode,
+        state,
+        data.version,
+        format_duration(data.uptime),
+        exchanges.join("\n"),
+        disabled_pairs,
+        Utc::now().format("%H:%M:%S UTC")
+    )
+}
+
+/// Форматирует балансы по биржам, нулевые балансы пропускаются
+pub fn format_balances(data: &BalancesData) -> String {
+    let mut sections = Vec::new();
+    for (exchange, assets) in &data.exchanges {
+        let lines: Vec<String> = assets
+            .iter()
+            .filter(|(_, amount)| *amount != 0.0)
+            .map(|(asset, amount)| format!("  {}: {}", asset, format_amount(*amount)))
+            .collect();
+        if !lines.is_empty() {
+            sections.push(format!("*{}*\n{}", exchange, lines.join("\n")));
+        }
+    }
+
+    if sections.is_empty() {
+        return "💼 *Балансы*\n\nНет данных: балансы еще не синхронизированы".to_string();
+    }
+    format!(
+        "💼 *Балансы*\n\n{}\n\n⏰ {}",
+        sections.join("\n\n"),
+        Utc::now().format("%H:%M:%S UTC")
+    )
+}
+
+/// Форматирует список пар со спредами и порогами
+pub fn format_pairs(pairs: &[PairData]) -> String {
+    let lines: Vec<String> = pairs
+        .iter()
+        .map(|p| {
+            let mark = if p.enabled { "✅" } else { "⏸" };
+            let spread = match (p.spread_percent, &p.buy_exchange, &p.sell_exchange) {
+                (Some(spread), Some(buy), Some(sell)) => {
+                    format!("{:+.4}% ({} → {})", spread, buy, sell)
+                }
+                _ => "нет данных".to_string(),
+            };
+            let threshold = p
+                .threshold_percent
+                .map(|t| format!(", порог {:.4}%", t))
+                .unwrap_or_default();
+            format!("{} #{}: {}{}", mark, format_pair_tag(&p.pair), spread, threshold)
+        })
+        .collect();
+
+    format!(
+        "📋 *Пары*\n\n{}\n\n⏰ {}",
+        lines.join("\n"),
+        Utc::now().format("%H:%M:%S UTC")
+    )
+}
+
 /// Форматирует событие в строку
 pub fn format_event(event: &Event) -> String {
     match &event.data {
@@ -475,6 +697,12 @@ fn format_duration(d: Duration) -> String {
     }
 }
 
+/// Форматирует количество актива без лишних нулей
+fn format_amount(amount: f64) -> String {
+    let s = format!("{:.8}", amount);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
 /// Добавляет разделители тысяч
 fn add_thousand_separators(n: u64) -> String {
     let s = n.to_string();
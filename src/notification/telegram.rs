@@ -1,23 +1,36 @@
 #![allow(dead_code)]
 
+use serde::Deserialize;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, Notify};
 use tokio::sync::mpsc;
-use tracing::error;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use zeroize::Zeroizing;
 
-use crate::notification::{Event, EventType, NotificationError, Notifier, format_event};
+use crate::notification::{
+    Command, CommandHandler, Event, EventType, NotificationError, Notifier, format_event,
+    format_reply,
+};
+use crate::secrets::Secret;
 
 const TELEGRAM_API_URL: &str = "https://api.telegram.org/bot";
 const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(10);
 const MAX_MESSAGE_LENGTH: usize = 4096;
 const ASYNC_QUEUE_SIZE: usize = 100;
+/// Сколько `close` по умолчанию ждет отправки сообщений из очереди
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Сколько Telegram держит запрос getUpdates, если новых сообщений нет
+const POLL_TIMEOUT: Duration = Duration::from_secs(25);
+/// Пауза перед повтором после ошибки getUpdates
+const POLL_RETRY_DELAY: Duration = Duration::from_secs(5);
 
 /// Конфигурация Telegram notifier
 #[derive(Debug, Clone)]
 pub struct TelegramConfig {
     /// Токен бота от BotFather
-    pub bot_token: String,
+    pub bot_token: Secret,
     /// ID чата для отправки уведомлений
     pub chat_id: String,
     /// Опциональный ID чата для ошибок
@@ -30,10 +43,14 @@ pub struct TelegramConfig {
     pub notify_errors: bool,
     /// Включить периодические обзоры
     pub notify_overview: bool,
+    /// Базовый URL Bot API, к которому дописывается токен
+    pub api_url: String,
+    /// Сколько `close` ждет отправки сообщений из очереди
+    pub close_timeout: Duration,
 }
 
 impl TelegramConfig {
-    pub fn new(bot_token: impl Into<String>, chat_id: impl Into<String>) -> Self {
+    pub fn new(bot_token: impl Into<Secret>, chat_id: impl Into<String>) -> Self {
         Self {
             bot_token: bot_token.into(),
             chat_id: chat_id.into(),
@@ -42,6 +59,8 @@ impl TelegramConfig {
             notify_executions: true,
             notify_errors: true,
             notify_overview: true,
+            api_url: TELEGRAM_API_URL.to_string(),
+            close_timeout: CLOSE_TIMEOUT,
         }
     }
 
@@ -49,15 +68,58 @@ impl TelegramConfig {
         self.error_chat_id = Some(chat_id.into());
         self
     }
+
+    /// Задает другой адрес Bot API (например, локальный сервер в тестах)
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    pub fn with_close_timeout(mut self, timeout: Duration) -> Self {
+        self.close_timeout = timeout;
+        self
+    }
 }
 
 /// Telegram Notifier для отправки уведомлений через Telegram Bot API
 pub struct TelegramNotifier {
     config: TelegramConfig,
     http_client: reqwest::Client,
-    api_url: String,
+    api_url: Zeroizing<String>,
     sender: mpsc::Sender<Event>,
-    shutdown: Arc<Mutex<bool>>,
+    /// Сигнал воркеру: отправить оставшиеся сообщения и завершиться
+    closing: Arc<Notify>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+    /// Задача обработки команд, если она запущена
+    poller: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Ответ getUpdates
+#[derive(Debug, Deserialize)]
+struct UpdatesResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    /// Время отправки, unix-секунды
+    date: i64,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
 }
 
 impl TelegramNotifier {
@@ -75,34 +137,155 @@ impl TelegramNotifier {
             .build()
             .map_err(|e| NotificationError::new(format!("Failed to create HTTP client: {}", e)))?;
 
-        let api_url = format!("{}{}", TELEGRAM_API_URL, config.bot_token);
+        let api_url = Zeroizing::new(format!("{}{}", config.api_url, config.bot_token.expose()));
 
         let (sender, receiver) = mpsc::channel(ASYNC_QUEUE_SIZE);
-        let shutdown = Arc::new(Mutex::new(false));
+        let closing = Arc::new(Notify::new());
+
+        // Запускаем воркер для асинхронной обработки
+        let worker = Self::spawn_worker(
+            receiver,
+            config.clone(),
+            http_client.clone(),
+            api_url.clone(),
+            closing.clone(),
+        );
 
-        let notifier = Self {
-            config: config.clone(),
-            http_client: http_client.clone(),
-            api_url: api_url.clone(),
+        Ok(Self {
+            config,
+            http_client,
+            api_url,
             sender,
-            shutdown: shutdown.clone(),
-        };
+            closing,
+            worker: Mutex::new(Some(worker)),
+            poller: Mutex::new(None),
+        })
+    }
 
-        // Запускаем воркер для асинхронной обработки
-        Self::spawn_worker(receiver, config, http_client, api_url, shutdown);
+    /// Запускает обработку команд через long polling getUpdates.
+    ///
+    /// Отвечает только в чаты из `allowed_chat_ids`; сообщения, отправленные
+    /// до запуска, пропускаются, чтобы после рестарта не выполнить старый /kill.
+    /// Задача работает до `close`.
+    pub async fn listen_commands(
+        &self,
+        handler: Arc<dyn CommandHandler>,
+        allowed_chat_ids: Vec<String>,
+    ) {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let http_client = self.http_client.clone();
+        let api_url = self.api_url.clone();
+
+        let poller = tokio::spawn(async move {
+            let mut offset = 0;
+            loop {
+                let updates = match Self::get_updates(&http_client, &api_url, offset).await {
+                    Ok(updates) => updates,
+                    Err(e) => {
+                        warn!(error = %e, "Failed to poll Telegram updates");
+                        tokio::time::sleep(POLL_RETRY_DELAY).await;
+                        continue;
+                    }
+                };
+
+                for update in updates {
+                    offset = offset.max(update.update_id + 1);
+                    let Some(message) = update.message else {
+                        continue;
+                    };
+                    if message.date < started {
+                        continue;
+                    }
+                    let Some(parsed) = message.text.as_deref().and_then(Command::parse) else {
+                        continue;
+                    };
+
+                    let chat_id = message.chat.id.to_string();
+                    if !allowed_chat_ids.contains(&chat_id) {
+                        warn!(chat_id = %chat_id, "Ignoring Telegram command from unauthorised chat");
+                        continue;
+                    }
+
+                    let reply = match parsed {
+                        Ok(command) => {
+                            info!(chat_id = %chat_id, command = ?command, "Telegram command");
+                            format_reply(&handler.handle(command, &chat_id).await)
+                        }
+                        Err(usage) => usage,
+                    };
+                    if let Err(e) =
+                        Self::send_message_to_chat_static(&http_client, &api_url, &chat_id, &reply)
+                            .await
+                    {
+                        error!(error = %e, "Failed to send Telegram reply");
+                    }
+                }
+            }
+        });
+
+        if let Some(previous) = self.poller.lock().await.replace(poller) {
+            previous.abort();
+        }
+    }
+
+    /// Получает новые сообщения, ожидая их до `POLL_TIMEOUT`
+    async fn get_updates(
+        http_client: &reqwest::Client,
+        api_url: &str,
+        offset: i64,
+    ) -> Result<Vec<Update>, NotificationError> {
+        let url = Zeroizing::new(format!("{}/getUpdates", api_url));
+
+        let payload = serde_json::json!({
+            "offset": offset,
+            "timeout": POLL_TIMEOUT.as_secs(),
+            "allowed_updates": ["message"]
+        });
+
+        let response: UpdatesResponse = http_client
+            .post(url.as_str())
+            .timeout(POLL_TIMEOUT + DEFAULT_HTTP_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| NotificationError::new(format!("HTTP request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| NotificationError::new(format!("Invalid getUpdates response: {}", e)))?;
 
-        Ok(notifier)
+        if !response.ok {
+            return Err(NotificationError::new(format!(
+                "Telegram API error: {}",
+                response.description.unwrap_or_else(|| "Unknown error".to_string())
+            )));
+        }
+        Ok(response.result)
     }
 
     fn spawn_worker(
         mut receiver: mpsc::Receiver<Event>,
         config: TelegramConfig,
         http_client: reqwest::Client,
-        api_url: String,
-        shutdown: Arc<Mutex<bool>>,
-    ) {
+        api_url: Zeroizing<String>,
+        closing: Arc<Notify>,
+    ) -> JoinHandle<()> {
         tokio::spawn(async move {
-            while let Some(event) = receiver.recv().await {
+            loop {
+                let event = tokio::select! {
+                    event = receiver.recv() => event,
+                    _ = closing.notified() => {
+                        // Новые сообщения больше не принимаются, очередь дочитывается до конца
+                        receiver.close();
+                        receiver.recv().await
+                    }
+                };
+                let Some(event) = event else {
+                    break;
+                };
+
                 let chat_id = if event.event_type == EventType::Error {
                     config.error_chat_id.as_ref().unwrap_or(&config.chat_id)
                 } else {
@@ -116,11 +299,7 @@ impl TelegramNotifier {
                     error!(error = %e, "Failed to send Telegram message");
                 }
             }
-
-            // Помечаем воркер как завершенный
-            let mut is_shutdown = shutdown.lock().await;
-            *is_shutdown = true;
-        });
+        })
     }
 
     async fn send_message_to_chat(
@@ -144,7 +323,7 @@ impl TelegramNotifier {
             text
         };
 
-        let url = format!("{}/sendMessage", api_url);
+        let url = Zeroizing::new(format!("{}/sendMessage", api_url));
 
         let payload = serde_json::json!({
             "chat_id": chat_id,
@@ -153,7 +332,7 @@ impl TelegramNotifier {
         });
 
         let response = http_client
-            .post(&url)
+            .post(url.as_str())
             .json(&payload)
             .send()
             .await
@@ -215,9 +394,31 @@ impl Notifier for TelegramNotifier {
         }
     }
 
+    /// Отправляет сообщения из очереди, но не дольше `close_timeout`
     async fn close(&self) -> Result<(), NotificationError> {
-        // Даем время воркеру обработать оставшиеся сообщения
-        tokio::time::sleep(Duration::from_millis(100)).await;
-        Ok(())
+        if let Some(poller) = self.poller.lock().await.take() {
+            poller.abort();
+        }
+
+        let Some(mut worker) = self.worker.lock().await.take() else {
+            return Ok(());
+        };
+
+        self.closing.notify_one();
+        let timeout = self.config.close_timeout;
+        if tokio::time::timeout(timeout, &mut worker).await.is_ok() {
+            return Ok(());
+        }
+
+        worker.abort();
+        let dropped = self.sender.max_capacity() - self.sender.capacity();
+        Err(NotificationError::new(format!(
+            "queue not flushed within {:?}, {} message(s) dropped",
+            timeout, dropped
+        )))
     }
 }
+
+#[cfg(test)]
+#[path = "telegram_tests.rs"]
+mod tests;
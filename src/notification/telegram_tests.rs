@@ -0,0 +1,200 @@
+//! Tests for Telegram commands, against a local mock of the Bot API.
+
+use super::*;
+use crate::notification::{ErrorData, Reply};
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{Value, json};
+use std::sync::Mutex as StdMutex;
+use tokio::net::TcpListener;
+
+const TOKEN: &str = "test-token";
+const CHAT_ID: i64 = 42;
+
+/// Mock Bot API: serves queued updates and records sent messages.
+#[derive(Default)]
+struct MockApi {
+    updates: StdMutex<Vec<Value>>,
+    sent: StdMutex<Vec<(String, String)>>,
+    /// How long sendMessage takes to answer.
+    send_delay: StdMutex<Duration>,
+}
+
+async fn get_updates(State(api): State<Arc<MockApi>>, Json(body): Json<Value>) -> Json<Value> {
+    let offset = body["offset"].as_i64().unwrap_or_default();
+    let updates: Vec<Value> = api
+        .updates
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|update| update["update_id"].as_i64().unwrap() >= offset)
+        .cloned()
+        .collect();
+    if updates.is_empty() {
+        // Stands in for the long poll
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    Json(json!({ "ok": true, "result": updates }))
+}
+
+async fn send_message(State(api): State<Arc<MockApi>>, Json(body): Json<Value>) -> Json<Value> {
+    let delay = *api.send_delay.lock().unwrap();
+    tokio::time::sleep(delay).await;
+    api.sent.lock().unwrap().push((
+        body["chat_id"].as_str().unwrap().to_string(),
+        body["text"].as_str().unwrap().to_string(),
+    ));
+    Json(json!({ "ok": true, "result": {} }))
+}
+
+/// Starts the mock and returns it with its Bot API base URL.
+async fn serve_mock_api(updates: Vec<Value>) -> (Arc<MockApi>, String) {
+    let api = Arc::new(MockApi::default());
+    *api.updates.lock().unwrap() = updates;
+
+    let app = Router::new()
+        .route(&format!("/bot{}/getUpdates", TOKEN), post(get_updates))
+        .route(&format!("/bot{}/sendMessage", TOKEN), post(send_message))
+        .with_state(Arc::clone(&api));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/bot", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (api, base)
+}
+
+fn update(update_id: i64, chat_id: i64, date: u64, text: &str) -> Value {
+    json!({
+        "update_id": update_id,
+        "message": {
+            "message_id": update_id,
+            "date": date,
+            "chat": { "id": chat_id, "type": "private" },
+            "text": text
+        }
+    })
+}
+
+/// Records commands; answers /pause and refuses everything else.
+#[derive(Default)]
+struct RecordingHandler {
+    commands: StdMutex<Vec<Command>>,
+}
+
+#[async_trait::async_trait]
+impl CommandHandler for RecordingHandler {
+    async fn handle(&self, command: Command, chat_id: &str) -> Reply {
+        assert_eq!(chat_id, CHAT_ID.to_string());
+        self.commands.lock().unwrap().push(command.clone());
+        match command {
+            Command::Pause => Reply::Paused,
+            _ => Reply::Refused("kill switch triggered: drill".to_string()),
+        }
+    }
+}
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(Command::parse("/status"), Some(Ok(Command::Status)));
+    assert_eq!(Command::parse("/Pause@arb_bot"), Some(Ok(Command::Pause)));
+    assert_eq!(
+        Command::parse("/threshold btc/usdt 0.3%"),
+        Some(Ok(Command::Threshold {
+            pair: "BTC/USDT".to_string(),
+            value: "0.3%".to_string(),
+        }))
+    );
+    assert_eq!(Command::parse("hello"), None);
+    assert!(matches!(Command::parse("/threshold BTC/USDT"), Some(Err(_))));
+    assert!(matches!(Command::parse("/withdraw"), Some(Err(_))));
+}
+
+#[tokio::test]
+async fn test_commands_answered_only_for_authorised_chats() {
+    // A second ahead, so the messages are never older than the listener
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + 1;
+    let (api, base) = serve_mock_api(vec![
+        update(1, CHAT_ID, now, "/pause@arb_bot"),
+        update(2, 7, now, "/kill"),
+        update(3, CHAT_ID, now, "hello"),
+        update(4, CHAT_ID, now, "/threshold BTC/USDT"),
+        // Sent before the listener started
+        update(5, CHAT_ID, 0, "/status"),
+        update(6, CHAT_ID, now, "/resume"),
+    ])
+    .await;
+
+    let notifier =
+        TelegramNotifier::new(TelegramConfig::new(TOKEN, CHAT_ID.to_string()).with_api_url(base))
+            .unwrap();
+    let handler = Arc::new(RecordingHandler::default());
+    notifier
+        .listen_commands(handler.clone(), vec![CHAT_ID.to_string()])
+        .await;
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while api.sent.lock().unwrap().len() < 3 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    // Answered updates are not fetched again
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    notifier.close().await.unwrap();
+
+    assert_eq!(
+        *handler.commands.lock().unwrap(),
+        vec![Command::Pause, Command::Resume]
+    );
+
+    let sent = api.sent.lock().unwrap();
+    assert_eq!(sent.len(), 3);
+    assert!(sent.iter().all(|(chat, _)| chat == &CHAT_ID.to_string()));
+    assert!(sent[0].1.contains("Торговля приостановлена"));
+    assert!(sent[1].1.contains("Использование: /threshold"));
+    assert!(sent[2].1.contains("Команда отклонена: kill switch triggered"));
+}
+
+fn error_event(message: &str) -> Event {
+    Event::error(ErrorData {
+        component: "test".to_string(),
+        message: message.to_string(),
+        error: None,
+    })
+}
+
+#[tokio::test]
+async fn test_close_flushes_the_queue() {
+    let (api, base) = serve_mock_api(Vec::new()).await;
+    let notifier =
+        TelegramNotifier::new(TelegramConfig::new(TOKEN, CHAT_ID.to_string()).with_api_url(base))
+            .unwrap();
+
+    for i in 0..3 {
+        notifier.send_async(error_event(&format!("error {}", i)));
+    }
+    notifier.close().await.unwrap();
+
+    assert_eq!(api.sent.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_close_gives_up_at_the_deadline() {
+    let (api, base) = serve_mock_api(Vec::new()).await;
+    *api.send_delay.lock().unwrap() = Duration::from_secs(5);
+    let notifier = TelegramNotifier::new(
+        TelegramConfig::new(TOKEN, CHAT_ID.to_string())
+            .with_api_url(base)
+            .with_close_timeout(Duration::from_millis(100)),
+    )
+    .unwrap();
+
+    for i in 0..3 {
+        notifier.send_async(error_event(&format!("error {}", i)));
+    }
+    let started = tokio::time::Instant::now();
+    let err = notifier.close().await.unwrap_err();
+
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert!(err.message.contains("message(s) dropped"), "{}", err.message);
+    assert!(api.sent.lock().unwrap().is_empty());
+}
@@ -1,6 +1,7 @@
 //! Tests for notification formatting functions.
 
 use super::*;
+use crate::notification::{Reply, format_reply};
 use std::time::Duration;
 
 // ==================== Helper function tests ====================
@@ -92,6 +93,7 @@ fn test_add_thousand_separators_zero() {
 #[test]
 fn test_format_opportunity_contains_pair_tag() {
     let data = OpportunityData {
+        kind: OpportunityKind::CrossExchange,
         pair: "BTC/USDT".to_string(),
         buy_exchange: "Binance".to_string(),
         sell_exchange: "Bybit".to_string(),
@@ -100,6 +102,7 @@ fn test_format_opportunity_contains_pair_tag() {
         spread_percent: 0.24,
         potential_profit: 10.0,
         quantity: 0.1,
+        legs: vec![],
     };
 
     let msg = format_opportunity(&data);
@@ -111,6 +114,41 @@ fn test_format_opportunity_contains_pair_tag() {
     assert!(msg.contains("0.24%"));
 }
 
+#[test]
+fn test_format_opportunity_triangular_lists_legs() {
+    let leg = |pair: &str, buy: bool, price: f64, quantity: f64| LegData {
+        exchange: "Poloniex".to_string(),
+        pair: pair.to_string(),
+        buy,
+        price,
+        quantity,
+    };
+    let data = OpportunityData {
+        kind: OpportunityKind::Triangular,
+        pair: "USDT/BTC/ETH".to_string(),
+        buy_exchange: "Poloniex".to_string(),
+        sell_exchange: "Poloniex".to_string(),
+        buy_price: 40000.0,
+        sell_price: 2100.0,
+        spread_percent: 5.0,
+        potential_profit: 400.0,
+        quantity: 8000.0,
+        legs: vec![
+            leg("BTC/USDT", true, 40000.0, 0.2),
+            leg("ETH/BTC", true, 0.05, 4.0),
+            leg("ETH/USDT", false, 2100.0, 4.0),
+        ],
+    };
+
+    let msg = format_opportunity(&data);
+
+    assert!(msg.contains("Треугольный арбитраж"));
+    assert!(msg.contains("#USDT\\_BTC\\_ETH"));
+    assert!(msg.contains("1. Poloniex: Покупка 0.200000 BTC/USDT"));
+    assert!(msg.contains("3. Poloniex: Продажа 4.000000 ETH/USDT"));
+    assert!(msg.contains("400.000000 USDT"));
+}
+
 #[test]
 fn test_format_startup_dry_run() {
     let data = StartupData {
@@ -247,6 +285,7 @@ fn test_format_overview() {
 #[test]
 fn test_event_opportunity_constructor() {
     let data = OpportunityData {
+        kind: OpportunityKind::CrossExchange,
         pair: "BTC/USDT".to_string(),
         buy_exchange: "Binance".to_string(),
         sell_exchange: "Bybit".to_string(),
@@ -255,6 +294,7 @@ fn test_event_opportunity_constructor() {
         spread_percent: 0.24,
         potential_profit: 10.0,
         quantity: 0.1,
+        legs: vec![],
     };
 
     let event = Event::opportunity(data);
@@ -284,3 +324,100 @@ fn test_event_type_display() {
     assert_eq!(EventType::Shutdown.to_string(), "shutdown");
     assert_eq!(EventType::Overview.to_string(), "overview");
 }
+
+// ==================== Command reply tests ====================
+
+#[test]
+fn test_format_amount_trims_zeros() {
+    assert_eq!(format_amount(1.5), "1.5");
+    assert_eq!(format_amount(100.0), "100");
+    assert_eq!(format_amount(0.00012), "0.00012");
+}
+
+#[test]
+fn test_format_balances_skips_zero_amounts() {
+    let data = BalancesData {
+        exchanges: vec![
+            (
+                "poloniex".to_string(),
+                vec![("BTC".to_string(), 0.25), ("ETH".to_string(), 0.0)],
+            ),
+            ("gate".to_string(), vec![("USDT".to_string(), 0.0)]),
+        ],
+    };
+
+    let text = format_balances(&data);
+    assert!(text.contains("*poloniex*\n  BTC: 0.25"));
+    assert!(!text.contains("ETH"));
+    assert!(!text.contains("gate"));
+
+    let empty = format_balances(&BalancesData { exchanges: vec![] });
+    assert!(empty.contains("Нет данных"));
+}
+
+#[test]
+fn test_format_pairs_shows_spread_and_threshold() {
+    let pairs = vec![
+        PairData {
+            pair: "BTC/USDT".to_string(),
+            enabled: true,
+            threshold_percent: Some(0.5),
+            spread_percent: Some(0.25),
+            buy_exchange: Some("gate".to_string()),
+            sell_exchange: Some("poloniex".to_string()),
+        },
+        PairData {
+            pair: "ETH/USDT".to_string(),
+            enabled: false,
+            threshold_percent: None,
+            spread_percent: None,
+            buy_exchange: None,
+            sell_exchange: None,
+        },
+    ];
+
+    let text = format_pairs(&pairs);
+    assert!(text.contains("✅ #BTC\\_USDT: +0.2500% (gate → poloniex), порог 0.5000%"));
+    assert!(text.contains("⏸ #ETH\\_USDT: нет данных"));
+}
+
+#[test]
+fn test_format_status_reports_kill_switch_and_exchanges() {
+    let data = StatusData {
+        version: "1.0.0".to_string(),
+        running: true,
+        paused: true,
+        kill_switch: Some("drill".to_string()),
+        dry_run: true,
+        uptime: Duration::from_secs(3700),
+        exchanges: vec![("gate".to_string(), false), ("poloniex".to_string(), true)],
+        disabled_pairs: vec!["ETH/USDT".to_string()],
+        disabled_exchanges: vec![],
+    };
+
+    let text = format_status(&data);
+    assert!(text.contains("Kill switch: drill"));
+    assert!(text.contains("1ч 1м"));
+    assert!(text.contains("❌ gate\n✅ poloniex"));
+    assert!(text.contains("Отключенные пары: ETH/USDT"));
+}
+
+#[test]
+fn test_format_reply_reuses_overview() {
+    let data = OverviewData {
+        uptime: Duration::from_secs(60),
+        detection_cycles: 1500,
+        opportunities_detected: 3,
+        opportunities_executed: 1,
+        successful_trades: 1,
+        failed_trades: 0,
+        total_profit: 12.5,
+        dry_run: false,
+    };
+
+    let text = format_reply(&Reply::Stats(data));
+    assert!(text.contains("Обзор торговли"));
+    assert!(text.contains("1,500"));
+    assert!(format_reply(&Reply::Refused("pair XRP/USDT is not monitored".to_string()))
+        .contains("XRP/USDT"));
+}
@@ -0,0 +1,27 @@
+//! Secret resolution error types.
+
+use thiserror::Error;
+
+/// Error resolving a secret or accessing the keystore.
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("invalid secret reference \"{0}\" (expected env:NAME, file:PATH or keystore:NAME)")]
+    InvalidReference(String),
+    #[error("no secret provider for {0}")]
+    NoProvider(String),
+    #[error("failed to read {reference}: {source}")]
+    Read {
+        reference: String,
+        source: std::io::Error,
+    },
+    #[error("keystore entry {0} not found")]
+    NotFound(String),
+    #[error("wrong keystore passphrase")]
+    WrongPassphrase,
+    #[error("keystore passphrase is empty")]
+    MissingPassphrase,
+    #[error("malformed keystore: {0}")]
+    Malformed(String),
+    #[error("keystore I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,258 @@
+//! Encrypted local keystore.
+//!
+//! A JSON file of named entries, each encrypted with ChaCha20-Poly1305 under a
+//! key derived from a passphrase with Argon2id. Entry names are authenticated
+//! with their ciphertext, so entries cannot be swapped between names. The file
+//! also holds an encrypted empty verifier that detects a wrong passphrase.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+use super::{Secret, SecretError, SecretProvider};
+
+/// Current keystore file format version.
+const VERSION: u32 = 1;
+
+/// Associated data of the passphrase verifier; entries use `entry:{name}`.
+const VERIFIER_AAD: &[u8] = b"verifier";
+
+/// Length of the random KDF salt in bytes.
+const SALT_LEN: usize = 16;
+
+/// Argon2id cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory size in KiB.
+    pub m_cost: u32,
+    /// Number of iterations.
+    pub t_cost: u32,
+    /// Degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// The Argon2 recommended defaults (19 MiB, 2 iterations, 1 lane).
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// On-disk keystore layout.
+#[derive(Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    verifier: Sealed,
+    entries: BTreeMap<String, Sealed>,
+}
+
+/// A base64 nonce and ciphertext.
+#[derive(Clone, Serialize, Deserialize)]
+struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// An unlocked keystore.
+pub struct Keystore {
+    path: PathBuf,
+    file: KeystoreFile,
+    cipher: ChaCha20Poly1305,
+}
+
+impl Keystore {
+    /// Creates an empty keystore at `path`; nothing is written until [`Keystore::save`].
+    pub fn create(
+        path: impl AsRef<Path>,
+        passphrase: &Secret,
+        kdf: KdfParams,
+    ) -> Result<Self, SecretError> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        let cipher = derive_cipher(passphrase, &salt, kdf)?;
+        let verifier = seal(&cipher, b"", VERIFIER_AAD)?;
+
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            file: KeystoreFile {
+                version: VERSION,
+                kdf,
+                salt: BASE64.encode(salt),
+                verifier,
+                entries: BTreeMap::new(),
+            },
+            cipher,
+        })
+    }
+
+    /// Opens the keystore at `path` and checks the passphrase.
+    pub fn open(path: impl AsRef<Path>, passphrase: &Secret) -> Result<Self, SecretError> {
+        let path = path.as_ref();
+        let file: KeystoreFile = serde_json::from_slice(&fs::read(path)?)
+            .map_err(|e| SecretError::Malformed(e.to_string()))?;
+        if file.version != VERSION {
+            return Err(SecretError::Malformed(format!(
+                "unsupported version {}",
+                file.version
+            )));
+        }
+
+        let salt = decode(&file.salt)?;
+        let cipher = derive_cipher(passphrase, &salt, file.kdf)?;
+        open_sealed(&cipher, &file.verifier, VERIFIER_AAD)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            cipher,
+        })
+    }
+
+    /// Opens the keystore at `path`, or creates an empty one if it does not exist.
+    pub fn open_or_create(path: impl AsRef<Path>, passphrase: &Secret) -> Result<Self, SecretError> {
+        if path.as_ref().exists() {
+            Self::open(path, passphrase)
+        } else {
+            Self::create(path, passphrase, KdfParams::default())
+        }
+    }
+
+    /// Returns the names of the stored entries, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.file.entries.keys().map(String::as_str)
+    }
+
+    /// Decrypts the entry stored under `name`.
+    pub fn get(&self, name: &str) -> Result<Option<Secret>, SecretError> {
+        let Some(sealed) = self.file.entries.get(name) else {
+            return Ok(None);
+        };
+        let plaintext = open_sealed(&self.cipher, sealed, &entry_aad(name))?;
+        let value = String::from_utf8(plaintext.to_vec())
+            .map_err(|_| SecretError::Malformed(format!("entry {} is not UTF-8", name)))?;
+        Ok(Some(Secret::new(value)))
+    }
+
+    /// Encrypts and stores a value under `name`, replacing any previous one.
+    pub fn set(&mut self, name: &str, value: &Secret) -> Result<(), SecretError> {
+        let sealed = seal(&self.cipher, value.expose().as_bytes(), &entry_aad(name))?;
+        self.file.entries.insert(name.to_string(), sealed);
+        Ok(())
+    }
+
+    /// Removes the entry stored under `name`. Returns false if there was none.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.file.entries.remove(name).is_some()
+    }
+
+    /// Writes the keystore, replacing the file atomically. On Unix the file is
+    /// readable by its owner only.
+    pub fn save(&self) -> Result<(), SecretError> {
+        let content = serde_json::to_vec_pretty(&self.file)
+            .map_err(|e| SecretError::Malformed(e.to_string()))?;
+
+        let tmp = self.path.with_extension("tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(&tmp)?;
+        file.write_all(&content)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl SecretProvider for Keystore {
+    fn scheme(&self) -> &str {
+        "keystore"
+    }
+
+    /// Unlike unset variables, a missing entry is an error: it was named explicitly.
+    fn get(&self, key: &str) -> Result<Option<Secret>, SecretError> {
+        match Keystore::get(self, key)? {
+            Some(secret) => Ok(Some(secret)),
+            None => Err(SecretError::NotFound(key.to_string())),
+        }
+    }
+}
+
+/// Derives the entry cipher from the passphrase.
+fn derive_cipher(
+    passphrase: &Secret,
+    salt: &[u8],
+    kdf: KdfParams,
+) -> Result<ChaCha20Poly1305, SecretError> {
+    if passphrase.is_empty() {
+        return Err(SecretError::MissingPassphrase);
+    }
+
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(32))
+        .map_err(|e| SecretError::Malformed(format!("KDF parameters: {}", e)))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.expose().as_bytes(), salt, key.as_mut())
+        .map_err(|e| SecretError::Malformed(format!("key derivation: {}", e)))?;
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+}
+
+/// Encrypts `plaintext` under a fresh random nonce.
+fn seal(cipher: &ChaCha20Poly1305, plaintext: &[u8], aad: &[u8]) -> Result<Sealed, SecretError> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| SecretError::Malformed("encryption failed".to_string()))?;
+
+    Ok(Sealed {
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+/// Decrypts a sealed value. A wrong key and tampered data are indistinguishable.
+fn open_sealed(
+    cipher: &ChaCha20Poly1305,
+    sealed: &Sealed,
+    aad: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, SecretError> {
+    let nonce = decode(&sealed.nonce)?;
+    if nonce.len() != 12 {
+        return Err(SecretError::Malformed("invalid nonce".to_string()));
+    }
+    let ciphertext = decode(&sealed.ciphertext)?;
+
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad })
+        .map(Zeroizing::new)
+        .map_err(|_| SecretError::WrongPassphrase)
+}
+
+/// Associated data binding an entry's ciphertext to its name.
+fn entry_aad(name: &str) -> Vec<u8> {
+    format!("entry:{}", name).into_bytes()
+}
+
+fn decode(s: &str) -> Result<Vec<u8>, SecretError> {
+    BASE64
+        .decode(s)
+        .map_err(|e| SecretError::Malformed(e.to_string()))
+}
@@ -0,0 +1,248 @@
+//! Secret values and the providers that resolve them.
+//!
+//! Config fields holding credentials take a reference of the form `scheme:key`:
+//! - `env:NAME` reads an environment variable (after `.env` is loaded);
+//! - `file:/run/secrets/name` reads a file, e.g. a Docker or Kubernetes secret;
+//! - `keystore:name` reads an entry of the encrypted keystore (see [`Keystore`]).
+//!
+//! Resolved values are zeroed when dropped and never shown by `Debug`.
+
+mod error;
+pub(crate) mod keystore;
+
+pub use error::SecretError;
+pub use keystore::Keystore;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+/// Environment variable holding the keystore passphrase by default.
+pub const KEYSTORE_PASSPHRASE_VAR: &str = "KEYSTORE_PASSPHRASE";
+
+/// Placeholder shown instead of a secret value.
+const REDACTED: &str = "<redacted>";
+
+/// Where a secret is read from: a provider scheme and a key within it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretRef {
+    scheme: String,
+    key: String,
+}
+
+impl SecretRef {
+    /// Creates a reference to `key` of the provider registered for `scheme`.
+    pub fn new(scheme: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            scheme: scheme.into(),
+            key: key.into(),
+        }
+    }
+
+    /// Creates a reference to an environment variable.
+    pub fn env(name: impl Into<String>) -> Self {
+        Self::new("env", name)
+    }
+}
+
+impl FromStr for SecretRef {
+    type Err = SecretError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SecretError::InvalidReference(s.to_string());
+        let (scheme, key) = s.split_once(':').ok_or_else(invalid)?;
+        if scheme.is_empty() || !scheme.chars().all(|c| c.is_ascii_lowercase()) || key.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self::new(scheme, key))
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.scheme, self.key)
+    }
+}
+
+/// A credential: where it comes from and, once resolved, its value.
+///
+/// Deserializes from a reference string; an empty string leaves the field's
+/// default reference in place.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret {
+    reference: Option<SecretRef>,
+    value: Zeroizing<String>,
+}
+
+impl Secret {
+    /// Creates a resolved secret.
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            reference: None,
+            value: Zeroizing::new(value.into()),
+        }
+    }
+
+    /// Returns the secret value.
+    pub fn expose(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns true if the secret has no value (unresolved or unset).
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Resolves the value from the configured reference, or from `default`
+    /// when none is configured. An unset default leaves the secret empty.
+    pub fn resolve(&mut self, providers: &Providers, default: &SecretRef) -> Result<(), SecretError> {
+        let reference = self.reference.as_ref().unwrap_or(default);
+        let value = providers.resolve(reference)?;
+        self.value = value.map(|secret| secret.value).unwrap_or_default();
+        Ok(())
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = if self.is_empty() { "" } else { REDACTED };
+        match self.reference {
+            Some(ref reference) => write!(f, "Secret({}, {:?})", reference, value),
+            None => write!(f, "Secret({:?})", value),
+        }
+    }
+}
+
+impl fmt::Display for Secret {
+    /// Shows the placeholder for a value, else the reference.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.is_empty(), &self.reference) {
+            (false, _) => f.write_str(REDACTED),
+            (true, Some(reference)) => write!(f, "{}", reference),
+            (true, None) => Ok(()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(SecretVisitor)
+    }
+}
+
+struct SecretVisitor;
+
+impl Visitor<'_> for SecretVisitor {
+    type Value = Secret;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a secret reference such as env:NAME, file:PATH or keystore:NAME")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Secret, E> {
+        if v.is_empty() {
+            return Ok(Secret::default());
+        }
+        let reference = v.parse().map_err(E::custom)?;
+        Ok(Secret {
+            reference: Some(reference),
+            value: Zeroizing::default(),
+        })
+    }
+}
+
+/// A source of secrets, addressed by the scheme of a [`SecretRef`].
+pub trait SecretProvider: Send + Sync {
+    /// Reference scheme served by this provider (e.g. "env").
+    fn scheme(&self) -> &str;
+
+    /// Returns the secret stored under `key`, or None if it is not set.
+    fn get(&self, key: &str) -> Result<Option<Secret>, SecretError>;
+}
+
+/// Reads environment variables. Unset variables resolve to nothing.
+pub struct EnvProvider;
+
+impl SecretProvider for EnvProvider {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Secret>, SecretError> {
+        Ok(std::env::var(key).ok().map(Secret::new))
+    }
+}
+
+/// Reads files, dropping the trailing newline most secret mounts include.
+pub struct FileProvider;
+
+impl SecretProvider for FileProvider {
+    fn scheme(&self) -> &str {
+        "file"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Secret>, SecretError> {
+        let mut content = Zeroizing::new(std::fs::read_to_string(key).map_err(|source| {
+            SecretError::Read {
+                reference: format!("file:{}", key),
+                source,
+            }
+        })?);
+        let len = content.trim_end_matches(['\r', '\n']).len();
+        content.truncate(len);
+        Ok(Some(Secret {
+            reference: None,
+            value: content,
+        }))
+    }
+}
+
+/// The secret providers available to resolve references, by scheme.
+pub struct Providers(Vec<Box<dyn SecretProvider>>);
+
+impl Providers {
+    /// Creates the environment and file providers.
+    pub fn new() -> Self {
+        Self(vec![Box::new(EnvProvider), Box::new(FileProvider)])
+    }
+
+    /// Adds a provider, replacing any registered for the same scheme.
+    pub fn with(mut self, provider: impl SecretProvider + 'static) -> Self {
+        self.0.retain(|p| p.scheme() != provider.scheme());
+        self.0.push(Box::new(provider));
+        self
+    }
+
+    /// Resolves a reference with the provider registered for its scheme.
+    pub fn resolve(&self, reference: &SecretRef) -> Result<Option<Secret>, SecretError> {
+        let provider = self
+            .0
+            .iter()
+            .find(|p| p.scheme() == reference.scheme)
+            .ok_or_else(|| SecretError::NoProvider(reference.to_string()))?;
+        provider.get(&reference.key)
+    }
+}
+
+impl Default for Providers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,177 @@
+//! Tests for secret references, providers and the keystore.
+
+use super::*;
+use super::keystore::KdfParams;
+use tempfile::TempDir;
+
+/// Cheap key derivation so tests stay fast.
+const TEST_KDF: KdfParams = KdfParams {
+    m_cost: 64,
+    t_cost: 1,
+    p_cost: 1,
+};
+
+fn passphrase(s: &str) -> Secret {
+    Secret::new(s)
+}
+
+#[test]
+fn test_parse_references() {
+    let reference: SecretRef = "file:/run/secrets/api_key".parse().unwrap();
+    assert_eq!(reference, SecretRef::new("file", "/run/secrets/api_key"));
+    assert_eq!(reference.to_string(), "file:/run/secrets/api_key");
+
+    for invalid in ["plain-api-key", "env:", ":KEY", "Env:KEY"] {
+        assert!(
+            matches!(invalid.parse::<SecretRef>(), Err(SecretError::InvalidReference(_))),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_debug_and_display_hide_values() {
+    let secret = Secret::new("hunter2");
+    assert_eq!(format!("{:?}", secret), "Secret(\"<redacted>\")");
+    assert_eq!(secret.to_string(), "<redacted>");
+
+    let unresolved: Secret = serde_yaml::from_str("keystore:api_key").unwrap();
+    assert!(unresolved.is_empty());
+    assert_eq!(format!("{:?}", unresolved), "Secret(keystore:api_key, \"\")");
+    assert_eq!(unresolved.to_string(), "keystore:api_key");
+}
+
+#[test]
+fn test_deserialize_rejects_plain_values() {
+    let err = serde_yaml::from_str::<Secret>("my-api-key").unwrap_err();
+    assert!(err.to_string().contains("invalid secret reference"), "{}", err);
+
+    let empty: Secret = serde_yaml::from_str("\"\"").unwrap();
+    assert_eq!(empty, Secret::default());
+}
+
+#[test]
+fn test_resolve_uses_default_reference_when_unset() {
+    unsafe {
+        std::env::set_var("SECRETS_TEST_DEFAULT", "from-env");
+    }
+
+    let mut secret = Secret::default();
+    secret
+        .resolve(&Providers::new(), &SecretRef::env("SECRETS_TEST_DEFAULT"))
+        .unwrap();
+    assert_eq!(secret.expose(), "from-env");
+
+    // An unset variable leaves the secret empty
+    let mut secret = Secret::default();
+    secret
+        .resolve(&Providers::new(), &SecretRef::env("SECRETS_TEST_UNSET"))
+        .unwrap();
+    assert!(secret.is_empty());
+
+    unsafe {
+        std::env::remove_var("SECRETS_TEST_DEFAULT");
+    }
+}
+
+#[test]
+fn test_file_provider_trims_trailing_newline() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("api_key");
+    std::fs::write(&path, "s3cret\n").unwrap();
+
+    let mut secret: Secret = serde_yaml::from_str(&format!("file:{}", path.display())).unwrap();
+    secret
+        .resolve(&Providers::new(), &SecretRef::env("UNUSED"))
+        .unwrap();
+    assert_eq!(secret.expose(), "s3cret");
+
+    let missing = SecretRef::new("file", dir.path().join("missing").display().to_string());
+    assert!(matches!(
+        Providers::new().resolve(&missing),
+        Err(SecretError::Read { .. })
+    ));
+}
+
+#[test]
+fn test_unknown_scheme_has_no_provider() {
+    let reference = SecretRef::new("vault", "api_key");
+    assert!(matches!(
+        Providers::new().resolve(&reference),
+        Err(SecretError::NoProvider(_))
+    ));
+}
+
+#[test]
+fn test_keystore_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("keystore.json");
+
+    let mut keystore = Keystore::create(&path, &passphrase("correct horse"), TEST_KDF).unwrap();
+    keystore.set("poloniex_api_key", &Secret::new("key-123")).unwrap();
+    keystore.set("poloniex_api_secret", &Secret::new("secret-456")).unwrap();
+    keystore.save().unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(!content.contains("key-123"));
+    assert!(!content.contains("secret-456"));
+
+    let keystore = Keystore::open(&path, &passphrase("correct horse")).unwrap();
+    assert_eq!(
+        keystore.names().collect::<Vec<_>>(),
+        vec!["poloniex_api_key", "poloniex_api_secret"]
+    );
+
+    let providers = Providers::new().with(keystore);
+    let secret = providers
+        .resolve(&SecretRef::new("keystore", "poloniex_api_secret"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(secret.expose(), "secret-456");
+    assert!(matches!(
+        providers.resolve(&SecretRef::new("keystore", "missing")),
+        Err(SecretError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_keystore_rejects_wrong_passphrase() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("keystore.json");
+    Keystore::create(&path, &passphrase("right"), TEST_KDF)
+        .unwrap()
+        .save()
+        .unwrap();
+
+    assert!(matches!(
+        Keystore::open(&path, &passphrase("wrong")),
+        Err(SecretError::WrongPassphrase)
+    ));
+    assert!(matches!(
+        Keystore::open(&path, &Secret::default()),
+        Err(SecretError::MissingPassphrase)
+    ));
+}
+
+#[test]
+fn test_keystore_detects_swapped_entries() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("keystore.json");
+
+    let mut keystore = Keystore::create(&path, &passphrase("pass"), TEST_KDF).unwrap();
+    keystore.set("a", &Secret::new("value-a")).unwrap();
+    keystore.set("b", &Secret::new("value-b")).unwrap();
+    keystore.save().unwrap();
+
+    let mut file: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let entries = file["entries"].as_object_mut().unwrap();
+    let a = entries["a"].clone();
+    entries.insert("b".to_string(), a);
+    std::fs::write(&path, file.to_string()).unwrap();
+
+    let keystore = Keystore::open(&path, &passphrase("pass")).unwrap();
+    assert_eq!(keystore.get("a").unwrap().unwrap().expose(), "value-a");
+    assert!(keystore.get("b").is_err());
+}
@@ -0,0 +1,145 @@
+//! Serialisation of opportunities to JSONL and CSV files.
+
+use std::io::Write;
+
+use crate::config::ArchiveFormat;
+use crate::domain::Opportunity;
+use crate::storage::{OpportunityQuery, Storage, StorageError};
+
+/// Number of opportunities fetched per page when exporting.
+const EXPORT_PAGE_SIZE: u32 = 1000;
+
+/// CSV columns, in order. Legs are embedded as a JSON array.
+const CSV_HEADER: [&str; 17] = [
+    "id",
+    "type",
+    "pair",
+    "buy_exchange",
+    "sell_exchange",
+    "buy_price",
+    "sell_price",
+    "quantity",
+    "gross_profit",
+    "net_profit",
+    "net_profit_after_rebalance",
+    "profit_percent",
+    "buy_fee",
+    "sell_fee",
+    "detected_at",
+    "expires_at",
+    "legs",
+];
+
+/// OpportunityWriter writes opportunities to a stream in one format.
+pub struct OpportunityWriter<W: Write> {
+    sink: Sink<W>,
+}
+
+enum Sink<W: Write> {
+    Jsonl(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> OpportunityWriter<W> {
+    /// Creates a writer; CSV output starts with the header row.
+    pub fn new(writer: W, format: ArchiveFormat) -> Result<Self, StorageError> {
+        let sink = match format {
+            ArchiveFormat::Jsonl => Sink::Jsonl(writer),
+            ArchiveFormat::Csv => {
+                let mut csv = csv::Writer::from_writer(writer);
+                csv.write_record(CSV_HEADER).map_err(std::io::Error::from)?;
+                Sink::Csv(Box::new(csv))
+            }
+        };
+
+        Ok(Self { sink })
+    }
+
+    /// Writes one opportunity.
+    pub fn write(&mut self, opp: &Opportunity) -> Result<(), StorageError> {
+        match self.sink {
+            Sink::Jsonl(ref mut writer) => {
+                serde_json::to_writer(&mut *writer, opp).map_err(std::io::Error::from)?;
+                writer.write_all(b"\n")?;
+            }
+            Sink::Csv(ref mut writer) => {
+                let legs = serde_json::to_string(&opp.legs).map_err(std::io::Error::from)?;
+                writer
+                    .write_record([
+                        opp.id.clone(),
+                        opp.opportunity_type.to_string(),
+                        opp.pair.clone(),
+                        opp.buy_exchange.clone(),
+                        opp.sell_exchange.clone(),
+                        opp.buy_price.to_string(),
+                        opp.sell_price.to_string(),
+                        opp.quantity.to_string(),
+                        opp.gross_profit.to_string(),
+                        opp.net_profit.to_string(),
+                        opp.net_profit_after_rebalance.to_string(),
+                        opp.profit_percent.to_string(),
+                        opp.buy_fee.to_string(),
+                        opp.sell_fee.to_string(),
+                        opp.detected_at.to_rfc3339(),
+                        opp.expires_at.to_rfc3339(),
+                        legs,
+                    ])
+                    .map_err(std::io::Error::from)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flushes buffered output to the underlying stream.
+    pub fn flush(&mut self) -> Result<(), StorageError> {
+        match self.sink {
+            Sink::Jsonl(ref mut writer) => writer.flush()?,
+            Sink::Csv(ref mut writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying stream.
+    pub fn into_inner(self) -> Result<W, StorageError> {
+        match self.sink {
+            Sink::Jsonl(mut writer) => {
+                writer.flush()?;
+                Ok(writer)
+            }
+            Sink::Csv(writer) => Ok(writer
+                .into_inner()
+                .map_err(|e| std::io::Error::other(e.to_string()))?),
+        }
+    }
+}
+
+/// Writes every opportunity matching the query, fetching one page at a time.
+///
+/// The query's limit sets the page size rather than capping the export.
+/// Returns the number of opportunities written.
+pub async fn export_opportunities<W: Write>(
+    storage: &dyn Storage,
+    query: &OpportunityQuery,
+    writer: &mut OpportunityWriter<W>,
+) -> Result<u64, StorageError> {
+    let mut query = query.clone();
+    query.limit = Some(query.limit.unwrap_or(EXPORT_PAGE_SIZE));
+
+    let mut exported = 0;
+    loop {
+        let page = storage.query(&query).await?;
+        for opp in &page.items {
+            writer.write(opp)?;
+        }
+        exported += page.items.len() as u64;
+
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => break,
+        }
+    }
+
+    writer.flush()?;
+    Ok(exported)
+}
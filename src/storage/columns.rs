@@ -0,0 +1,275 @@
+//! Conversions between domain values and column values, shared by all backends.
+//!
+//! Timestamps are stored as integer milliseconds since the Unix epoch. Decimals
+//! are stored as exact TEXT alongside a `_real` floating-point shadow used by
+//! SQL arithmetic.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Timelike, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use sqlx::postgres::PgRow;
+use sqlx::sqlite::SqliteRow;
+
+use crate::domain::{
+    Execution, ExecutionLeg, Opportunity, OpportunityLifecycle, Order, Trade,
+};
+use crate::storage::{OpportunityRollup, OpportunityStats, StorageError};
+
+/// Typed column access implemented by the row type of each backend.
+pub(super) trait ColumnRow {
+    fn text(&self, column: &str) -> Result<String, StorageError>;
+    fn optional_text(&self, column: &str) -> Result<Option<String>, StorageError>;
+    fn integer(&self, column: &str) -> Result<i64, StorageError>;
+    fn optional_integer(&self, column: &str) -> Result<Option<i64>, StorageError>;
+    fn real(&self, column: &str) -> Result<f64, StorageError>;
+}
+
+macro_rules! impl_column_row {
+    ($row:ty) => {
+        impl ColumnRow for $row {
+            fn text(&self, column: &str) -> Result<String, StorageError> {
+                Ok(self.try_get(column)?)
+            }
+
+            fn optional_text(&self, column: &str) -> Result<Option<String>, StorageError> {
+                Ok(self.try_get(column)?)
+            }
+
+            fn integer(&self, column: &str) -> Result<i64, StorageError> {
+                Ok(self.try_get(column)?)
+            }
+
+            fn optional_integer(&self, column: &str) -> Result<Option<i64>, StorageError> {
+                Ok(self.try_get(column)?)
+            }
+
+            fn real(&self, column: &str) -> Result<f64, StorageError> {
+                Ok(self.try_get(column)?)
+            }
+        }
+    };
+}
+
+impl_column_row!(SqliteRow);
+impl_column_row!(PgRow);
+
+/// Converts a timestamp to its column value.
+pub(super) fn to_millis(time: DateTime<Utc>) -> i64 {
+    time.timestamp_millis()
+}
+
+/// Converts a decimal to its `_real` shadow column value.
+pub(super) fn to_real(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or_default()
+}
+
+/// Generates a unique hash for detecting duplicate opportunities.
+///
+/// An opportunity is unique based on: pair, buy_exchange, sell_exchange,
+/// profit_percent (rounded to 2 decimals), and a 5-minute time window.
+/// This prevents duplicate notifications for the same opportunity within 5 minutes.
+pub(super) fn generate_unique_hash(opp: &Opportunity) -> String {
+    // Round profit percent to 2 decimal places (e.g., 0.7234 -> 0.72)
+    let profit_rounded = (opp.profit_percent * Decimal::from(100))
+        .round_dp(2)
+        .to_string();
+
+    // Round detected_at to 5-minute window (e.g., 16:37 and 16:39 both become 16:35)
+    let minute = opp.detected_at.minute();
+    let window_minutes = (minute / 5) * 5;
+    let time_window = opp
+        .detected_at
+        .with_minute(window_minutes)
+        .unwrap()
+        .with_second(0)
+        .unwrap()
+        .with_nanosecond(0)
+        .unwrap();
+    let time_window_str = time_window.format("%Y-%m-%dT%H:%M").to_string();
+
+    let data = format!(
+        "{}|{}|{}|{}|{}",
+        opp.pair, opp.buy_exchange, opp.sell_exchange, profit_rounded, time_window_str
+    );
+
+    let mut hasher = Sha256::new();
+    hasher.update(data.as_bytes());
+    let hash = hasher.finalize();
+
+    // Use first 16 bytes for shorter hash
+    hex::encode(&hash[..16])
+}
+
+/// Serialises opportunity legs for the `legs` column.
+pub(super) fn legs_json(opp: &Opportunity) -> Result<String, StorageError> {
+    serde_json::to_string(&opp.legs)
+        .map_err(|e| StorageError::InvalidData(format!("Invalid legs: {}", e)))
+}
+
+/// Parses a timestamp column.
+pub(super) fn parse_time(row: &impl ColumnRow, column: &str) -> Result<DateTime<Utc>, StorageError> {
+    from_millis(column, row.integer(column)?)
+}
+
+/// Parses a nullable timestamp column.
+pub(super) fn parse_optional_time(
+    row: &impl ColumnRow,
+    column: &str,
+) -> Result<Option<DateTime<Utc>>, StorageError> {
+    row.optional_integer(column)?
+        .map(|m| from_millis(column, m))
+        .transpose()
+}
+
+/// Parses a decimal column from its exact TEXT value.
+pub(super) fn parse_decimal(row: &impl ColumnRow, column: &str) -> Result<Decimal, StorageError> {
+    Decimal::from_str(&row.text(column)?)
+        .map_err(|e| StorageError::InvalidData(format!("Invalid {}: {}", column, e)))
+}
+
+/// Parses an enum column via its FromStr implementation.
+pub(super) fn parse_enum<T: FromStr<Err = String>>(
+    row: &impl ColumnRow,
+    column: &str,
+) -> Result<T, StorageError> {
+    T::from_str(&row.text(column)?).map_err(StorageError::InvalidData)
+}
+
+/// Parses an opportunity from a database row.
+pub(super) fn parse_opportunity_row(row: &impl ColumnRow) -> Result<Opportunity, StorageError> {
+    let legs = serde_json::from_str(&row.text("legs")?)
+        .map_err(|e| StorageError::InvalidData(format!("Invalid legs: {}", e)))?;
+
+    Ok(Opportunity {
+        id: row.text("id")?,
+        opportunity_type: parse_enum(row, "type")?,
+        pair: row.text("pair")?,
+        buy_exchange: row.text("buy_exchange")?,
+        sell_exchange: row.text("sell_exchange")?,
+        buy_price: parse_decimal(row, "buy_price")?,
+        sell_price: parse_decimal(row, "sell_price")?,
+        quantity: parse_decimal(row, "quantity")?,
+        gross_profit: parse_decimal(row, "gross_profit")?,
+        net_profit: parse_decimal(row, "net_profit")?,
+        net_profit_after_rebalance: parse_decimal(row, "net_profit_after_rebalance")?,
+        profit_percent: parse_decimal(row, "profit_percent")?,
+        buy_fee: parse_decimal(row, "buy_fee")?,
+        sell_fee: parse_decimal(row, "sell_fee")?,
+        detected_at: parse_time(row, "detected_at")?,
+        expires_at: parse_time(row, "expires_at")?,
+        legs,
+    })
+}
+
+/// Parses an opportunity lifecycle from a database row.
+pub(super) fn parse_lifecycle_row(
+    row: &impl ColumnRow,
+) -> Result<OpportunityLifecycle, StorageError> {
+    Ok(OpportunityLifecycle {
+        opportunity_id: row.text("opportunity_id")?,
+        first_seen: parse_time(row, "first_seen")?,
+        last_seen: parse_time(row, "last_seen")?,
+        observations: row.integer("observations")? as u32,
+        peak_profit_percent: parse_decimal(row, "peak_profit_percent")?,
+        avg_profit_percent: parse_decimal(row, "avg_profit_percent")?,
+        last_profit_percent: parse_decimal(row, "last_profit_percent")?,
+        closed_at: parse_optional_time(row, "closed_at")?,
+        close_reason: match row.optional_text("close_reason")? {
+            Some(s) => Some(s.parse().map_err(StorageError::InvalidData)?),
+            None => None,
+        },
+    })
+}
+
+/// Parses an order from a database row.
+pub(super) fn parse_order_row(row: &impl ColumnRow) -> Result<Order, StorageError> {
+    Ok(Order {
+        id: row.text("id")?,
+        exchange: row.text("exchange")?,
+        pair: row.text("pair")?,
+        side: parse_enum(row, "side")?,
+        order_type: parse_enum(row, "type")?,
+        price: parse_decimal(row, "price")?,
+        quantity: parse_decimal(row, "quantity")?,
+        status: parse_enum(row, "status")?,
+        created_at: parse_time(row, "created_at")?.into(),
+        updated_at: parse_time(row, "updated_at")?.into(),
+    })
+}
+
+/// Parses a trade from a database row.
+pub(super) fn parse_trade_row(row: &impl ColumnRow) -> Result<Trade, StorageError> {
+    Ok(Trade {
+        id: row.text("id")?,
+        order_id: row.text("order_id")?,
+        exchange: row.text("exchange")?,
+        pair: row.text("pair")?,
+        side: parse_enum(row, "side")?,
+        price: parse_decimal(row, "price")?,
+        quantity: parse_decimal(row, "quantity")?,
+        fee: parse_decimal(row, "fee")?,
+        fee_currency: row.text("fee_currency")?,
+        timestamp: parse_time(row, "timestamp")?.into(),
+    })
+}
+
+/// Parses an execution leg from a database row.
+pub(super) fn parse_execution_leg_row(row: &impl ColumnRow) -> Result<ExecutionLeg, StorageError> {
+    Ok(ExecutionLeg {
+        exchange: row.text("exchange")?,
+        order_id: row.text("order_id")?,
+    })
+}
+
+/// Parses an execution from its row and separately loaded legs.
+pub(super) fn parse_execution_row(
+    row: &impl ColumnRow,
+    legs: Vec<ExecutionLeg>,
+) -> Result<Execution, StorageError> {
+    Ok(Execution {
+        id: row.text("id")?,
+        opportunity_id: row.text("opportunity_id")?,
+        pair: row.text("pair")?,
+        status: parse_enum(row, "status")?,
+        legs,
+        realized_pnl: parse_decimal(row, "realized_pnl")?,
+        fees: parse_decimal(row, "fees")?,
+        started_at: parse_time(row, "started_at")?,
+        finished_at: parse_optional_time(row, "finished_at")?,
+        error: row.optional_text("error")?,
+    })
+}
+
+/// Parses one group of an aggregate query.
+pub(super) fn parse_stats_row(row: &impl ColumnRow) -> Result<OpportunityStats, StorageError> {
+    Ok(OpportunityStats {
+        key: row.text("key")?,
+        count: row.integer("count")?,
+        avg_profit_percent: row.real("avg_profit_percent")?,
+        max_profit_percent: row.real("max_profit_percent")?,
+        total_net_profit: row.real("total_net_profit")?,
+    })
+}
+
+/// Parses an hourly rollup from a database row.
+pub(super) fn parse_rollup_row(row: &impl ColumnRow) -> Result<OpportunityRollup, StorageError> {
+    let count = row.integer("count")?;
+    Ok(OpportunityRollup {
+        hour: parse_time(row, "hour")?,
+        buy_exchange: row.text("buy_exchange")?,
+        sell_exchange: row.text("sell_exchange")?,
+        count,
+        avg_profit_percent: row.real("sum_profit_percent_real")? / count.max(1) as f64,
+        max_profit_percent: row.real("max_profit_percent_real")?,
+        total_net_profit: row.real("total_net_profit_real")?,
+    })
+}
+
+fn from_millis(column: &str, millis: i64) -> Result<DateTime<Utc>, StorageError> {
+    DateTime::from_timestamp_millis(millis)
+        .ok_or_else(|| StorageError::InvalidData(format!("Invalid {}: {}", column, millis)))
+}
@@ -0,0 +1,556 @@
+//! Conformance tests shared by every storage backend.
+//!
+//! Each case takes a freshly migrated, empty storage. Backends run the whole
+//! suite with `conformance_suite!`, passing a function that opens a harness.
+
+use crate::domain::execution::ExecutionStatus;
+use crate::domain::{
+    CloseReason, Execution, ExecutionLeg, Opportunity, OpportunityLeg,
+    OpportunityLifecycle, OpportunityType, Order, OrderSide, OrderStatus, OrderType, Trade,
+};
+use crate::config::{ArchiveConfig, ArchiveFormat, RetentionConfig};
+use crate::storage::{
+    GroupBy, OpportunityQuery, OpportunityWriter, RecordFilter, SortKey, SortOrder, Storage,
+    StorageError, apply_retention, export_opportunities,
+};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use rust_decimal::Decimal;
+use std::fs::File;
+use std::io::Read;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// TestHarness owns a storage under test and whatever backs it.
+pub(super) trait TestHarness {
+    /// Storage the cases run against.
+    fn storage(&self) -> &dyn Storage;
+
+    /// Releases the storage and its backing database.
+    async fn teardown(self);
+}
+
+/// Generates one test per conformance case.
+///
+/// `$open` is an async function returning an `impl TestHarness`. Attributes
+/// before it apply to every case, e.g. `#[ignore]` for a backend whose database
+/// has to be provided.
+macro_rules! conformance_suite {
+    ($(#[$attr:meta])* $open:path) => {
+        $crate::storage::conformance::conformance_suite!(
+            @cases [$(#[$attr])*] $open;
+            save_and_get_round_trip,
+            save_deduplicates_within_window,
+            query_filters_and_pages,
+            aggregate_groups,
+            triangular_legs_round_trip,
+            lifecycle_upsert,
+            order_upsert_and_filter,
+            trades_are_saved_once,
+            execution_links_opportunity_to_legs,
+            prune_rolls_up_and_keeps_executed,
+            retention_prunes_oldest_and_archives,
+            export_streams_matching_pages
+        );
+    };
+    (@cases $attrs:tt $open:path; $($case:ident),*) => {
+        $(
+            $crate::storage::conformance::conformance_suite!(@case $attrs $open; $case);
+        )*
+    };
+    (@case [$(#[$attr:meta])*] $open:path; $case:ident) => {
+        #[tokio::test]
+        $(#[$attr])*
+        async fn $case() {
+            use $crate::storage::conformance::TestHarness;
+
+            let harness = $open().await;
+            $crate::storage::conformance::$case(harness.storage()).await;
+            harness.teardown().await;
+        }
+    };
+}
+
+pub(crate) use conformance_suite;
+
+pub(super) fn sample_opportunity(id: &str) -> Opportunity {
+    // Storage keeps millisecond precision
+    let detected_at = DateTime::from_timestamp_millis(Utc::now().timestamp_millis()).unwrap();
+    Opportunity {
+        id: id.to_string(),
+        opportunity_type: OpportunityType::CrossExchange,
+        pair: "BTC/USDT".to_string(),
+        buy_exchange: "poloniex".to_string(),
+        sell_exchange: "gate".to_string(),
+        buy_price: Decimal::from(40000),
+        sell_price: Decimal::from(40100),
+        quantity: Decimal::new(5, 1),
+        gross_profit: Decimal::from(50),
+        net_profit: Decimal::new(1006, 2),
+        net_profit_after_rebalance: Decimal::new(806, 2),
+        profit_percent: Decimal::new(5, 4),
+        buy_fee: Decimal::new(1, 3),
+        sell_fee: Decimal::new(1, 3),
+        detected_at,
+        expires_at: detected_at + chrono::Duration::seconds(5),
+        legs: vec![],
+    }
+}
+
+pub(super) async fn save_and_get_round_trip(storage: &dyn Storage) {
+
+    let opp = sample_opportunity("opp-1");
+    assert!(storage.save(&opp).await.unwrap());
+
+    let loaded = storage.get_by_id("opp-1").await.unwrap().unwrap();
+    assert_eq!(loaded.net_profit, opp.net_profit);
+    assert_eq!(loaded.net_profit_after_rebalance, opp.net_profit_after_rebalance);
+    assert_eq!(loaded.detected_at, opp.detected_at);
+}
+
+pub(super) async fn save_deduplicates_within_window(storage: &dyn Storage) {
+
+    let first = sample_opportunity("opp-1");
+    let mut second = sample_opportunity("opp-2");
+    second.detected_at = first.detected_at;
+
+    assert!(storage.save(&first).await.unwrap());
+    assert!(!storage.save(&second).await.unwrap());
+    assert_eq!(storage.count().await.unwrap(), 1);
+}
+
+/// Saves opportunities detected one minute apart from `at(0)`, as (id, pair, route, profit %).
+pub(crate) async fn save_history(storage: &dyn Storage, rows: &[(&str, &str, (&str, &str), i64)]) {
+    for (i, (id, pair, (buy, sell), profit)) in rows.iter().enumerate() {
+        let mut opp = sample_opportunity(id);
+        opp.pair = pair.to_string();
+        opp.buy_exchange = buy.to_string();
+        opp.sell_exchange = sell.to_string();
+        opp.profit_percent = Decimal::new(*profit, 3);
+        opp.net_profit = Decimal::from(*profit);
+        opp.detected_at = at(60 * i as i64);
+        opp.expires_at = opp.detected_at + chrono::Duration::seconds(5);
+        assert!(storage.save(&opp).await.unwrap());
+    }
+}
+
+pub(super) async fn query_filters_and_pages(storage: &dyn Storage) {
+    save_history(
+        storage,
+        &[
+            ("a", "BTC/USDT", ("poloniex", "gate"), 1),
+            ("b", "BTC/USDT", ("gate", "poloniex"), 3),
+            ("c", "ETH/USDT", ("poloniex", "gate"), 2),
+            ("d", "BTC/USDT", ("poloniex", "gate"), 5),
+            ("e", "BTC/USDT", ("poloniex", "gate"), 4),
+        ],
+    )
+    .await;
+
+    let query = OpportunityQuery::new()
+        .with_pair("BTC/USDT")
+        .with_route("poloniex", "gate")
+        .with_min_profit_percent(Decimal::new(2, 3));
+    assert_eq!(storage.count_matching(&query).await.unwrap(), 2);
+
+    let page = storage.query(&query).await.unwrap();
+    let ids: Vec<_> = page.items.iter().map(|o| o.id.as_str()).collect();
+    assert_eq!(ids, ["e", "d"]);
+    assert!(page.next_cursor.is_none());
+
+    // Walk every opportunity by profit, two per page
+    let mut query = OpportunityQuery::new()
+        .with_sort(SortKey::ProfitPercent, SortOrder::Ascending)
+        .with_limit(2);
+    let mut ids = Vec::new();
+    loop {
+        let page = storage.query(&query).await.unwrap();
+        ids.extend(page.items.into_iter().map(|o| o.id));
+        match page.next_cursor {
+            Some(cursor) => query = query.with_cursor(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(ids, ["a", "c", "b", "e", "d"]);
+
+    let ranged = OpportunityQuery::new().with_time_range(Some(at(60)), Some(at(180)));
+    let page = storage.query(&ranged).await.unwrap();
+    let ids: Vec<_> = page.items.iter().map(|o| o.id.as_str()).collect();
+    assert_eq!(ids, ["c", "b"]);
+
+    let bad = OpportunityQuery::new().with_cursor("nonsense");
+    assert!(matches!(
+        storage.query(&bad).await,
+        Err(StorageError::InvalidData(_))
+    ));
+}
+
+pub(super) async fn aggregate_groups(storage: &dyn Storage) {
+    save_history(
+        storage,
+        &[
+            ("a", "BTC/USDT", ("poloniex", "gate"), 1),
+            ("b", "BTC/USDT", ("gate", "poloniex"), 3),
+            ("c", "ETH/USDT", ("poloniex", "gate"), 2),
+        ],
+    )
+    .await;
+
+    let by_pair = storage
+        .aggregate(&OpportunityQuery::new(), GroupBy::Pair)
+        .await
+        .unwrap();
+    assert_eq!(by_pair.len(), 2);
+    assert_eq!(by_pair[0].key, "BTC/USDT");
+    assert_eq!(by_pair[0].count, 2);
+    assert!((by_pair[0].avg_profit_percent - 0.002).abs() < 1e-12);
+    assert!((by_pair[0].max_profit_percent - 0.003).abs() < 1e-12);
+    assert!((by_pair[0].total_net_profit - 4.0).abs() < 1e-12);
+
+    let by_route = storage
+        .aggregate(&OpportunityQuery::new(), GroupBy::Route)
+        .await
+        .unwrap();
+    let keys: Vec<_> = by_route.iter().map(|s| (s.key.as_str(), s.count)).collect();
+    assert_eq!(keys, [("gate->poloniex", 1), ("poloniex->gate", 2)]);
+
+    let by_hour = storage
+        .aggregate(&OpportunityQuery::new(), GroupBy::Hour)
+        .await
+        .unwrap();
+    assert_eq!(by_hour.len(), 1);
+    assert_eq!(by_hour[0].key, "2023-11-14T22:00:00Z");
+    assert_eq!(by_hour[0].count, 3);
+}
+
+pub(super) async fn triangular_legs_round_trip(storage: &dyn Storage) {
+
+    let mut opp = sample_opportunity("tri-1");
+    opp.opportunity_type = OpportunityType::Triangular;
+    opp.pair = "USDT/BTC/ETH".to_string();
+    opp.sell_exchange = opp.buy_exchange.clone();
+    opp.legs = vec![
+        OpportunityLeg {
+            exchange: "poloniex".to_string(),
+            pair: "BTC/USDT".to_string(),
+            side: OrderSide::Buy,
+            price: Decimal::from(40000),
+            quantity: Decimal::new(2, 1),
+            fee: Decimal::new(1, 3),
+        },
+        OpportunityLeg {
+            exchange: "poloniex".to_string(),
+            pair: "ETH/USDT".to_string(),
+            side: OrderSide::Sell,
+            price: Decimal::from(2100),
+            quantity: Decimal::from(4),
+            fee: Decimal::new(1, 3),
+        },
+    ];
+    assert!(storage.save(&opp).await.unwrap());
+
+    let loaded = storage.get_by_id("tri-1").await.unwrap().unwrap();
+    assert_eq!(loaded.opportunity_type, OpportunityType::Triangular);
+    assert_eq!(loaded.legs, opp.legs);
+}
+
+pub(super) async fn lifecycle_upsert(storage: &dyn Storage) {
+
+    let opp = sample_opportunity("opp-1");
+    storage.save(&opp).await.unwrap();
+
+    let mut lifecycle = OpportunityLifecycle::open(&opp);
+    storage.save_lifecycle(&lifecycle).await.unwrap();
+
+    let mut later = opp.clone();
+    later.detected_at = opp.detected_at + chrono::Duration::seconds(2);
+    later.profit_percent = Decimal::new(7, 4);
+    lifecycle.observe(&later);
+    lifecycle.close(CloseReason::SpreadCollapsed, later.detected_at);
+    storage.save_lifecycle(&lifecycle).await.unwrap();
+
+    let loaded = storage.get_lifecycle("opp-1").await.unwrap().unwrap();
+    assert_eq!(loaded, lifecycle);
+    assert_eq!(loaded.observations, 2);
+    assert_eq!(loaded.peak_profit_percent, Decimal::new(7, 4));
+    assert_eq!(loaded.close_reason, Some(CloseReason::SpreadCollapsed));
+
+    assert!(storage.get_lifecycle("missing").await.unwrap().is_none());
+}
+
+pub(super) fn at(seconds: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+}
+
+pub(crate) fn sample_order(exchange: &str, id: &str, pair: &str, created: i64) -> Order {
+    Order {
+        id: id.to_string(),
+        exchange: exchange.to_string(),
+        pair: pair.to_string(),
+        side: OrderSide::Buy,
+        order_type: OrderType::Limit,
+        price: Decimal::from(40000),
+        quantity: Decimal::new(1, 1),
+        status: OrderStatus::Open,
+        created_at: at(created).into(),
+        updated_at: at(created).into(),
+    }
+}
+
+pub(super) async fn order_upsert_and_filter(storage: &dyn Storage) {
+
+    let mut order = sample_order("poloniex", "o-1", "BTC/USDT", 0);
+    storage.save_order(&order).await.unwrap();
+    storage.save_order(&sample_order("gate", "o-1", "BTC/USDT", 60)).await.unwrap();
+    storage.save_order(&sample_order("gate", "o-2", "ETH/USDT", 120)).await.unwrap();
+
+    order.status = OrderStatus::Filled;
+    order.updated_at = at(5).into();
+    storage.save_order(&order).await.unwrap();
+
+    let loaded = storage.get_order("poloniex", "o-1").await.unwrap().unwrap();
+    assert_eq!(loaded.status, OrderStatus::Filled);
+    assert_eq!(loaded.updated_at, order.updated_at);
+
+    let in_range = storage
+        .get_orders(&RecordFilter {
+            from: Some(at(30)),
+            to: Some(at(120)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(in_range.len(), 1);
+    assert_eq!(in_range[0].exchange, "gate");
+
+    let gate_btc = storage
+        .get_orders(&RecordFilter {
+            pair: Some("BTC/USDT".to_string()),
+            exchange: Some("gate".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(gate_btc.len(), 1);
+    assert_eq!(gate_btc[0].id, "o-1");
+}
+
+pub(super) async fn trades_are_saved_once(storage: &dyn Storage) {
+
+    let trade = Trade {
+        id: "t-1".to_string(),
+        order_id: "o-1".to_string(),
+        exchange: "poloniex".to_string(),
+        pair: "BTC/USDT".to_string(),
+        side: OrderSide::Sell,
+        price: Decimal::from(40100),
+        quantity: Decimal::new(1, 1),
+        fee: Decimal::new(401, 2),
+        fee_currency: "USDT".to_string(),
+        timestamp: at(10).into(),
+    };
+    assert!(storage.save_trade(&trade).await.unwrap());
+    assert!(!storage.save_trade(&trade).await.unwrap());
+
+    let trades = storage.get_trades(&RecordFilter::default()).await.unwrap();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].fee, trade.fee);
+    assert_eq!(trades[0].side, OrderSide::Sell);
+}
+
+pub(super) async fn execution_links_opportunity_to_legs(storage: &dyn Storage) {
+    storage.save(&sample_opportunity("opp-1")).await.unwrap();
+
+    let mut execution = Execution {
+        id: "exec-1".to_string(),
+        opportunity_id: "opp-1".to_string(),
+        pair: "BTC/USDT".to_string(),
+        status: ExecutionStatus::Pending,
+        legs: vec![ExecutionLeg {
+            exchange: "poloniex".to_string(),
+            order_id: "o-1".to_string(),
+        }],
+        realized_pnl: Decimal::ZERO,
+        fees: Decimal::ZERO,
+        started_at: at(0),
+        finished_at: None,
+        error: None,
+    };
+    storage.save_execution(&execution).await.unwrap();
+
+    execution.status = ExecutionStatus::Completed;
+    execution.legs.push(ExecutionLeg {
+        exchange: "gate".to_string(),
+        order_id: "o-2".to_string(),
+    });
+    execution.realized_pnl = Decimal::new(975, 2);
+    execution.fees = Decimal::new(81, 2);
+    execution.finished_at = Some(at(2));
+    storage.save_execution(&execution).await.unwrap();
+
+    let loaded = storage.get_execution("exec-1").await.unwrap().unwrap();
+    assert_eq!(loaded, execution);
+
+    let by_exchange = storage
+        .get_executions(&RecordFilter {
+            exchange: Some("gate".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(by_exchange.len(), 1);
+
+    let other = storage
+        .get_executions(&RecordFilter {
+            exchange: Some("binance".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(other.is_empty());
+}
+
+pub(super) async fn prune_rolls_up_and_keeps_executed(storage: &dyn Storage) {
+    save_history(
+        storage,
+        &[
+            ("a", "BTC/USDT", ("poloniex", "gate"), 1),
+            ("b", "ETH/USDT", ("poloniex", "gate"), 3),
+            ("c", "BTC/USDT", ("gate", "poloniex"), 2),
+            ("d", "BTC/USDT", ("poloniex", "gate"), 4),
+        ],
+    )
+    .await;
+    storage
+        .save_lifecycle(&OpportunityLifecycle::open(
+            &storage.get_by_id("a").await.unwrap().unwrap(),
+        ))
+        .await
+        .unwrap();
+    storage
+        .save_execution(&Execution {
+            id: "exec-1".to_string(),
+            opportunity_id: "c".to_string(),
+            pair: "BTC/USDT".to_string(),
+            status: ExecutionStatus::Completed,
+            legs: vec![],
+            realized_pnl: Decimal::ZERO,
+            fees: Decimal::ZERO,
+            started_at: at(120),
+            finished_at: None,
+            error: None,
+        })
+        .await
+        .unwrap();
+
+    // Executed opportunities are neither candidates nor deleted
+    let prunable = storage.prunable(at(180), 10).await.unwrap();
+    let ids: Vec<&str> = prunable.iter().map(|o| o.id.as_str()).collect();
+    assert_eq!(ids, ["a", "b"]);
+
+    let ids = ["a".to_string(), "b".to_string(), "c".to_string()];
+    assert_eq!(storage.prune(&ids, true).await.unwrap(), 2);
+    assert_eq!(storage.count().await.unwrap(), 2);
+    assert!(storage.get_by_id("c").await.unwrap().is_some());
+    assert!(storage.get_lifecycle("a").await.unwrap().is_none());
+
+    // A later prune of the same hour and route merges into the existing rollup
+    assert_eq!(storage.prune(&["d".to_string()], true).await.unwrap(), 1);
+
+    let rollups = storage.get_rollups().await.unwrap();
+    assert_eq!(rollups.len(), 1);
+    let rollup = &rollups[0];
+    assert_eq!(rollup.hour, DateTime::from_timestamp(1_699_999_200, 0).unwrap());
+    assert_eq!(
+        (rollup.buy_exchange.as_str(), rollup.sell_exchange.as_str()),
+        ("poloniex", "gate")
+    );
+    assert_eq!(rollup.count, 3);
+    assert!((rollup.avg_profit_percent - 0.008 / 3.0).abs() < 1e-9);
+    assert!((rollup.max_profit_percent - 0.004).abs() < 1e-9);
+    assert!((rollup.total_net_profit - 8.0).abs() < 1e-9);
+
+    storage.vacuum().await.unwrap();
+}
+
+pub(super) async fn retention_prunes_oldest_and_archives(storage: &dyn Storage) {
+    save_history(
+        storage,
+        &[
+            ("a", "BTC/USDT", ("poloniex", "gate"), 1),
+            ("b", "BTC/USDT", ("poloniex", "gate"), 2),
+            ("c", "BTC/USDT", ("poloniex", "gate"), 3),
+            ("d", "BTC/USDT", ("poloniex", "gate"), 4),
+        ],
+    )
+    .await;
+
+    let dir = TempDir::new().unwrap();
+    let config = RetentionConfig {
+        interval: Duration::ZERO,
+        max_age: Duration::from_secs(24 * 3600),
+        max_rows: Some(2),
+        rollup: false,
+        archive: Some(ArchiveConfig {
+            dir: dir.path().to_string_lossy().to_string(),
+            format: ArchiveFormat::Jsonl,
+        }),
+        vacuum_interval: Duration::ZERO,
+    };
+
+    // All rows are younger than max_age, so max_rows decides
+    let report = apply_retention(storage, &config, at(3600)).await.unwrap();
+    assert_eq!(report.cutoff, Some(at(120)));
+    assert_eq!(report.pruned, 2);
+    assert!(storage.get_by_id("a").await.unwrap().is_none());
+    assert!(storage.get_by_id("c").await.unwrap().is_some());
+    assert!(storage.get_rollups().await.unwrap().is_empty());
+
+    let mut archived = String::new();
+    GzDecoder::new(File::open(report.archive.unwrap()).unwrap())
+        .read_to_string(&mut archived)
+        .unwrap();
+    let ids: Vec<String> = archived
+        .lines()
+        .map(|line| serde_json::from_str::<Opportunity>(line).unwrap().id)
+        .collect();
+    assert_eq!(ids, ["a", "b"]);
+
+    // Nothing is left to prune, so no archive is written
+    let report = apply_retention(storage, &config, at(3600)).await.unwrap();
+    assert_eq!(report.pruned, 0);
+    assert!(report.archive.is_none());
+}
+
+pub(super) async fn export_streams_matching_pages(storage: &dyn Storage) {
+    save_history(
+        storage,
+        &[
+            ("a", "BTC/USDT", ("poloniex", "gate"), 1),
+            ("b", "BTC/USDT", ("gate", "poloniex"), 2),
+            ("c", "BTC/USDT", ("poloniex", "gate"), 3),
+            ("d", "ETH/USDT", ("poloniex", "gate"), 4),
+            ("e", "BTC/USDT", ("poloniex", "gate"), 5),
+        ],
+    )
+    .await;
+
+    // Pages of two, so the export spans several queries
+    let query = OpportunityQuery::new()
+        .with_pair("BTC/USDT")
+        .with_route("poloniex", "gate")
+        .with_sort(SortKey::DetectedAt, SortOrder::Ascending)
+        .with_limit(2);
+
+    let mut writer = OpportunityWriter::new(Vec::new(), ArchiveFormat::Csv).unwrap();
+    let exported = export_opportunities(storage, &query, &mut writer).await.unwrap();
+    assert_eq!(exported, 3);
+
+    let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("id,type,pair,buy_exchange,sell_exchange,"));
+    assert!(lines[1].starts_with("a,cross_exchange,BTC/USDT,poloniex,gate,40000,"));
+    assert!(lines[3].starts_with("e,"));
+    assert!(lines[3].ends_with(",[]"));
+}
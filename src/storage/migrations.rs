@@ -0,0 +1,583 @@
+//! Versioned schema migrations for SQLite storage.
+//!
+//! Each migration runs in its own transaction and is recorded in the
+//! `schema_version` table. Migrations up to version 5 are idempotent so that
+//! databases created before versioning existed are brought up to date from
+//! version zero.
+
+use chrono::Utc;
+use sqlx::{Connection, Pool, Row, Sqlite, SqliteConnection};
+use tracing::info;
+
+use crate::storage::StorageError;
+
+/// One schema change.
+enum Step {
+    /// Executes a statement.
+    Sql(&'static str),
+    /// Adds a column unless it exists, then runs `backfill` if it was added.
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+        backfill: Option<&'static str>,
+    },
+}
+
+/// A numbered set of steps applied atomically.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    steps: &'static [Step],
+}
+
+/// All migrations, in version order. Never edit a released migration; add a new one.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create opportunities",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS opportunities (
+                    id TEXT PRIMARY KEY,
+                    unique_hash TEXT NOT NULL UNIQUE,
+                    type TEXT NOT NULL,
+                    pair TEXT NOT NULL,
+                    buy_exchange TEXT NOT NULL,
+                    sell_exchange TEXT NOT NULL,
+                    buy_price TEXT NOT NULL,
+                    sell_price TEXT NOT NULL,
+                    quantity TEXT NOT NULL,
+                    gross_profit TEXT NOT NULL,
+                    net_profit TEXT NOT NULL,
+                    profit_percent TEXT NOT NULL,
+                    buy_fee TEXT NOT NULL,
+                    sell_fee TEXT NOT NULL,
+                    detected_at TEXT NOT NULL,
+                    expires_at TEXT NOT NULL,
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_opportunities_pair ON opportunities(pair)"),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_opportunities_detected_at ON opportunities(detected_at)",
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_opportunities_exchanges ON opportunities(buy_exchange, sell_exchange)",
+            ),
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "add net profit after rebalance",
+        steps: &[Step::AddColumn {
+            table: "opportunities",
+            column: "net_profit_after_rebalance",
+            definition: "TEXT NOT NULL DEFAULT '0'",
+            // Rows stored before rebalancing costs were tracked keep their plain net profit
+            backfill: Some("UPDATE opportunities SET net_profit_after_rebalance = net_profit"),
+        }],
+    },
+    Migration {
+        version: 3,
+        description: "add opportunity legs",
+        steps: &[Step::AddColumn {
+            table: "opportunities",
+            column: "legs",
+            definition: "TEXT NOT NULL DEFAULT '[]'",
+            backfill: None,
+        }],
+    },
+    Migration {
+        version: 4,
+        description: "create opportunity lifecycles",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS opportunity_lifecycles (
+                    opportunity_id TEXT PRIMARY KEY REFERENCES opportunities(id),
+                    first_seen TEXT NOT NULL,
+                    last_seen TEXT NOT NULL,
+                    observations INTEGER NOT NULL,
+                    peak_profit_percent TEXT NOT NULL,
+                    avg_profit_percent TEXT NOT NULL,
+                    last_profit_percent TEXT NOT NULL,
+                    closed_at TEXT,
+                    close_reason TEXT
+                )
+                "#,
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_opportunity_lifecycles_first_seen ON opportunity_lifecycles(first_seen)",
+            ),
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "create orders, trades and executions",
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS orders (
+                    exchange TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    pair TEXT NOT NULL,
+                    side TEXT NOT NULL,
+                    type TEXT NOT NULL,
+                    price TEXT NOT NULL,
+                    quantity TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    PRIMARY KEY (exchange, id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_orders_created_at ON orders(created_at)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_orders_pair ON orders(pair)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS trades (
+                    exchange TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    order_id TEXT NOT NULL,
+                    pair TEXT NOT NULL,
+                    side TEXT NOT NULL,
+                    price TEXT NOT NULL,
+                    quantity TEXT NOT NULL,
+                    fee TEXT NOT NULL,
+                    fee_currency TEXT NOT NULL,
+                    timestamp TEXT NOT NULL,
+                    PRIMARY KEY (exchange, id)
+                )
+                "#,
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_trades_timestamp ON trades(timestamp)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_trades_order ON trades(exchange, order_id)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS executions (
+                    id TEXT PRIMARY KEY,
+                    opportunity_id TEXT NOT NULL REFERENCES opportunities(id),
+                    pair TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    realized_pnl TEXT NOT NULL,
+                    fees TEXT NOT NULL,
+                    started_at TEXT NOT NULL,
+                    finished_at TEXT,
+                    error TEXT
+                )
+                "#,
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_executions_started_at ON executions(started_at)",
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_executions_opportunity ON executions(opportunity_id)",
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS execution_legs (
+                    execution_id TEXT NOT NULL REFERENCES executions(id),
+                    leg_index INTEGER NOT NULL,
+                    exchange TEXT NOT NULL,
+                    order_id TEXT NOT NULL,
+                    PRIMARY KEY (execution_id, leg_index)
+                )
+                "#,
+            ),
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "store timestamps as epoch millis with numeric shadow columns",
+        // Timestamps become INTEGER milliseconds since the Unix epoch. Decimals keep
+        // their exact TEXT value, and a `_real` REAL shadow is added for SQL arithmetic.
+        // SQLite cannot change column types in place, so each table is rebuilt.
+        steps: &[
+            Step::Sql(
+                r#"
+                CREATE TABLE opportunities_new (
+                    id TEXT PRIMARY KEY,
+                    unique_hash TEXT NOT NULL UNIQUE,
+                    type TEXT NOT NULL,
+                    pair TEXT NOT NULL,
+                    buy_exchange TEXT NOT NULL,
+                    sell_exchange TEXT NOT NULL,
+                    buy_price TEXT NOT NULL,
+                    buy_price_real REAL NOT NULL,
+                    sell_price TEXT NOT NULL,
+                    sell_price_real REAL NOT NULL,
+                    quantity TEXT NOT NULL,
+                    quantity_real REAL NOT NULL,
+                    gross_profit TEXT NOT NULL,
+                    gross_profit_real REAL NOT NULL,
+                    net_profit TEXT NOT NULL,
+                    net_profit_real REAL NOT NULL,
+                    net_profit_after_rebalance TEXT NOT NULL,
+                    net_profit_after_rebalance_real REAL NOT NULL,
+                    profit_percent TEXT NOT NULL,
+                    profit_percent_real REAL NOT NULL,
+                    buy_fee TEXT NOT NULL,
+                    buy_fee_real REAL NOT NULL,
+                    sell_fee TEXT NOT NULL,
+                    sell_fee_real REAL NOT NULL,
+                    detected_at INTEGER NOT NULL,
+                    expires_at INTEGER NOT NULL,
+                    legs TEXT NOT NULL DEFAULT '[]',
+                    created_at INTEGER DEFAULT (CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER))
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                INSERT INTO opportunities_new SELECT
+                    id, unique_hash, type, pair, buy_exchange, sell_exchange,
+                    buy_price, CAST(buy_price AS REAL),
+                    sell_price, CAST(sell_price AS REAL),
+                    quantity, CAST(quantity AS REAL),
+                    gross_profit, CAST(gross_profit AS REAL),
+                    net_profit, CAST(net_profit AS REAL),
+                    net_profit_after_rebalance, CAST(net_profit_after_rebalance AS REAL),
+                    profit_percent, CAST(profit_percent AS REAL),
+                    buy_fee, CAST(buy_fee AS REAL),
+                    sell_fee, CAST(sell_fee AS REAL),
+                    CAST(ROUND((julianday(detected_at) - 2440587.5) * 86400000) AS INTEGER),
+                    CAST(ROUND((julianday(expires_at) - 2440587.5) * 86400000) AS INTEGER),
+                    legs,
+                    CAST(ROUND((julianday(created_at) - 2440587.5) * 86400000) AS INTEGER)
+                FROM opportunities
+                "#,
+            ),
+            Step::Sql("DROP TABLE opportunities"),
+            Step::Sql("ALTER TABLE opportunities_new RENAME TO opportunities"),
+            Step::Sql("CREATE INDEX idx_opportunities_pair ON opportunities(pair)"),
+            Step::Sql("CREATE INDEX idx_opportunities_detected_at ON opportunities(detected_at)"),
+            Step::Sql(
+                "CREATE INDEX idx_opportunities_exchanges ON opportunities(buy_exchange, sell_exchange)",
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE opportunity_lifecycles_new (
+                    opportunity_id TEXT PRIMARY KEY REFERENCES opportunities(id),
+                    first_seen INTEGER NOT NULL,
+                    last_seen INTEGER NOT NULL,
+                    observations INTEGER NOT NULL,
+                    peak_profit_percent TEXT NOT NULL,
+                    peak_profit_percent_real REAL NOT NULL,
+                    avg_profit_percent TEXT NOT NULL,
+                    avg_profit_percent_real REAL NOT NULL,
+                    last_profit_percent TEXT NOT NULL,
+                    last_profit_percent_real REAL NOT NULL,
+                    closed_at INTEGER,
+                    close_reason TEXT
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                INSERT INTO opportunity_lifecycles_new SELECT
+                    opportunity_id,
+                    CAST(ROUND((julianday(first_seen) - 2440587.5) * 86400000) AS INTEGER),
+                    CAST(ROUND((julianday(last_seen) - 2440587.5) * 86400000) AS INTEGER),
+                    observations,
+                    peak_profit_percent, CAST(peak_profit_percent AS REAL),
+                    avg_profit_percent, CAST(avg_profit_percent AS REAL),
+                    last_profit_percent, CAST(last_profit_percent AS REAL),
+                    CAST(ROUND((julianday(closed_at) - 2440587.5) * 86400000) AS INTEGER),
+                    close_reason
+                FROM opportunity_lifecycles
+                "#,
+            ),
+            Step::Sql("DROP TABLE opportunity_lifecycles"),
+            Step::Sql("ALTER TABLE opportunity_lifecycles_new RENAME TO opportunity_lifecycles"),
+            Step::Sql(
+                "CREATE INDEX idx_opportunity_lifecycles_first_seen ON opportunity_lifecycles(first_seen)",
+            ),
+            Step::Sql(
+                r#"
+                CREATE TABLE orders_new (
+                    exchange TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    pair TEXT NOT NULL,
+                    side TEXT NOT NULL,
+                    type TEXT NOT NULL,
+                    price TEXT NOT NULL,
+                    price_real REAL NOT NULL,
+                    quantity TEXT NOT NULL,
+                    quantity_real REAL NOT NULL,
+                    status TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    updated_at INTEGER NOT NULL,
+                    PRIMARY KEY (exchange, id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                INSERT INTO orders_new SELECT
+                    exchange, id, pair, side, type,
+                    price, CAST(price AS REAL),
+                    quantity, CAST(quantity AS REAL),
+                    status,
+                    CAST(ROUND((julianday(created_at) - 2440587.5) * 86400000) AS INTEGER),
+                    CAST(ROUND((julianday(updated_at) - 2440587.5) * 86400000) AS INTEGER)
+                FROM orders
+                "#,
+            ),
+            Step::Sql("DROP TABLE orders"),
+            Step::Sql("ALTER TABLE orders_new RENAME TO orders"),
+            Step::Sql("CREATE INDEX idx_orders_created_at ON orders(created_at)"),
+            Step::Sql("CREATE INDEX idx_orders_pair ON orders(pair)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE trades_new (
+                    exchange TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    order_id TEXT NOT NULL,
+                    pair TEXT NOT NULL,
+                    side TEXT NOT NULL,
+                    price TEXT NOT NULL,
+                    price_real REAL NOT NULL,
+                    quantity TEXT NOT NULL,
+                    quantity_real REAL NOT NULL,
+                    fee TEXT NOT NULL,
+                    fee_real REAL NOT NULL,
+                    fee_currency TEXT NOT NULL,
+                    timestamp INTEGER NOT NULL,
+                    PRIMARY KEY (exchange, id)
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                INSERT INTO trades_new SELECT
+                    exchange, id, order_id, pair, side,
+                    price, CAST(price AS REAL),
+                    quantity, CAST(quantity AS REAL),
+                    fee, CAST(fee AS REAL),
+                    fee_currency,
+                    CAST(ROUND((julianday(timestamp) - 2440587.5) * 86400000) AS INTEGER)
+                FROM trades
+                "#,
+            ),
+            Step::Sql("DROP TABLE trades"),
+            Step::Sql("ALTER TABLE trades_new RENAME TO trades"),
+            Step::Sql("CREATE INDEX idx_trades_timestamp ON trades(timestamp)"),
+            Step::Sql("CREATE INDEX idx_trades_order ON trades(exchange, order_id)"),
+            Step::Sql(
+                r#"
+                CREATE TABLE executions_new (
+                    id TEXT PRIMARY KEY,
+                    opportunity_id TEXT NOT NULL REFERENCES opportunities(id),
+                    pair TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    realized_pnl TEXT NOT NULL,
+                    realized_pnl_real REAL NOT NULL,
+                    fees TEXT NOT NULL,
+                    fees_real REAL NOT NULL,
+                    started_at INTEGER NOT NULL,
+                    finished_at INTEGER,
+                    error TEXT
+                )
+                "#,
+            ),
+            Step::Sql(
+                r#"
+                INSERT INTO executions_new SELECT
+                    id, opportunity_id, pair, status,
+                    realized_pnl, CAST(realized_pnl AS REAL),
+                    fees, CAST(fees AS REAL),
+                    CAST(ROUND((julianday(started_at) - 2440587.5) * 86400000) AS INTEGER),
+                    CAST(ROUND((julianday(finished_at) - 2440587.5) * 86400000) AS INTEGER),
+                    error
+                FROM executions
+                "#,
+            ),
+            Step::Sql("DROP TABLE executions"),
+            Step::Sql("ALTER TABLE executions_new RENAME TO executions"),
+            Step::Sql("CREATE INDEX idx_executions_started_at ON executions(started_at)"),
+            Step::Sql("CREATE INDEX idx_executions_opportunity ON executions(opportunity_id)"),
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "index opportunity profit",
+        steps: &[Step::Sql(
+            "CREATE INDEX idx_opportunities_profit ON opportunities(profit_percent_real, id)",
+        )],
+    },
+    Migration {
+        version: 8,
+        description: "create opportunity rollups",
+        steps: &[Step::Sql(
+            r#"
+            CREATE TABLE opportunity_rollups (
+                hour INTEGER NOT NULL,
+                buy_exchange TEXT NOT NULL,
+                sell_exchange TEXT NOT NULL,
+                count INTEGER NOT NULL,
+                sum_profit_percent_real REAL NOT NULL,
+                max_profit_percent_real REAL NOT NULL,
+                total_net_profit_real REAL NOT NULL,
+                PRIMARY KEY (hour, buy_exchange, sell_exchange)
+            )
+            "#,
+        )],
+    },
+];
+
+/// Returns the schema version this binary writes.
+pub(super) fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or_default()
+}
+
+/// Returns the schema version recorded in the database (zero if none).
+pub(super) async fn current_version(pool: &Pool<Sqlite>) -> Result<i64, StorageError> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.try_get("version")?)
+}
+
+/// Checks, without writing, that the database is at the latest schema version.
+pub(super) async fn check_version(pool: &Pool<Sqlite>) -> Result<(), StorageError> {
+    let versioned =
+        sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'")
+            .fetch_optional(pool)
+            .await?
+            .is_some();
+    let found = if versioned {
+        sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version")
+            .fetch_one(pool)
+            .await?
+            .try_get("version")?
+    } else {
+        0
+    };
+    super::check_schema_version(found, latest_version())
+}
+
+/// Applies every pending migration. Fails without changes if the database
+/// was written by a newer binary.
+///
+/// Migrations run on one connection with foreign keys disabled, so that tables
+/// referenced by others can be rebuilt; references are checked before each commit.
+pub(super) async fn run(pool: &Pool<Sqlite>) -> Result<(), StorageError> {
+    let current = current_version(pool).await?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(StorageError::SchemaTooNew {
+            found: current,
+            supported: latest,
+        });
+    }
+
+    let mut conn = pool.acquire().await?;
+
+    // Has no effect inside a transaction, so it is set before each one begins
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+
+    let result = apply_pending(&mut conn, current).await;
+
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+
+    result
+}
+
+/// Applies migrations newer than `current`, each in its own transaction.
+async fn apply_pending(conn: &mut SqliteConnection, current: i64) -> Result<(), StorageError> {
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let mut tx = conn.begin().await?;
+
+        for step in migration.steps {
+            apply(&mut tx, step).await?;
+        }
+
+        let violations = sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(&mut *tx)
+            .await?;
+        if !violations.is_empty() {
+            return Err(StorageError::InvalidData(format!(
+                "Migration {} left {} broken references",
+                migration.version,
+                violations.len()
+            )));
+        }
+
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        info!(
+            version = migration.version,
+            description = migration.description,
+            "Applied schema migration"
+        );
+    }
+
+    Ok(())
+}
+
+/// Applies a single step inside the migration's transaction.
+async fn apply(conn: &mut SqliteConnection, step: &Step) -> Result<(), StorageError> {
+    match step {
+        Step::Sql(sql) => {
+            sqlx::query(sql).execute(&mut *conn).await?;
+        }
+        Step::AddColumn {
+            table,
+            column,
+            definition,
+            backfill,
+        } => {
+            let rows = sqlx::query(&format!("PRAGMA table_info({})", table))
+                .fetch_all(&mut *conn)
+                .await?;
+
+            for row in &rows {
+                let name: String = row.try_get("name")?;
+                if name == *column {
+                    return Ok(());
+                }
+            }
+
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))
+            .execute(&mut *conn)
+            .await?;
+
+            if let Some(backfill) = backfill {
+                sqlx::query(backfill).execute(&mut *conn).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,11 +1,133 @@
 //! Storage interfaces and implementations for persisting arbitrage data.
 
+mod archive;
+mod columns;
+#[cfg(test)]
+pub(crate) mod conformance;
+mod migrations;
+mod postgres;
+mod query;
+mod retention;
 mod sqlite;
+mod trading;
 
+pub use archive::{OpportunityWriter, export_opportunities};
+
+pub use postgres::{PostgresStorage, PostgresStorageConfig};
+pub use query::{GroupBy, OpportunityPage, OpportunityQuery, OpportunityStats, SortKey, SortOrder};
+pub use retention::apply_retention;
 pub use sqlite::{SqliteStorage, SqliteStorageConfig};
 
-use crate::domain::Opportunity;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::{StorageBackend, StorageConfig};
+use crate::domain::{Execution, Opportunity, OpportunityLifecycle, Order, Trade};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tracing::info;
+
+/// Directory holding SQLite database files.
+const DATA_DIR: &str = "data";
+
+/// Default size of the connection pool.
+const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+
+/// Storage combines every storage interface; each backend implements all of them.
+pub trait Storage:
+    OpportunityStorage + OrderStorage + TradeStorage + ExecutionStorage + RetentionStorage
+{
+}
+
+impl<T> Storage for T where
+    T: OpportunityStorage + OrderStorage + TradeStorage + ExecutionStorage + RetentionStorage
+{
+}
+
+/// Opens the backend selected by the storage configuration.
+///
+/// SQLite files are created under the `data/` directory.
+pub async fn connect(config: &StorageConfig) -> Result<Arc<dyn Storage>, StorageError> {
+    let max_connections = config.max_connections.unwrap_or(DEFAULT_MAX_CONNECTIONS);
+
+    match config.backend {
+        StorageBackend::Sqlite => {
+            let data_dir = Path::new(DATA_DIR);
+            if !data_dir.exists() {
+                std::fs::create_dir_all(data_dir)?;
+            }
+
+            let path = sqlite_path(config);
+
+            let storage = SqliteStorage::new(SqliteStorageConfig {
+                path: path.clone(),
+                max_connections,
+            })
+            .await?;
+
+            info!(path = %path, "Storage initialized");
+            Ok(Arc::new(storage))
+        }
+        StorageBackend::Postgres => {
+            let url = config.url.clone().unwrap_or_default();
+            let storage = PostgresStorage::new(PostgresStorageConfig {
+                url,
+                max_connections,
+            })
+            .await?;
+
+            info!("Storage initialized");
+            Ok(Arc::new(storage))
+        }
+    }
+}
+
+/// Opens the existing database of the storage configuration for reading.
+///
+/// Nothing is created or migrated: the database must exist and be at the
+/// schema version this binary writes.
+pub async fn open_read_only(config: &StorageConfig) -> Result<Arc<dyn Storage>, StorageError> {
+    let max_connections = config.max_connections.unwrap_or(DEFAULT_MAX_CONNECTIONS);
+
+    match config.backend {
+        StorageBackend::Sqlite => {
+            let storage = SqliteStorage::open_read_only(SqliteStorageConfig {
+                path: sqlite_path(config),
+                max_connections,
+            })
+            .await?;
+            Ok(Arc::new(storage))
+        }
+        StorageBackend::Postgres => {
+            let storage = PostgresStorage::open_read_only(PostgresStorageConfig {
+                url: config.url.clone().unwrap_or_default(),
+                max_connections,
+            })
+            .await?;
+            Ok(Arc::new(storage))
+        }
+    }
+}
+
+/// Returns the path of the SQLite file under the `data/` directory.
+fn sqlite_path(config: &StorageConfig) -> String {
+    let filename = config.path.as_deref().unwrap_or("opportunities.db");
+    Path::new(DATA_DIR).join(filename).to_string_lossy().to_string()
+}
+
+/// Fails unless a database at schema version `found` is at `expected`.
+fn check_schema_version(found: i64, expected: i64) -> Result<(), StorageError> {
+    if found > expected {
+        return Err(StorageError::SchemaTooNew {
+            found,
+            supported: expected,
+        });
+    }
+    if found < expected {
+        return Err(StorageError::SchemaOutdated { found, expected });
+    }
+    Ok(())
+}
 
 /// OpportunityStorage defines the interface for storing arbitrage opportunities.
 #[async_trait]
@@ -17,11 +139,28 @@ pub trait OpportunityStorage: Send + Sync {
     /// GetByID retrieves an opportunity by its ID.
     async fn get_by_id(&self, id: &str) -> Result<Option<Opportunity>, StorageError>;
 
-    /// GetAll retrieves all stored opportunities.
-    async fn get_all(&self) -> Result<Vec<Opportunity>, StorageError>;
+    /// Query retrieves one page of opportunities matching the query.
+    async fn query(&self, query: &OpportunityQuery) -> Result<OpportunityPage, StorageError>;
+
+    /// CountMatching returns the number of opportunities matching the query's filters.
+    async fn count_matching(&self, query: &OpportunityQuery) -> Result<i64, StorageError>;
+
+    /// Aggregate computes statistics of matching opportunities per group,
+    /// ordered by group key. Sorting and pagination fields are ignored.
+    async fn aggregate(
+        &self,
+        query: &OpportunityQuery,
+        group_by: GroupBy,
+    ) -> Result<Vec<OpportunityStats>, StorageError>;
 
-    /// GetByPair retrieves opportunities for a specific trading pair.
-    async fn get_by_pair(&self, pair: &str) -> Result<Vec<Opportunity>, StorageError>;
+    /// SaveLifecycle inserts or updates the lifecycle of an opportunity.
+    async fn save_lifecycle(&self, lifecycle: &OpportunityLifecycle) -> Result<(), StorageError>;
+
+    /// GetLifecycle retrieves the lifecycle opened by an opportunity.
+    async fn get_lifecycle(
+        &self,
+        opportunity_id: &str,
+    ) -> Result<Option<OpportunityLifecycle>, StorageError>;
 
     /// Count returns the total number of stored opportunities.
     async fn count(&self) -> Result<i64, StorageError>;
@@ -30,18 +169,118 @@ pub trait OpportunityStorage: Send + Sync {
     async fn close(&self) -> Result<(), StorageError>;
 }
 
+/// RecordFilter narrows order, trade and execution queries.
+/// Unset fields match everything; the time range is half-open `[from, to)`.
+#[derive(Debug, Clone, Default)]
+pub struct RecordFilter {
+    /// Earliest timestamp to include.
+    pub from: Option<DateTime<Utc>>,
+    /// Timestamp to stop before.
+    pub to: Option<DateTime<Utc>>,
+    /// Trading pair (for executions, the opportunity pair).
+    pub pair: Option<String>,
+    /// Exchange name (for executions, any leg's exchange).
+    pub exchange: Option<String>,
+}
+
+/// OrderStorage defines the interface for storing orders.
+#[async_trait]
+pub trait OrderStorage: Send + Sync {
+    /// SaveOrder inserts an order or updates its status and timestamps.
+    async fn save_order(&self, order: &Order) -> Result<(), StorageError>;
+
+    /// GetOrder retrieves an order by exchange and exchange-assigned ID.
+    async fn get_order(&self, exchange: &str, id: &str) -> Result<Option<Order>, StorageError>;
+
+    /// GetOrders retrieves orders created within the filter, oldest first.
+    async fn get_orders(&self, filter: &RecordFilter) -> Result<Vec<Order>, StorageError>;
+}
+
+/// TradeStorage defines the interface for storing trade fills.
+#[async_trait]
+pub trait TradeStorage: Send + Sync {
+    /// SaveTrade persists a fill.
+    /// Returns true if the trade was saved (new), false if it already exists.
+    async fn save_trade(&self, trade: &Trade) -> Result<bool, StorageError>;
+
+    /// GetTrades retrieves fills executed within the filter, oldest first.
+    async fn get_trades(&self, filter: &RecordFilter) -> Result<Vec<Trade>, StorageError>;
+}
+
+/// ExecutionStorage defines the interface for storing opportunity executions.
+#[async_trait]
+pub trait ExecutionStorage: Send + Sync {
+    /// SaveExecution inserts an execution or replaces its outcome and legs.
+    async fn save_execution(&self, execution: &Execution) -> Result<(), StorageError>;
+
+    /// GetExecution retrieves an execution by its ID.
+    async fn get_execution(&self, id: &str) -> Result<Option<Execution>, StorageError>;
+
+    /// GetExecutions retrieves executions started within the filter, oldest first.
+    async fn get_executions(&self, filter: &RecordFilter) -> Result<Vec<Execution>, StorageError>;
+}
+
+/// OpportunityRollup aggregates the pruned opportunities of one route in one hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpportunityRollup {
+    /// Start of the hour (UTC).
+    pub hour: DateTime<Utc>,
+    /// Exchange bought on.
+    pub buy_exchange: String,
+    /// Exchange sold on.
+    pub sell_exchange: String,
+    /// Number of opportunities.
+    pub count: i64,
+    /// Mean profit percentage.
+    pub avg_profit_percent: f64,
+    /// Highest profit percentage.
+    pub max_profit_percent: f64,
+    /// Sum of net profit in quote currency.
+    pub total_net_profit: f64,
+}
+
+/// RetentionStorage defines the interface for pruning old opportunities.
+#[async_trait]
+pub trait RetentionStorage: Send + Sync {
+    /// KeepNewestCutoff returns the detection time of the oldest of the `keep`
+    /// newest opportunities, or None if no more than `keep` are stored.
+    async fn keep_newest_cutoff(&self, keep: u64) -> Result<Option<DateTime<Utc>>, StorageError>;
+
+    /// Prunable retrieves up to `limit` opportunities detected before `before`
+    /// that no execution references, oldest first.
+    async fn prunable(
+        &self,
+        before: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<Opportunity>, StorageError>;
+
+    /// Prune deletes opportunities and their lifecycles, first adding them to
+    /// the hourly rollups if `rollup` is set. Opportunities referenced by an
+    /// execution are kept. Returns the number of opportunities deleted.
+    async fn prune(&self, ids: &[String], rollup: bool) -> Result<u64, StorageError>;
+
+    /// GetRollups retrieves all hourly rollups, oldest hour first.
+    async fn get_rollups(&self) -> Result<Vec<OpportunityRollup>, StorageError>;
+
+    /// Vacuum reclaims the space freed by pruning.
+    async fn vacuum(&self) -> Result<(), StorageError>;
+}
+
 /// StorageError represents errors that can occur during storage operations.
 #[derive(Debug, thiserror::Error)]
 pub enum StorageError {
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
-    #[error("Not found: {0}")]
-    NotFound(String),
-
     #[error("Invalid data: {0}")]
     InvalidData(String),
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("Database schema version {found} is newer than supported version {supported}")]
+    SchemaTooNew { found: i64, supported: i64 },
+
+    #[error("Database schema version {found} is older than expected version {expected}; start the bot once to migrate it")]
+    SchemaOutdated { found: i64, expected: i64 },
 }
@@ -0,0 +1,96 @@
+//! Tests for PostgreSQL storage.
+//!
+//! These need `POSTGRES_TEST_URL` to point at a database the tests may create
+//! schemas in, so they are ignored by default. Run them with
+//! `POSTGRES_TEST_URL=postgres://... cargo test -- --ignored postgres`.
+
+use super::*;
+use crate::storage::Storage;
+use crate::storage::conformance::{TestHarness, conformance_suite};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Environment variable holding the test database URL.
+const TEST_URL_VAR: &str = "POSTGRES_TEST_URL";
+
+static SCHEMA_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// PostgresHarness isolates each test in its own schema, dropped on teardown.
+struct PostgresHarness {
+    storage: PostgresStorage,
+    admin: PgPool,
+    schema: String,
+}
+
+impl TestHarness for PostgresHarness {
+    fn storage(&self) -> &dyn Storage {
+        &self.storage
+    }
+
+    async fn teardown(self) {
+        self.storage.close().await.unwrap();
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", self.schema))
+            .execute(&self.admin)
+            .await
+            .unwrap();
+        self.admin.close().await;
+    }
+}
+
+async fn open_harness() -> PostgresHarness {
+    let url = std::env::var(TEST_URL_VAR)
+        .unwrap_or_else(|_| panic!("{} must point at a test database", TEST_URL_VAR));
+
+    let admin = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .unwrap();
+
+    let schema = format!(
+        "test_{}_{}",
+        std::process::id(),
+        SCHEMA_COUNTER.fetch_add(1, Ordering::SeqCst)
+    );
+    sqlx::query(&format!("CREATE SCHEMA {}", schema))
+        .execute(&admin)
+        .await
+        .unwrap();
+
+    // Every pooled connection resolves unqualified tables in the test schema
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let storage = PostgresStorage::new(PostgresStorageConfig {
+        url: format!("{}{}options=-c%20search_path%3D{}", url, separator, schema),
+        max_connections: 2,
+    })
+    .await
+    .unwrap();
+
+    PostgresHarness {
+        storage,
+        admin,
+        schema,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs POSTGRES_TEST_URL"]
+async fn test_migrate_is_idempotent() {
+    let harness = open_harness().await;
+
+    harness.storage.migrate().await.unwrap();
+
+    let version: i64 = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+        .fetch_one(&harness.storage.pool)
+        .await
+        .unwrap();
+    assert_eq!(version, MIGRATIONS.last().unwrap().version);
+
+    harness.teardown().await;
+}
+
+mod conformance {
+    use super::*;
+
+    conformance_suite!(#[ignore = "needs POSTGRES_TEST_URL"] open_harness);
+}
@@ -0,0 +1,425 @@
+//! Opportunity queries: filters, cursor pagination and aggregates.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::{Database, Encode, QueryBuilder, Type};
+
+use crate::domain::{Opportunity, OpportunityType};
+use crate::storage::{RecordFilter, StorageError};
+use crate::storage::columns::{to_millis, to_real};
+
+/// Field opportunities are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    /// Detection time.
+    #[default]
+    DetectedAt,
+    /// Profit percentage.
+    ProfitPercent,
+}
+
+/// Sort direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    Ascending,
+    /// Newest or most profitable first.
+    #[default]
+    Descending,
+}
+
+/// Grouping of opportunity aggregates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Trading pair (or asset cycle).
+    Pair,
+    /// Buy and sell exchange, as "buy->sell".
+    Route,
+    /// Hour of detection (UTC), as "YYYY-MM-DDTHH:00:00Z".
+    Hour,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pair" => Ok(GroupBy::Pair),
+            "route" => Ok(GroupBy::Route),
+            "hour" => Ok(GroupBy::Hour),
+            _ => Err(format!("Unknown grouping: {}", s)),
+        }
+    }
+}
+
+/// OpportunityQuery selects stored opportunities.
+///
+/// Unset fields match everything; the time range is half-open `[from, to)`.
+/// Results are ordered by the sort key with the ID as tie-breaker, so that a
+/// cursor from one page resumes exactly after its last item.
+#[derive(Debug, Clone, Default)]
+pub struct OpportunityQuery {
+    /// Earliest detection time to include.
+    pub from: Option<DateTime<Utc>>,
+    /// Detection time to stop before.
+    pub to: Option<DateTime<Utc>>,
+    /// Trading pair (or asset cycle).
+    pub pair: Option<String>,
+    /// Exchange bought on.
+    pub buy_exchange: Option<String>,
+    /// Exchange sold on.
+    pub sell_exchange: Option<String>,
+    /// Minimum profit percentage, inclusive.
+    pub min_profit_percent: Option<Decimal>,
+    /// Opportunity type.
+    pub opportunity_type: Option<OpportunityType>,
+    /// Sort field.
+    pub sort_key: SortKey,
+    /// Sort direction.
+    pub sort_order: SortOrder,
+    /// Page size; None returns every match.
+    pub limit: Option<u32>,
+    /// Cursor returned with the previous page.
+    pub cursor: Option<String>,
+}
+
+impl OpportunityQuery {
+    /// Creates a query matching every opportunity, newest first.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts detection time to `[from, to)`; an unset bound is open.
+    pub fn with_time_range(mut self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    /// Restricts to one trading pair.
+    pub fn with_pair(mut self, pair: impl Into<String>) -> Self {
+        self.pair = Some(pair.into());
+        self
+    }
+
+    /// Restricts to one buy/sell exchange route.
+    pub fn with_route(
+        mut self,
+        buy_exchange: impl Into<String>,
+        sell_exchange: impl Into<String>,
+    ) -> Self {
+        self.buy_exchange = Some(buy_exchange.into());
+        self.sell_exchange = Some(sell_exchange.into());
+        self
+    }
+
+    /// Restricts to opportunities at or above a profit percentage.
+    pub fn with_min_profit_percent(mut self, min_profit_percent: Decimal) -> Self {
+        self.min_profit_percent = Some(min_profit_percent);
+        self
+    }
+
+    /// Restricts to one opportunity type.
+    pub fn with_type(mut self, opportunity_type: OpportunityType) -> Self {
+        self.opportunity_type = Some(opportunity_type);
+        self
+    }
+
+    /// Sets the sort field and direction.
+    pub fn with_sort(mut self, key: SortKey, order: SortOrder) -> Self {
+        self.sort_key = key;
+        self.sort_order = order;
+        self
+    }
+
+    /// Returns pages of at most `limit` opportunities.
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Resumes after the page that returned `cursor`.
+    pub fn with_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+}
+
+/// One page of query results.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpportunityPage {
+    /// Opportunities in sort order.
+    pub items: Vec<Opportunity>,
+    /// Cursor for the next page; None on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Aggregate of the opportunities in one group.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpportunityStats {
+    /// Group value (pair, route or hour).
+    pub key: String,
+    /// Number of opportunities.
+    pub count: i64,
+    /// Mean profit percentage.
+    pub avg_profit_percent: f64,
+    /// Highest profit percentage.
+    pub max_profit_percent: f64,
+    /// Sum of net profit in quote currency.
+    pub total_net_profit: f64,
+}
+
+/// Position after the last item of a page: the sort value and the item ID.
+#[derive(Debug, Clone, PartialEq)]
+enum Cursor {
+    DetectedAt(i64, String),
+    ProfitPercent(f64, String),
+}
+
+impl Cursor {
+    /// Builds the cursor pointing after an opportunity.
+    fn after(key: SortKey, opportunity: &Opportunity) -> Self {
+        match key {
+            SortKey::DetectedAt => Cursor::DetectedAt(
+                opportunity.detected_at.timestamp_millis(),
+                opportunity.id.clone(),
+            ),
+            SortKey::ProfitPercent => Cursor::ProfitPercent(
+                to_real(opportunity.profit_percent),
+                opportunity.id.clone(),
+            ),
+        }
+    }
+
+    /// Encodes the cursor as an opaque string.
+    fn encode(&self) -> String {
+        match self {
+            Cursor::DetectedAt(millis, id) => format!("t:{}:{}", millis, id),
+            Cursor::ProfitPercent(profit, id) => format!("p:{}:{}", profit, id),
+        }
+    }
+
+    /// Decodes a cursor issued for the given sort key.
+    fn decode(key: SortKey, cursor: &str) -> Result<Self, StorageError> {
+        let invalid = || StorageError::InvalidData(format!("Invalid cursor: {}", cursor));
+
+        let mut parts = cursor.splitn(3, ':');
+        let (kind, value, id) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(kind), Some(value), Some(id)) => (kind, value, id.to_string()),
+            _ => return Err(invalid()),
+        };
+
+        match (key, kind) {
+            (SortKey::DetectedAt, "t") => Ok(Cursor::DetectedAt(
+                value.parse().map_err(|_| invalid())?,
+                id,
+            )),
+            (SortKey::ProfitPercent, "p") => Ok(Cursor::ProfitPercent(
+                value.parse().map_err(|_| invalid())?,
+                id,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Appends the filter conditions of a query.
+pub(super) fn push_conditions<'a, DB>(sql: &mut QueryBuilder<'a, DB>, query: &OpportunityQuery)
+where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    f64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>,
+{
+    if let Some(from) = query.from {
+        sql.push(" AND detected_at >= ").push_bind(to_millis(from));
+    }
+    if let Some(to) = query.to {
+        sql.push(" AND detected_at < ").push_bind(to_millis(to));
+    }
+    if let Some(ref pair) = query.pair {
+        sql.push(" AND pair = ").push_bind(pair.clone());
+    }
+    if let Some(ref exchange) = query.buy_exchange {
+        sql.push(" AND buy_exchange = ").push_bind(exchange.clone());
+    }
+    if let Some(ref exchange) = query.sell_exchange {
+        sql.push(" AND sell_exchange = ").push_bind(exchange.clone());
+    }
+    if let Some(min) = query.min_profit_percent {
+        sql.push(" AND profit_percent_real >= ").push_bind(to_real(min));
+    }
+    if let Some(opportunity_type) = query.opportunity_type {
+        sql.push(" AND type = ").push_bind(opportunity_type.to_string());
+    }
+}
+
+/// Appends the time range and pair conditions of an order, trade or execution filter.
+pub(super) fn push_record_filter<'a, DB>(
+    sql: &mut QueryBuilder<'a, DB>,
+    filter: &RecordFilter,
+    time_column: &str,
+) where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>,
+{
+    if let Some(from) = filter.from {
+        sql.push(format!(" AND {} >= ", time_column))
+            .push_bind(to_millis(from));
+    }
+    if let Some(to) = filter.to {
+        sql.push(format!(" AND {} < ", time_column))
+            .push_bind(to_millis(to));
+    }
+    if let Some(ref pair) = filter.pair {
+        sql.push(" AND pair = ").push_bind(pair.clone());
+    }
+}
+
+/// Appends the cursor condition, ordering and limit of a query.
+///
+/// One row more than the page size is requested to tell whether another page follows.
+pub(super) fn push_page<'a, DB>(
+    sql: &mut QueryBuilder<'a, DB>,
+    query: &OpportunityQuery,
+) -> Result<(), StorageError>
+where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    f64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>,
+{
+    let column = match query.sort_key {
+        SortKey::DetectedAt => "detected_at",
+        SortKey::ProfitPercent => "profit_percent_real",
+    };
+    let (direction, comparison) = match query.sort_order {
+        SortOrder::Ascending => ("ASC", ">"),
+        SortOrder::Descending => ("DESC", "<"),
+    };
+
+    if let Some(ref cursor) = query.cursor {
+        // Keyset pagination: rows strictly after (sort value, id) in sort order
+        sql.push(format!(" AND ({} {} ", column, comparison));
+        match Cursor::decode(query.sort_key, cursor)? {
+            Cursor::DetectedAt(millis, id) => {
+                sql.push_bind(millis)
+                    .push(format!(" OR ({} = ", column))
+                    .push_bind(millis)
+                    .push(format!(" AND id {} ", comparison))
+                    .push_bind(id);
+            }
+            Cursor::ProfitPercent(profit, id) => {
+                sql.push_bind(profit)
+                    .push(format!(" OR ({} = ", column))
+                    .push_bind(profit)
+                    .push(format!(" AND id {} ", comparison))
+                    .push_bind(id);
+            }
+        }
+        sql.push("))");
+    }
+
+    sql.push(format!(
+        " ORDER BY {} {}, id {}",
+        column, direction, direction
+    ));
+
+    if let Some(limit) = query.limit {
+        sql.push(" LIMIT ").push_bind(limit as i64 + 1);
+    }
+
+    Ok(())
+}
+
+/// Appends the condition selecting the given opportunities that no execution references.
+pub(super) fn push_prunable_ids<'a, DB>(
+    sql: &mut QueryBuilder<'a, DB>,
+    id_column: &str,
+    ids: &[String],
+) where
+    DB: Database,
+    String: Encode<'a, DB> + Type<DB>,
+{
+    sql.push(format!(" {} IN (", id_column));
+    let mut separated = sql.separated(", ");
+    for id in ids {
+        separated.push_bind(id.clone());
+    }
+    sql.push(format!(
+        ") AND {} NOT IN (SELECT opportunity_id FROM executions)",
+        id_column
+    ));
+}
+
+/// Adds opportunities to the hourly route rollups; completed by `push_prunable_ids`
+/// and `ROLLUP_CONFLICT`.
+pub(super) const ROLLUP_INSERT: &str = r#"
+    INSERT INTO opportunity_rollups (
+        hour, buy_exchange, sell_exchange, count, sum_profit_percent_real,
+        max_profit_percent_real, total_net_profit_real
+    )
+    SELECT detected_at / 3600000 * 3600000, buy_exchange, sell_exchange, COUNT(*),
+        SUM(profit_percent_real), MAX(profit_percent_real), SUM(net_profit_real)
+    FROM opportunities WHERE"#;
+
+/// Merges new rollup rows into existing ones for the same hour and route.
+pub(super) const ROLLUP_CONFLICT: &str = r#"
+    GROUP BY 1, 2, 3
+    ON CONFLICT (hour, buy_exchange, sell_exchange) DO UPDATE SET
+        count = opportunity_rollups.count + excluded.count,
+        sum_profit_percent_real =
+            opportunity_rollups.sum_profit_percent_real + excluded.sum_profit_percent_real,
+        max_profit_percent_real = CASE
+            WHEN excluded.max_profit_percent_real > opportunity_rollups.max_profit_percent_real
+            THEN excluded.max_profit_percent_real
+            ELSE opportunity_rollups.max_profit_percent_real
+        END,
+        total_net_profit_real =
+            opportunity_rollups.total_net_profit_real + excluded.total_net_profit_real
+"#;
+
+/// Trims the extra row fetched by `push_page` and sets the next cursor.
+pub(super) fn into_page(mut items: Vec<Opportunity>, query: &OpportunityQuery) -> OpportunityPage {
+    let next_cursor = match query.limit {
+        Some(limit) if items.len() > limit as usize => {
+            items.truncate(limit as usize);
+            items
+                .last()
+                .map(|last| Cursor::after(query.sort_key, last).encode())
+        }
+        _ => None,
+    };
+
+    OpportunityPage { items, next_cursor }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor::DetectedAt(1_700_000_000_123, "cross_exchange-1:2".to_string());
+        assert_eq!(
+            Cursor::decode(SortKey::DetectedAt, &cursor.encode()).unwrap(),
+            cursor
+        );
+
+        let cursor = Cursor::ProfitPercent(0.0015, "id".to_string());
+        assert_eq!(
+            Cursor::decode(SortKey::ProfitPercent, &cursor.encode()).unwrap(),
+            cursor
+        );
+    }
+
+    #[test]
+    fn test_cursor_rejects_other_sort_key() {
+        let cursor = Cursor::DetectedAt(1, "id".to_string()).encode();
+        assert!(Cursor::decode(SortKey::ProfitPercent, &cursor).is_err());
+        assert!(Cursor::decode(SortKey::DetectedAt, "garbage").is_err());
+    }
+}
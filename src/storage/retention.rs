@@ -0,0 +1,139 @@
+//! Pruning of old opportunities according to the retention configuration.
+//!
+//! Opportunities are pruned in batches, oldest first. Each batch is written to
+//! the archive file (if configured) before it is deleted, so an interrupted run
+//! loses nothing it has not archived.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use tracing::debug;
+
+use crate::config::{ArchiveConfig, ArchiveFormat, RetentionConfig};
+use crate::storage::{OpportunityWriter, Storage, StorageError};
+
+/// Number of opportunities fetched and deleted per transaction.
+const PRUNE_BATCH: u32 = 500;
+
+type ArchiveFile = OpportunityWriter<GzEncoder<BufWriter<File>>>;
+
+/// Outcome of one retention run.
+#[derive(Debug, Clone, Default)]
+pub struct RetentionReport {
+    /// Detection time before which opportunities were pruned.
+    pub cutoff: Option<DateTime<Utc>>,
+    /// Number of opportunities deleted.
+    pub pruned: u64,
+    /// Archive file written, if any opportunity was archived.
+    pub archive: Option<PathBuf>,
+}
+
+/// Prunes the opportunities that fall outside the retention limits.
+///
+/// Opportunities older than `max_age`, or beyond the `max_rows` newest, are
+/// rolled up and archived as configured, then deleted. Opportunities that an
+/// execution references are always kept.
+pub async fn apply_retention(
+    storage: &dyn Storage,
+    config: &RetentionConfig,
+    now: DateTime<Utc>,
+) -> Result<RetentionReport, StorageError> {
+    let mut report = RetentionReport {
+        cutoff: cutoff(storage, config, now).await?,
+        ..Default::default()
+    };
+    let Some(cutoff) = report.cutoff else {
+        return Ok(report);
+    };
+
+    let mut archive: Option<(PathBuf, ArchiveFile)> = None;
+
+    loop {
+        let batch = storage.prunable(cutoff, PRUNE_BATCH).await?;
+        if batch.is_empty() {
+            break;
+        }
+
+        if let Some(ref archive_cfg) = config.archive {
+            if archive.is_none() {
+                archive = Some(open_archive(archive_cfg, now)?);
+            }
+            if let Some((_, ref mut writer)) = archive {
+                for opp in &batch {
+                    writer.write(opp)?;
+                }
+                writer.flush()?;
+            }
+        }
+
+        let ids: Vec<String> = batch.iter().map(|o| o.id.clone()).collect();
+        let pruned = storage.prune(&ids, config.rollup).await?;
+        report.pruned += pruned;
+
+        debug!(batch = batch.len(), pruned, "Pruned opportunity batch");
+
+        if pruned == 0 || batch.len() < PRUNE_BATCH as usize {
+            break;
+        }
+    }
+
+    if let Some((path, writer)) = archive {
+        writer.into_inner()?.finish()?.flush()?;
+        report.archive = Some(path);
+    }
+
+    Ok(report)
+}
+
+/// Returns the detection time before which opportunities are pruned, or None
+/// if every stored opportunity is within the limits.
+async fn cutoff(
+    storage: &dyn Storage,
+    config: &RetentionConfig,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, StorageError> {
+    let by_age = if config.max_age.is_zero() {
+        None
+    } else {
+        chrono::Duration::from_std(config.max_age)
+            .ok()
+            .and_then(|age| now.checked_sub_signed(age))
+    };
+
+    let by_rows = match config.max_rows {
+        Some(keep) => storage.keep_newest_cutoff(keep).await?,
+        None => None,
+    };
+
+    // The later cutoff is the stricter one
+    Ok(by_age.max(by_rows))
+}
+
+/// Creates the archive file of a run, named after its start time.
+fn open_archive(
+    config: &ArchiveConfig,
+    now: DateTime<Utc>,
+) -> Result<(PathBuf, ArchiveFile), StorageError> {
+    let dir = Path::new(&config.dir);
+    fs::create_dir_all(dir)?;
+
+    let extension = match config.format {
+        ArchiveFormat::Jsonl => "jsonl",
+        ArchiveFormat::Csv => "csv",
+    };
+    let path = dir.join(format!(
+        "opportunities-{}.{}.gz",
+        now.format("%Y%m%dT%H%M%SZ"),
+        extension
+    ));
+
+    let file = File::create_new(&path)?;
+    let encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+    let writer = OpportunityWriter::new(encoder, config.format)?;
+
+    Ok((path, writer))
+}
@@ -1,19 +1,29 @@
-//! SQLite implementation of OpportunityStorage.
-
-use crate::domain::{Opportunity, OpportunityType};
-use crate::storage::{OpportunityStorage, StorageError};
+//! SQLite implementation of OpportunityStorage and RetentionStorage.
+
+use crate::domain::{Opportunity, OpportunityLifecycle};
+use crate::storage::columns::{
+    generate_unique_hash, legs_json, parse_lifecycle_row, parse_opportunity_row,
+    parse_rollup_row, parse_stats_row, parse_time, to_millis, to_real,
+};
+use crate::storage::query::{
+    ROLLUP_CONFLICT, ROLLUP_INSERT, into_page, push_conditions, push_page, push_prunable_ids,
+};
+use crate::storage::{
+    GroupBy, OpportunityPage, OpportunityQuery, OpportunityRollup, OpportunityStats,
+    OpportunityStorage, RetentionStorage, StorageError, migrations,
+};
 use async_trait::async_trait;
-use chrono::{DateTime, Timelike, Utc};
-use rust_decimal::Decimal;
-use sha2::{Digest, Sha256};
+use chrono::{DateTime, Utc};
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
-use sqlx::{Pool, Row, Sqlite};
+use sqlx::{Pool, QueryBuilder, Row, Sqlite};
+use std::io;
+use std::path::Path;
 use std::str::FromStr;
 use tracing::{debug, info};
 
 /// SqliteStorage implements OpportunityStorage using SQLite.
 pub struct SqliteStorage {
-    pool: Pool<Sqlite>,
+    pub(super) pool: Pool<Sqlite>,
 }
 
 /// SqliteStorageConfig holds SQLite storage configuration.
@@ -54,103 +64,55 @@ impl SqliteStorage {
         Ok(storage)
     }
 
-    /// Runs database migrations to create the schema.
-    async fn migrate(&self) -> Result<(), StorageError> {
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS opportunities (
-                id TEXT PRIMARY KEY,
-                unique_hash TEXT NOT NULL UNIQUE,
-                type TEXT NOT NULL,
-                pair TEXT NOT NULL,
-                buy_exchange TEXT NOT NULL,
-                sell_exchange TEXT NOT NULL,
-                buy_price TEXT NOT NULL,
-                sell_price TEXT NOT NULL,
-                quantity TEXT NOT NULL,
-                gross_profit TEXT NOT NULL,
-                net_profit TEXT NOT NULL,
-                profit_percent TEXT NOT NULL,
-                buy_fee TEXT NOT NULL,
-                sell_fee TEXT NOT NULL,
-                detected_at TEXT NOT NULL,
-                expires_at TEXT NOT NULL,
-                created_at TEXT DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+    /// Opens an existing database read-only, without migrating it.
+    pub async fn open_read_only(config: SqliteStorageConfig) -> Result<Self, StorageError> {
+        if !Path::new(&config.path).exists() {
+            return Err(StorageError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("database {} does not exist", config.path),
+            )));
+        }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_opportunities_pair ON opportunities(pair)")
-            .execute(&self.pool)
-            .await?;
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", config.path))?
+            .read_only(true);
 
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_opportunities_detected_at ON opportunities(detected_at)",
-        )
-        .execute(&self.pool)
-        .await?;
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect_with(options)
+            .await?;
 
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_opportunities_exchanges ON opportunities(buy_exchange, sell_exchange)",
-        )
-        .execute(&self.pool)
-        .await?;
+        migrations::check_version(&pool).await?;
 
-        Ok(())
+        debug!(path = %config.path, "SQLite storage opened read-only");
+        Ok(Self { pool })
     }
-}
 
-/// Generates a unique hash for detecting duplicate opportunities.
-///
-/// An opportunity is unique based on: pair, buy_exchange, sell_exchange,
-/// profit_percent (rounded to 2 decimals), and a 5-minute time window.
-/// This prevents duplicate notifications for the same opportunity within 5 minutes.
-fn generate_unique_hash(opp: &Opportunity) -> String {
-    // Round profit percent to 2 decimal places (e.g., 0.7234 -> 0.72)
-    let profit_rounded = (opp.profit_percent * Decimal::from(100))
-        .round_dp(2)
-        .to_string();
-
-    // Round detected_at to 5-minute window (e.g., 16:37 and 16:39 both become 16:35)
-    let minute = opp.detected_at.minute();
-    let window_minutes = (minute / 5) * 5;
-    let time_window = opp
-        .detected_at
-        .with_minute(window_minutes)
-        .unwrap()
-        .with_second(0)
-        .unwrap()
-        .with_nanosecond(0)
-        .unwrap();
-    let time_window_str = time_window.format("%Y-%m-%dT%H:%M").to_string();
-
-    let data = format!(
-        "{}|{}|{}|{}|{}",
-        opp.pair, opp.buy_exchange, opp.sell_exchange, profit_rounded, time_window_str
-    );
-
-    let mut hasher = Sha256::new();
-    hasher.update(data.as_bytes());
-    let hash = hasher.finalize();
-
-    // Use first 16 bytes for shorter hash
-    hex::encode(&hash[..16])
+    /// Brings the database schema up to date.
+    async fn migrate(&self) -> Result<(), StorageError> {
+        migrations::run(&self.pool).await
+    }
 }
 
 #[async_trait]
 impl OpportunityStorage for SqliteStorage {
     async fn save(&self, opp: &Opportunity) -> Result<bool, StorageError> {
         let unique_hash = generate_unique_hash(opp);
+        let legs = legs_json(opp)?;
 
         let result = sqlx::query(
             r#"
             INSERT INTO opportunities (
                 id, unique_hash, type, pair, buy_exchange, sell_exchange,
-                buy_price, sell_price, quantity, gross_profit, net_profit,
-                profit_percent, buy_fee, sell_fee, detected_at, expires_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+                buy_price, buy_price_real, sell_price, sell_price_real,
+                quantity, quantity_real, gross_profit, gross_profit_real,
+                net_profit, net_profit_real,
+                net_profit_after_rebalance, net_profit_after_rebalance_real,
+                profit_percent, profit_percent_real, buy_fee, buy_fee_real,
+                sell_fee, sell_fee_real, detected_at, expires_at, legs
+            ) VALUES (
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27
+            )
             ON CONFLICT(unique_hash) DO NOTHING
             "#,
         )
@@ -161,15 +123,26 @@ impl OpportunityStorage for SqliteStorage {
         .bind(&opp.buy_exchange)
         .bind(&opp.sell_exchange)
         .bind(opp.buy_price.to_string())
+        .bind(to_real(opp.buy_price))
         .bind(opp.sell_price.to_string())
+        .bind(to_real(opp.sell_price))
         .bind(opp.quantity.to_string())
+        .bind(to_real(opp.quantity))
         .bind(opp.gross_profit.to_string())
+        .bind(to_real(opp.gross_profit))
         .bind(opp.net_profit.to_string())
+        .bind(to_real(opp.net_profit))
+        .bind(opp.net_profit_after_rebalance.to_string())
+        .bind(to_real(opp.net_profit_after_rebalance))
         .bind(opp.profit_percent.to_string())
+        .bind(to_real(opp.profit_percent))
         .bind(opp.buy_fee.to_string())
+        .bind(to_real(opp.buy_fee))
         .bind(opp.sell_fee.to_string())
-        .bind(opp.detected_at.to_rfc3339())
-        .bind(opp.expires_at.to_rfc3339())
+        .bind(to_real(opp.sell_fee))
+        .bind(to_millis(opp.detected_at))
+        .bind(to_millis(opp.expires_at))
+        .bind(&legs)
         .execute(&self.pool)
         .await?;
 
@@ -191,8 +164,8 @@ impl OpportunityStorage for SqliteStorage {
         let row = sqlx::query(
             r#"
             SELECT id, type, pair, buy_exchange, sell_exchange, buy_price, sell_price,
-                quantity, gross_profit, net_profit, profit_percent, buy_fee, sell_fee,
-                detected_at, expires_at
+                quantity, gross_profit, net_profit, net_profit_after_rebalance,
+                profit_percent, buy_fee, sell_fee, detected_at, expires_at, legs
             FROM opportunities WHERE id = ?
             "#,
         )
@@ -209,35 +182,119 @@ impl OpportunityStorage for SqliteStorage {
         }
     }
 
-    async fn get_all(&self) -> Result<Vec<Opportunity>, StorageError> {
-        let rows = sqlx::query(
+    async fn query(&self, query: &OpportunityQuery) -> Result<OpportunityPage, StorageError> {
+        let mut sql = QueryBuilder::new(
             r#"
             SELECT id, type, pair, buy_exchange, sell_exchange, buy_price, sell_price,
-                quantity, gross_profit, net_profit, profit_percent, buy_fee, sell_fee,
-                detected_at, expires_at
-            FROM opportunities ORDER BY detected_at DESC
+                quantity, gross_profit, net_profit, net_profit_after_rebalance,
+                profit_percent, buy_fee, sell_fee, detected_at, expires_at, legs
+            FROM opportunities WHERE 1 = 1
+            "#,
+        );
+        push_conditions(&mut sql, query);
+        push_page(&mut sql, query)?;
+
+        let rows = sql.build().fetch_all(&self.pool).await?;
+        let items = rows
+            .iter()
+            .map(parse_opportunity_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(into_page(items, query))
+    }
+
+    async fn count_matching(&self, query: &OpportunityQuery) -> Result<i64, StorageError> {
+        let mut sql = QueryBuilder::new("SELECT COUNT(*) AS count FROM opportunities WHERE 1 = 1");
+        push_conditions(&mut sql, query);
+
+        let row = sql.build().fetch_one(&self.pool).await?;
+        Ok(row.try_get("count")?)
+    }
+
+    async fn aggregate(
+        &self,
+        query: &OpportunityQuery,
+        group_by: GroupBy,
+    ) -> Result<Vec<OpportunityStats>, StorageError> {
+        let key = match group_by {
+            GroupBy::Pair => "pair",
+            GroupBy::Route => "buy_exchange || '->' || sell_exchange",
+            GroupBy::Hour => "strftime('%Y-%m-%dT%H:00:00Z', detected_at / 1000, 'unixepoch')",
+        };
+
+        let mut sql = QueryBuilder::new(format!(
+            r#"
+            SELECT {} AS key, COUNT(*) AS count,
+                AVG(profit_percent_real) AS avg_profit_percent,
+                MAX(profit_percent_real) AS max_profit_percent,
+                SUM(net_profit_real) AS total_net_profit
+            FROM opportunities WHERE 1 = 1
+            "#,
+            key
+        ));
+        push_conditions(&mut sql, query);
+        sql.push(" GROUP BY key ORDER BY key");
+
+        let rows = sql.build().fetch_all(&self.pool).await?;
+        rows.iter().map(parse_stats_row).collect()
+    }
+
+    async fn save_lifecycle(&self, lifecycle: &OpportunityLifecycle) -> Result<(), StorageError> {
+        sqlx::query(
+            r#"
+            INSERT INTO opportunity_lifecycles (
+                opportunity_id, first_seen, last_seen, observations,
+                peak_profit_percent, peak_profit_percent_real,
+                avg_profit_percent, avg_profit_percent_real,
+                last_profit_percent, last_profit_percent_real, closed_at, close_reason
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            ON CONFLICT(opportunity_id) DO UPDATE SET
+                last_seen = excluded.last_seen,
+                observations = excluded.observations,
+                peak_profit_percent = excluded.peak_profit_percent,
+                peak_profit_percent_real = excluded.peak_profit_percent_real,
+                avg_profit_percent = excluded.avg_profit_percent,
+                avg_profit_percent_real = excluded.avg_profit_percent_real,
+                last_profit_percent = excluded.last_profit_percent,
+                last_profit_percent_real = excluded.last_profit_percent_real,
+                closed_at = excluded.closed_at,
+                close_reason = excluded.close_reason
             "#,
         )
-        .fetch_all(&self.pool)
+        .bind(&lifecycle.opportunity_id)
+        .bind(to_millis(lifecycle.first_seen))
+        .bind(to_millis(lifecycle.last_seen))
+        .bind(lifecycle.observations as i64)
+        .bind(lifecycle.peak_profit_percent.to_string())
+        .bind(to_real(lifecycle.peak_profit_percent))
+        .bind(lifecycle.avg_profit_percent.to_string())
+        .bind(to_real(lifecycle.avg_profit_percent))
+        .bind(lifecycle.last_profit_percent.to_string())
+        .bind(to_real(lifecycle.last_profit_percent))
+        .bind(lifecycle.closed_at.map(to_millis))
+        .bind(lifecycle.close_reason.map(|r| r.to_string()))
+        .execute(&self.pool)
         .await?;
 
-        rows.iter().map(parse_opportunity_row).collect()
+        Ok(())
     }
 
-    async fn get_by_pair(&self, pair: &str) -> Result<Vec<Opportunity>, StorageError> {
-        let rows = sqlx::query(
+    async fn get_lifecycle(
+        &self,
+        opportunity_id: &str,
+    ) -> Result<Option<OpportunityLifecycle>, StorageError> {
+        let row = sqlx::query(
             r#"
-            SELECT id, type, pair, buy_exchange, sell_exchange, buy_price, sell_price,
-                quantity, gross_profit, net_profit, profit_percent, buy_fee, sell_fee,
-                detected_at, expires_at
-            FROM opportunities WHERE pair = ? ORDER BY detected_at DESC
+            SELECT opportunity_id, first_seen, last_seen, observations, peak_profit_percent,
+                avg_profit_percent, last_profit_percent, closed_at, close_reason
+            FROM opportunity_lifecycles WHERE opportunity_id = ?
             "#,
         )
-        .bind(pair)
-        .fetch_all(&self.pool)
+        .bind(opportunity_id)
+        .fetch_optional(&self.pool)
         .await?;
 
-        rows.iter().map(parse_opportunity_row).collect()
+        row.as_ref().map(parse_lifecycle_row).transpose()
     }
 
     async fn count(&self) -> Result<i64, StorageError> {
@@ -255,69 +312,88 @@ impl OpportunityStorage for SqliteStorage {
     }
 }
 
-/// Parses an opportunity from a database row.
-fn parse_opportunity_row(row: &sqlx::sqlite::SqliteRow) -> Result<Opportunity, StorageError> {
-    let opp_type_str: String = row.try_get("type")?;
-    let opportunity_type = OpportunityType::from_str(&opp_type_str)
-        .map_err(|e| StorageError::InvalidData(e.to_string()))?;
-
-    let buy_price_str: String = row.try_get("buy_price")?;
-    let buy_price = Decimal::from_str(&buy_price_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid buy_price: {}", e)))?;
-
-    let sell_price_str: String = row.try_get("sell_price")?;
-    let sell_price = Decimal::from_str(&sell_price_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid sell_price: {}", e)))?;
-
-    let quantity_str: String = row.try_get("quantity")?;
-    let quantity = Decimal::from_str(&quantity_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid quantity: {}", e)))?;
-
-    let gross_profit_str: String = row.try_get("gross_profit")?;
-    let gross_profit = Decimal::from_str(&gross_profit_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid gross_profit: {}", e)))?;
-
-    let net_profit_str: String = row.try_get("net_profit")?;
-    let net_profit = Decimal::from_str(&net_profit_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid net_profit: {}", e)))?;
-
-    let profit_percent_str: String = row.try_get("profit_percent")?;
-    let profit_percent = Decimal::from_str(&profit_percent_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid profit_percent: {}", e)))?;
-
-    let buy_fee_str: String = row.try_get("buy_fee")?;
-    let buy_fee = Decimal::from_str(&buy_fee_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid buy_fee: {}", e)))?;
-
-    let sell_fee_str: String = row.try_get("sell_fee")?;
-    let sell_fee = Decimal::from_str(&sell_fee_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid sell_fee: {}", e)))?;
-
-    let detected_at_str: String = row.try_get("detected_at")?;
-    let detected_at = DateTime::parse_from_rfc3339(&detected_at_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid detected_at: {}", e)))?
-        .with_timezone(&Utc);
-
-    let expires_at_str: String = row.try_get("expires_at")?;
-    let expires_at = DateTime::parse_from_rfc3339(&expires_at_str)
-        .map_err(|e| StorageError::InvalidData(format!("Invalid expires_at: {}", e)))?
-        .with_timezone(&Utc);
-
-    Ok(Opportunity {
-        id: row.try_get("id")?,
-        opportunity_type,
-        pair: row.try_get("pair")?,
-        buy_exchange: row.try_get("buy_exchange")?,
-        sell_exchange: row.try_get("sell_exchange")?,
-        buy_price,
-        sell_price,
-        quantity,
-        gross_profit,
-        net_profit,
-        profit_percent,
-        buy_fee,
-        sell_fee,
-        detected_at,
-        expires_at,
-    })
+#[async_trait]
+impl RetentionStorage for SqliteStorage {
+    async fn keep_newest_cutoff(&self, keep: u64) -> Result<Option<DateTime<Utc>>, StorageError> {
+        let row = sqlx::query(
+            "SELECT detected_at FROM opportunities ORDER BY detected_at DESC, id DESC LIMIT 1 OFFSET ?",
+        )
+        .bind(keep.saturating_sub(1) as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(|r| parse_time(r, "detected_at")).transpose()
+    }
+
+    async fn prunable(
+        &self,
+        before: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<Opportunity>, StorageError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, type, pair, buy_exchange, sell_exchange, buy_price, sell_price,
+                quantity, gross_profit, net_profit, net_profit_after_rebalance,
+                profit_percent, buy_fee, sell_fee, detected_at, expires_at, legs
+            FROM opportunities
+            WHERE detected_at < ? AND id NOT IN (SELECT opportunity_id FROM executions)
+            ORDER BY detected_at, id LIMIT ?
+            "#,
+        )
+        .bind(to_millis(before))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(parse_opportunity_row).collect()
+    }
+
+    async fn prune(&self, ids: &[String], rollup: bool) -> Result<u64, StorageError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        if rollup {
+            let mut sql = QueryBuilder::new(ROLLUP_INSERT);
+            push_prunable_ids(&mut sql, "id", ids);
+            sql.push(ROLLUP_CONFLICT);
+            sql.build().execute(&mut *tx).await?;
+        }
+
+        let mut sql = QueryBuilder::new("DELETE FROM opportunity_lifecycles WHERE");
+        push_prunable_ids(&mut sql, "opportunity_id", ids);
+        sql.build().execute(&mut *tx).await?;
+
+        let mut sql = QueryBuilder::new("DELETE FROM opportunities WHERE");
+        push_prunable_ids(&mut sql, "id", ids);
+        let deleted = sql.build().execute(&mut *tx).await?.rows_affected();
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    async fn get_rollups(&self) -> Result<Vec<OpportunityRollup>, StorageError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT hour, buy_exchange, sell_exchange, count, sum_profit_percent_real,
+                max_profit_percent_real, total_net_profit_real
+            FROM opportunity_rollups ORDER BY hour, buy_exchange, sell_exchange
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(parse_rollup_row).collect()
+    }
+
+    async fn vacuum(&self) -> Result<(), StorageError> {
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        Ok(())
+    }
 }
+
+#[cfg(test)]
+#[path = "tests.rs"]
+mod tests;
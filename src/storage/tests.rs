@@ -0,0 +1,216 @@
+//! Tests for SQLite storage.
+
+use super::*;
+use crate::storage::conformance::{TestHarness, conformance_suite, sample_opportunity};
+use crate::storage::Storage;
+use chrono::DateTime;
+use rust_decimal::Decimal;
+use tempfile::TempDir;
+
+async fn open_storage(dir: &TempDir) -> SqliteStorage {
+    let config = SqliteStorageConfig {
+        path: dir.path().join("test.db").to_string_lossy().to_string(),
+        max_connections: 1,
+    };
+    SqliteStorage::new(config).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_migrate_adds_rebalance_column_to_existing_table() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+
+    // Table layout from before rebalancing costs were stored
+    {
+        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .unwrap()
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new().connect_with(options).await.unwrap();
+        sqlx::query(
+            r#"
+            CREATE TABLE opportunities (
+                id TEXT PRIMARY KEY, unique_hash TEXT NOT NULL UNIQUE, type TEXT NOT NULL,
+                pair TEXT NOT NULL, buy_exchange TEXT NOT NULL, sell_exchange TEXT NOT NULL,
+                buy_price TEXT NOT NULL, sell_price TEXT NOT NULL, quantity TEXT NOT NULL,
+                gross_profit TEXT NOT NULL, net_profit TEXT NOT NULL, profit_percent TEXT NOT NULL,
+                buy_fee TEXT NOT NULL, sell_fee TEXT NOT NULL, detected_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL, created_at TEXT DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO opportunities VALUES (
+                'old', 'hash', 'cross_exchange', 'BTC/USDT', 'poloniex', 'gate', '1', '2', '1',
+                '1', '0.9', '0.9', '0', '0', '2024-01-01T00:00:00+00:00',
+                '2024-01-01T00:00:05+00:00', NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+    }
+
+    let storage = open_storage(&dir).await;
+    let old = storage.get_by_id("old").await.unwrap().unwrap();
+    assert_eq!(old.net_profit_after_rebalance, Decimal::new(9, 1));
+    assert!(old.legs.is_empty());
+    assert_eq!(old.detected_at, DateTime::from_timestamp(1_704_067_200, 0).unwrap());
+    assert_eq!(old.expires_at, DateTime::from_timestamp(1_704_067_205, 0).unwrap());
+
+    let row = sqlx::query("SELECT detected_at, net_profit_real FROM opportunities WHERE id = 'old'")
+        .fetch_one(&storage.pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<i64, _>("detected_at"), 1_704_067_200_000);
+    assert_eq!(row.get::<f64, _>("net_profit_real"), 0.9);
+    assert_eq!(
+        migrations::current_version(&storage.pool).await.unwrap(),
+        migrations::latest_version()
+    );
+}
+
+#[tokio::test]
+async fn test_reopen_keeps_schema_version() {
+    let dir = TempDir::new().unwrap();
+
+    let storage = open_storage(&dir).await;
+    storage.save(&sample_opportunity("opp-1")).await.unwrap();
+    storage.close().await.unwrap();
+
+    let storage = open_storage(&dir).await;
+    let rows = sqlx::query("SELECT version FROM schema_version")
+        .fetch_all(&storage.pool)
+        .await
+        .unwrap();
+    assert_eq!(rows.len() as i64, migrations::latest_version());
+    assert_eq!(storage.count().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_refuses_newer_schema() {
+    let dir = TempDir::new().unwrap();
+
+    let storage = open_storage(&dir).await;
+    let newer = migrations::latest_version() + 1;
+    sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, 'future', '')")
+        .bind(newer)
+        .execute(&storage.pool)
+        .await
+        .unwrap();
+    storage.close().await.unwrap();
+
+    let config = SqliteStorageConfig {
+        path: dir.path().join("test.db").to_string_lossy().to_string(),
+        max_connections: 1,
+    };
+    match SqliteStorage::new(config).await {
+        Err(StorageError::SchemaTooNew { found, supported }) => {
+            assert_eq!(found, newer);
+            assert_eq!(supported, migrations::latest_version());
+        }
+        other => panic!("expected SchemaTooNew, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn test_open_read_only_neither_creates_nor_migrates() {
+    let dir = TempDir::new().unwrap();
+    let config = SqliteStorageConfig {
+        path: dir.path().join("test.db").to_string_lossy().to_string(),
+        max_connections: 1,
+    };
+
+    assert!(matches!(
+        SqliteStorage::open_read_only(config.clone()).await,
+        Err(StorageError::Io(_))
+    ));
+    assert!(!dir.path().join("test.db").exists());
+
+    let storage = open_storage(&dir).await;
+    storage.save(&sample_opportunity("opp-1")).await.unwrap();
+    storage.close().await.unwrap();
+
+    let reader = SqliteStorage::open_read_only(config.clone()).await.unwrap();
+    assert_eq!(reader.count().await.unwrap(), 1);
+    assert!(reader.save(&sample_opportunity("opp-2")).await.is_err());
+    reader.close().await.unwrap();
+
+    // A database from before the latest migration is reported, not migrated
+    let storage = open_storage(&dir).await;
+    sqlx::query("DELETE FROM schema_version WHERE version = ?")
+        .bind(migrations::latest_version())
+        .execute(&storage.pool)
+        .await
+        .unwrap();
+    storage.close().await.unwrap();
+
+    match SqliteStorage::open_read_only(config).await {
+        Err(StorageError::SchemaOutdated { found, expected }) => {
+            assert_eq!(found, migrations::latest_version() - 1);
+            assert_eq!(expected, migrations::latest_version());
+        }
+        other => panic!("expected SchemaOutdated, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn test_numeric_columns_support_sql_analytics() {
+    let dir = TempDir::new().unwrap();
+    let storage = open_storage(&dir).await;
+
+    let base = sample_opportunity("opp-1");
+    for (i, net_profit) in [Decimal::new(150, 2), Decimal::new(250, 2), Decimal::new(400, 2)]
+        .into_iter()
+        .enumerate()
+    {
+        let mut opp = sample_opportunity(&format!("opp-{}", i));
+        opp.net_profit = net_profit;
+        opp.profit_percent = Decimal::new(i as i64 + 1, 3);
+        opp.detected_at = base.detected_at + chrono::Duration::minutes(10 * i as i64);
+        assert!(storage.save(&opp).await.unwrap());
+    }
+
+    let row = sqlx::query(
+        "SELECT SUM(net_profit_real) AS total, COUNT(*) AS n FROM opportunities WHERE detected_at >= ?",
+    )
+    .bind((base.detected_at + chrono::Duration::minutes(5)).timestamp_millis())
+    .fetch_one(&storage.pool)
+    .await
+    .unwrap();
+    assert_eq!(row.get::<i64, _>("n"), 2);
+    assert!((row.get::<f64, _>("total") - 6.5).abs() < 1e-9);
+}
+
+/// SqliteHarness keeps the temporary directory alive while its storage is used.
+struct SqliteHarness {
+    storage: SqliteStorage,
+    _dir: TempDir,
+}
+
+impl TestHarness for SqliteHarness {
+    fn storage(&self) -> &dyn Storage {
+        &self.storage
+    }
+
+    async fn teardown(self) {
+        self.storage.close().await.unwrap();
+    }
+}
+
+async fn open_harness() -> SqliteHarness {
+    let dir = TempDir::new().unwrap();
+    let storage = open_storage(&dir).await;
+    SqliteHarness { storage, _dir: dir }
+}
+
+mod conformance {
+    use super::*;
+
+    conformance_suite!(open_harness);
+}
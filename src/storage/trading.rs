@@ -0,0 +1,236 @@
+//! SQLite persistence of orders, trades and executions.
+
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{QueryBuilder, Row};
+
+use crate::domain::{Execution, Order, Trade};
+use crate::storage::columns::{
+    parse_execution_leg_row, parse_execution_row, parse_order_row, parse_trade_row, to_millis,
+    to_real,
+};
+use crate::storage::query::push_record_filter;
+use crate::storage::{
+    ExecutionStorage, OrderStorage, RecordFilter, SqliteStorage, StorageError, TradeStorage,
+};
+
+#[async_trait]
+impl OrderStorage for SqliteStorage {
+    async fn save_order(&self, order: &Order) -> Result<(), StorageError> {
+        sqlx::query(
+            r#"
+            INSERT INTO orders (
+                exchange, id, pair, side, type, price, price_real, quantity, quantity_real,
+                status, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            ON CONFLICT(exchange, id) DO UPDATE SET
+                price = excluded.price,
+                price_real = excluded.price_real,
+                quantity = excluded.quantity,
+                quantity_real = excluded.quantity_real,
+                status = excluded.status,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&order.exchange)
+        .bind(&order.id)
+        .bind(&order.pair)
+        .bind(order.side.to_string())
+        .bind(order.order_type.to_string())
+        .bind(order.price.to_string())
+        .bind(to_real(order.price))
+        .bind(order.quantity.to_string())
+        .bind(to_real(order.quantity))
+        .bind(order.status.to_string())
+        .bind(system_millis(order.created_at))
+        .bind(system_millis(order.updated_at))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_order(&self, exchange: &str, id: &str) -> Result<Option<Order>, StorageError> {
+        let row = sqlx::query("SELECT * FROM orders WHERE exchange = ? AND id = ?")
+            .bind(exchange)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(parse_order_row).transpose()
+    }
+
+    async fn get_orders(&self, filter: &RecordFilter) -> Result<Vec<Order>, StorageError> {
+        let mut query = QueryBuilder::new("SELECT * FROM orders WHERE 1 = 1");
+        push_record_filter(&mut query, filter, "created_at");
+        if let Some(ref exchange) = filter.exchange {
+            query.push(" AND exchange = ").push_bind(exchange.clone());
+        }
+        query.push(" ORDER BY created_at");
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        rows.iter().map(parse_order_row).collect()
+    }
+}
+
+#[async_trait]
+impl TradeStorage for SqliteStorage {
+    async fn save_trade(&self, trade: &Trade) -> Result<bool, StorageError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO trades (
+                exchange, id, order_id, pair, side, price, price_real, quantity, quantity_real,
+                fee, fee_real, fee_currency, timestamp
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            ON CONFLICT(exchange, id) DO NOTHING
+            "#,
+        )
+        .bind(&trade.exchange)
+        .bind(&trade.id)
+        .bind(&trade.order_id)
+        .bind(&trade.pair)
+        .bind(trade.side.to_string())
+        .bind(trade.price.to_string())
+        .bind(to_real(trade.price))
+        .bind(trade.quantity.to_string())
+        .bind(to_real(trade.quantity))
+        .bind(trade.fee.to_string())
+        .bind(to_real(trade.fee))
+        .bind(&trade.fee_currency)
+        .bind(system_millis(trade.timestamp))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_trades(&self, filter: &RecordFilter) -> Result<Vec<Trade>, StorageError> {
+        let mut query = QueryBuilder::new("SELECT * FROM trades WHERE 1 = 1");
+        push_record_filter(&mut query, filter, "timestamp");
+        if let Some(ref exchange) = filter.exchange {
+            query.push(" AND exchange = ").push_bind(exchange.clone());
+        }
+        query.push(" ORDER BY timestamp");
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        rows.iter().map(parse_trade_row).collect()
+    }
+}
+
+#[async_trait]
+impl ExecutionStorage for SqliteStorage {
+    async fn save_execution(&self, execution: &Execution) -> Result<(), StorageError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO executions (
+                id, opportunity_id, pair, status, realized_pnl, realized_pnl_real, fees, fees_real,
+                started_at, finished_at, error
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            ON CONFLICT(id) DO UPDATE SET
+                status = excluded.status,
+                realized_pnl = excluded.realized_pnl,
+                realized_pnl_real = excluded.realized_pnl_real,
+                fees = excluded.fees,
+                fees_real = excluded.fees_real,
+                finished_at = excluded.finished_at,
+                error = excluded.error
+            "#,
+        )
+        .bind(&execution.id)
+        .bind(&execution.opportunity_id)
+        .bind(&execution.pair)
+        .bind(execution.status.to_string())
+        .bind(execution.realized_pnl.to_string())
+        .bind(to_real(execution.realized_pnl))
+        .bind(execution.fees.to_string())
+        .bind(to_real(execution.fees))
+        .bind(to_millis(execution.started_at))
+        .bind(execution.finished_at.map(to_millis))
+        .bind(&execution.error)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM execution_legs WHERE execution_id = ?")
+            .bind(&execution.id)
+            .execute(&mut *tx)
+            .await?;
+
+        for (index, leg) in execution.legs.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO execution_legs (execution_id, leg_index, exchange, order_id) VALUES (?, ?, ?, ?)",
+            )
+            .bind(&execution.id)
+            .bind(index as i64)
+            .bind(&leg.exchange)
+            .bind(&leg.order_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_execution(&self, id: &str) -> Result<Option<Execution>, StorageError> {
+        let row = sqlx::query("SELECT * FROM executions WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.load_execution(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_executions(&self, filter: &RecordFilter) -> Result<Vec<Execution>, StorageError> {
+        let mut query = QueryBuilder::new("SELECT * FROM executions WHERE 1 = 1");
+        push_record_filter(&mut query, filter, "started_at");
+        if let Some(ref exchange) = filter.exchange {
+            query
+                .push(
+                    " AND EXISTS (SELECT 1 FROM execution_legs l \
+                     WHERE l.execution_id = executions.id AND l.exchange = ",
+                )
+                .push_bind(exchange.clone())
+                .push(")");
+        }
+        query.push(" ORDER BY started_at");
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        let mut executions = Vec::with_capacity(rows.len());
+        for row in &rows {
+            executions.push(self.load_execution(row).await?);
+        }
+        Ok(executions)
+    }
+}
+
+impl SqliteStorage {
+    /// Parses an execution row and loads its legs.
+    async fn load_execution(&self, row: &SqliteRow) -> Result<Execution, StorageError> {
+        let id: String = row.try_get("id")?;
+
+        let legs = sqlx::query(
+            "SELECT exchange, order_id FROM execution_legs WHERE execution_id = ? ORDER BY leg_index",
+        )
+        .bind(&id)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(parse_execution_leg_row)
+        .collect::<Result<Vec<_>, _>>()?;
+
+        parse_execution_row(row, legs)
+    }
+}
+
+/// Converts a system timestamp to its column value.
+fn system_millis(time: SystemTime) -> i64 {
+    to_millis(DateTime::<Utc>::from(time))
+}